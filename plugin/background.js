@@ -22,28 +22,36 @@ chrome.contextMenus.onClicked.addListener((info, tab) => {
 chrome.runtime.onMessage.addListener((request, sender, sendResponse) => {
   if (request.type === "SAVE_TEXT") {
     // 从storage获取服务器地址
-    chrome.storage.local.get(['serverUrl'], function(result) {
+    chrome.storage.local.get(['serverUrl', 'apiKey'], function(result) {
       const serverUrl = result.serverUrl || 'http://localhost:3000';
+      const apiKey = result.apiKey || '';
       
       // 直接使用request.data中的数据，因为content.js已经正确格式化了数据
       const data = {
         category_id: parseInt(request.data.category_id) || null, // 使用category_id而不是category
         text: request.data.text,
         url: request.data.url,
-        tags: request.data.tags
+        tags: request.data.tags,
+        title: request.data.title,
+        context_before: request.data.context_before,
+        context_after: request.data.context_after,
+        html: request.data.html,
+        lang: request.data.lang
       };
       
       // 发送数据到服务器
       fetch(`${serverUrl}/api/favorites`, {
         method: 'POST',
         headers: {
-          'Content-Type': 'application/json'
+          'Content-Type': 'application/json',
+          'Authorization': `Bearer ${apiKey}`
         },
         body: JSON.stringify(data)
       })
-      .then(response => response.json())
-      .then(data => {
-        sendResponse({ success: true, data });
+      .then(response => response.json().then(data => ({ status: response.status, data })))
+      .then(({ status, data }) => {
+        // 409 表示已存在相同的收藏，返回的是已有记录
+        sendResponse({ success: true, duplicate: status === 409, data });
       })
       .catch(error => {
         sendResponse({ success: false, error: error.message });
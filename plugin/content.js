@@ -1,3 +1,57 @@
+// 前后文最多保留的字符数
+const CONTEXT_MAX_CHARS = 200;
+
+// 获取选区所在的块级元素
+function getBlockAncestor(node) {
+  let element = node.nodeType === Node.ELEMENT_NODE ? node : node.parentElement;
+  while (element && element !== document.body) {
+    const display = window.getComputedStyle(element).display;
+    if (display !== 'inline' && display !== 'inline-block') {
+      return element;
+    }
+    element = element.parentElement;
+  }
+  return document.body;
+}
+
+// 采集选区的前后文、HTML、页面标题和语言
+function captureSelectionContext() {
+  const context = {
+    title: document.title || null,
+    lang: document.documentElement.lang || null,
+    context_before: null,
+    context_after: null,
+    html: null
+  };
+
+  const selection = window.getSelection();
+  if (!selection || selection.rangeCount === 0) {
+    return context;
+  }
+  const range = selection.getRangeAt(0);
+
+  // 选区的 HTML，由服务端清理
+  const container = document.createElement('div');
+  container.appendChild(range.cloneContents());
+  context.html = container.innerHTML || null;
+
+  // 在所在段落内截取选区前后的文本
+  const block = getBlockAncestor(range.commonAncestorContainer);
+  const beforeRange = document.createRange();
+  beforeRange.setStart(block, 0);
+  beforeRange.setEnd(range.startContainer, range.startOffset);
+  const afterRange = document.createRange();
+  afterRange.setStart(range.endContainer, range.endOffset);
+  afterRange.setEnd(block, block.childNodes.length);
+
+  const before = beforeRange.toString().replace(/\s+/g, ' ').trim();
+  const after = afterRange.toString().replace(/\s+/g, ' ').trim();
+  context.context_before = before.slice(-CONTEXT_MAX_CHARS) || null;
+  context.context_after = after.slice(0, CONTEXT_MAX_CHARS) || null;
+
+  return context;
+}
+
 // 创建对话框HTML
 function createDialog(text, url) {
   const dialog = document.createElement('div');
@@ -196,6 +250,8 @@ document.head.appendChild(style);
 // 监听来自background的消息
 chrome.runtime.onMessage.addListener((request, sender, sendResponse) => {
   if (request.type === "SHOW_DIALOG") {
+    // 在对话框获取焦点前采集选区信息
+    const selectionContext = captureSelectionContext();
     const dialog = createDialog(request.text, request.url);
     document.body.appendChild(dialog);
     
@@ -221,7 +277,8 @@ chrome.runtime.onMessage.addListener((request, sender, sendResponse) => {
         text: dialog.querySelector('#selected-text').value,
         url: dialog.querySelector('#page-url').value,
         tags: Array.from(dialog.querySelector('#tag-select').selectedOptions)
-          .map(option => option.textContent) // 使用标签名称而不是ID
+          .map(option => option.textContent), // 使用标签名称而不是ID
+        ...selectionContext
       };
       
       try {
@@ -232,7 +289,7 @@ chrome.runtime.onMessage.addListener((request, sender, sendResponse) => {
         });
         
         if (response.success) {
-          messageDiv.textContent = '保存成功！';
+          messageDiv.textContent = response.duplicate ? '已收藏过相同内容' : '保存成功！';
           messageDiv.className = 'save-message success';
           
           // 1秒后关闭对话框
@@ -269,11 +326,12 @@ chrome.runtime.onMessage.addListener((request, sender, sendResponse) => {
 // 加载分类和标签数据
 async function loadCategoriesAndTags() {
   try {
-    const storage = await chrome.storage.local.get(['serverUrl']);
+    const storage = await chrome.storage.local.get(['serverUrl', 'apiKey']);
     const serverUrl = storage.serverUrl || 'http://localhost:3000';
+    const headers = { 'Authorization': `Bearer ${storage.apiKey || ''}` };
     
     // 加载分类
-    const categoriesResponse = await fetch(`${serverUrl}/api/categories`);
+    const categoriesResponse = await fetch(`${serverUrl}/api/categories`, { headers });
     const categories = await categoriesResponse.json();
     const categorySelect = document.querySelector('#category-select');
     categorySelect.innerHTML = ''; // 清空现有选项
@@ -292,7 +350,7 @@ async function loadCategoriesAndTags() {
     });
     
     // 加载标签
-    const tagsResponse = await fetch(`${serverUrl}/api/tags`);
+    const tagsResponse = await fetch(`${serverUrl}/api/tags`, { headers });
     const tags = await tagsResponse.json();
     const tagSelect = document.querySelector('#tag-select');
     tagSelect.innerHTML = ''; // 清空现有选项
@@ -141,12 +141,12 @@ class CategoryManager {
     }
 
     async deleteCategory(id) {
-        if (!confirm('确定要删除这个分类吗？')) {
+        if (!confirm('确定要删除这个分类吗？分类下的收藏将变为未分类，子分类移到上一级。')) {
             return;
         }
 
         try {
-            await window.utils.fetchApi(`/api/categories/${id}`, {
+            await window.utils.fetchApi(`/api/categories/${id}?strategy=uncategorize`, {
                 method: 'DELETE'
             });
 
@@ -27,6 +27,23 @@ document.addEventListener('DOMContentLoaded', async function() {
         window.utils.showMessage(messageDiv, '页面初始化失败，请刷新重试', 'error');
     }
 
+    // 在其他页面、插件弹窗或设备上修改数据后自动刷新，短时间内的多个事件合并为一次刷新
+    let refreshTimeout;
+    let filtersChanged = false;
+    window.utils.subscribeEvents((name) => {
+        if (!name.startsWith('favorite.')) {
+            filtersChanged = true; // 标签、分类变化或需要全部重新加载
+        }
+        clearTimeout(refreshTimeout);
+        refreshTimeout = setTimeout(async () => {
+            if (filtersChanged) {
+                filtersChanged = false;
+                await loadFilters();
+            }
+            await loadFavorites();
+        }, 300);
+    });
+
     // 搜索输入防抖
     searchInput.addEventListener('input', function() {
         clearTimeout(searchTimeout);
@@ -98,7 +115,7 @@ document.addEventListener('DOMContentLoaded', async function() {
             tr.innerHTML = `
                 <td class="content-cell">${favorite.text}</td>
                 <td class="url-cell">
-                    <a href="${favorite.url}" target="_blank">${favorite.url}</a>
+                    <a href="${favorite.deep_link || favorite.url}" target="_blank">${favorite.url}</a>
                 </td>
                 <td class="time-cell">${createdAt}</td>
                 <td>
@@ -330,6 +347,10 @@ document.addEventListener('DOMContentLoaded', async function() {
             } else {
                 console.error('标签数据格式错误:', tags);
             }
+
+            // 重新加载后保留当前的筛选条件
+            categoryFilter.value = currentFilters.category;
+            tagFilter.value = currentFilters.tag;
         } catch (error) {
             console.error('加载筛选选项失败:', error);
             window.utils.showMessage(messageDiv, '加载筛选选项失败，请刷新页面重试', 'error');
@@ -1,14 +1,18 @@
 document.addEventListener('DOMContentLoaded', function() {
     const serverUrlInput = document.getElementById('serverUrl');
+    const apiKeyInput = document.getElementById('apiKey');
     const saveBtn = document.getElementById('saveBtn');
     const testBtn = document.getElementById('testBtn');
     const messageDiv = document.getElementById('message');
 
-    // 加载已保存的服务器地址
-    chrome.storage.local.get(['serverUrl'], function(result) {
+    // 加载已保存的服务器地址和 API Key
+    chrome.storage.local.get(['serverUrl', 'apiKey'], function(result) {
         if (result.serverUrl) {
             serverUrlInput.value = result.serverUrl;
         }
+        if (result.apiKey) {
+            apiKeyInput.value = result.apiKey;
+        }
     });
 
     // 保存设置
@@ -21,7 +25,8 @@ document.addEventListener('DOMContentLoaded', function() {
         }
 
         chrome.storage.local.set({
-            serverUrl: serverUrl
+            serverUrl: serverUrl,
+            apiKey: apiKeyInput.value.trim()
         }, function() {
             window.utils.showMessage(messageDiv, '设置已保存', 'success');
         });
@@ -9,6 +9,15 @@ window.utils = {
         });
     },
 
+    // API Key 获取
+    async getApiKey() {
+        return new Promise((resolve) => {
+            chrome.storage.local.get(['apiKey'], function(result) {
+                resolve(result.apiKey || '');
+            });
+        });
+    },
+
     // 统一的消息显示
     showMessage(messageDiv, text, type) {
         messageDiv.textContent = text;
@@ -28,13 +37,17 @@ window.utils = {
     async fetchApi(url, options = {}) {
         try {
             const serverUrl = await this.getServerUrl();
+            const apiKey = await this.getApiKey();
             console.log('请求URL:', `${serverUrl}${url}`);
             console.log('请求选项:', options);
 
             const defaultOptions = {
                 method: 'GET',
                 headers: {
-                    'Content-Type': 'application/json'
+                    'Content-Type': 'application/json',
+                    'Authorization': `Bearer ${apiKey}`,
+                    // 服务器按 UTC 保存时间，按浏览器所在时区返回
+                    'X-Timezone': Intl.DateTimeFormat().resolvedOptions().timeZone
                 }
             };
             
@@ -58,6 +71,21 @@ window.utils = {
             const responseText = await response.text();
             console.log('原始响应数据:', responseText);
 
+            // 请求失败时使用服务器返回的错误信息，校验错误附带各字段的提示
+            if (!response.ok) {
+                let message = `请求失败（${response.status}）`;
+                try {
+                    const body = JSON.parse(responseText);
+                    message = body.error || message;
+                    if (body.details) {
+                        message += '：' + Object.values(body.details).join('；');
+                    }
+                } catch (e) {
+                    message = responseText || message;
+                }
+                throw new Error(message);
+            }
+
             // 如果响应是空的，直接返回 null
             if (!responseText) {
                 return null;
@@ -77,5 +105,50 @@ window.utils = {
             console.error('请求失败:', error);
             throw error;
         }
+    },
+
+    // 订阅服务器推送的数据变更事件，回调参数为事件名称（如 favorite.created、reset）和数据
+    // EventSource 无法携带 Authorization 请求头，先用 API Key 换取短期凭证再通过查询参数订阅
+    async subscribeEvents(onEvent) {
+        const serverUrl = await this.getServerUrl();
+        const eventNames = ['reset'];
+        ['favorite', 'tag', 'category'].forEach(topic => {
+            ['created', 'updated', 'deleted'].forEach(action => eventNames.push(`${topic}.${action}`));
+        });
+        let lastEventId = '';
+
+        const handle = (event) => {
+            if (event.lastEventId) {
+                lastEventId = event.lastEventId;
+            }
+            onEvent(event.type, event.data ? JSON.parse(event.data) : {});
+        };
+
+        const connect = async () => {
+            let source;
+            try {
+                const { token } = await this.fetchApi('/api/events/token', { method: 'POST' });
+                const params = new URLSearchParams({ token });
+                // 重新创建连接时由查询参数带回最后收到的事件ID，补发期间错过的事件
+                if (lastEventId) {
+                    params.set('last_event_id', lastEventId);
+                }
+                source = new EventSource(`${serverUrl}/api/events?${params}`);
+            } catch (error) {
+                console.error('订阅事件失败:', error);
+                setTimeout(connect, 10000);
+                return;
+            }
+
+            eventNames.forEach(name => source.addEventListener(name, handle));
+            // 凭证过期后浏览器不再自动重连，重新获取凭证
+            source.onerror = () => {
+                if (source.readyState === EventSource.CLOSED) {
+                    setTimeout(connect, 3000);
+                }
+            };
+        };
+
+        await connect();
     }
 }; 
\ No newline at end of file
@@ -1,35 +1,128 @@
-use utoipa::OpenApi;
-use crate::handlers::{category, favorite, tag};
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+use crate::handlers::{admin, batch, category, domain, event, export, favorite, history, import, link, note, page, search, snapshot, tag, trash};
 
 #[derive(OpenApi)]
 #[openapi(
     paths(
         category::list_categories,
         category::create_category,
+        category::get_category_tree,
+        category::move_category,
         category::get_category,
         category::update_category,
         category::delete_category,
         favorite::list_favorites,
         favorite::create_favorite,
+        favorite::merge_favorites,
+        batch::batch_favorites,
+        trash::list_trash,
+        trash::restore_favorite,
+        link::list_broken_links,
+        domain::list_domains,
+        page::list_pages,
+        page::list_page_favorites,
+        event::issue_event_token,
+        event::stream_events,
+        favorite::get_favorite,
         favorite::update_favorite,
+        favorite::patch_favorite,
         favorite::delete_favorite,
+        history::favorite_history,
+        history::revert_favorite,
+        snapshot::get_snapshot,
+        note::list_notes,
+        note::create_note,
+        note::update_note,
+        note::delete_note,
         tag::list_tags,
-        tag::get_favorites_by_tag
+        tag::get_favorites_by_tag,
+        search::search_favorites,
+        export::export_favorites,
+        import::import_favorites,
+        admin::list_keys,
+        admin::issue_key,
+        admin::revoke_key
     ),
     components(
         schemas(
             category::Category,
+            category::CreateCategory,
+            category::MoveCategory,
+            category::CategoryNode,
+            category::DeleteStrategy,
+            category::DeleteCategoryResult,
             favorite::Favorite,
             favorite::FavoriteResponse,
+            favorite::FavoriteSort,
+            favorite::SortOrder,
             favorite::CreateFavorite,
             favorite::UpdateFavorite,
-            tag::Tag
+            favorite::PatchFavorite,
+            favorite::MergeFavorites,
+            batch::BatchAction,
+            batch::BatchRequest,
+            batch::BatchStatus,
+            batch::BatchItemResult,
+            batch::BatchResponse,
+            trash::TrashResponse,
+            link::LinkState,
+            link::BrokenLink,
+            link::BrokenLinkResponse,
+            domain::DomainStats,
+            domain::TagCount,
+            page::Page,
+            page::PageListResponse,
+            page::PageFavorites,
+            history::HistoryEntry,
+            snapshot::Snapshot,
+            snapshot::SnapshotFormat,
+            note::Note,
+            note::SaveNote,
+            tag::Tag,
+            search::SearchHit,
+            search::SearchResponse,
+            event::EventToken,
+            export::ExportFormat,
+            export::ExportItem,
+            import::ImportFormat,
+            import::ImportStatus,
+            import::ImportRowResult,
+            import::ImportResponse,
+            admin::ApiKeyInfo,
+            admin::IssueKey,
+            admin::IssuedKey
         )
     ),
     tags(
         (name = "categories", description = "Category management endpoints"),
         (name = "favorites", description = "Favorite management endpoints"),
-        (name = "tags", description = "Tag management endpoints")
-    )
+        (name = "notes", description = "Favorite note endpoints"),
+        (name = "trash", description = "Trash and restore endpoints"),
+        (name = "links", description = "Dead link checking endpoints"),
+        (name = "domains", description = "Per-domain statistics endpoints"),
+        (name = "pages", description = "Source page grouping endpoints"),
+        (name = "tags", description = "Tag management endpoints"),
+        (name = "search", description = "Full-text search endpoints"),
+        (name = "events", description = "Server-Sent Events change stream"),
+        (name = "export", description = "Data import and export endpoints"),
+        (name = "admin", description = "API key management endpoints")
+    ),
+    modifiers(&SecurityAddon),
+    security(("api_key" = []))
 )]
-pub struct ApiDoc; 
\ No newline at end of file
+pub struct ApiDoc;
+
+/// 注册 Bearer API Key 认证方式
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                "api_key",
+                SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+            );
+        }
+    }
+} 
\ No newline at end of file
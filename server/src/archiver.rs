@@ -0,0 +1,368 @@
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use html5ever::tokenizer::states::RawKind;
+use html5ever::tokenizer::{
+    BufferQueue, TagKind, Token, TokenSink, TokenSinkResult, Tokenizer, TokenizerOpts,
+};
+use sha2::{Digest, Sha256};
+use sqlx::sqlite::SqlitePool;
+use std::cell::RefCell;
+use std::io::{Read, Write};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use crate::config::ArchiverConfig;
+use crate::outbound;
+use crate::timezone;
+
+/// 每轮最多抓取的收藏数量
+const BATCH_SIZE: i64 = 20;
+
+/// 不计入正文的元素，其中的内容整体跳过
+/// 只列出结束标签不可省略的元素，否则跳过的范围无法确定
+const SKIPPED_TAGS: &[&str] = &[
+    "script", "style", "noscript", "template", "title", "svg", "nav", "header", "footer", "aside",
+    "form", "iframe",
+];
+
+/// 块级元素，前后换行以保留段落结构
+const BLOCK_TAGS: &[&str] = &[
+    "address", "article", "blockquote", "br", "dd", "div", "dl", "dt", "figcaption", "figure",
+    "h1", "h2", "h3", "h4", "h5", "h6", "hr", "li", "main", "ol", "p", "pre", "section", "table",
+    "td", "th", "tr", "ul",
+];
+
+/// 唤醒抓取任务的句柄，由 main 创建后交给抓取任务和路由，克隆后指向同一个任务
+/// 未启用快照时没有任务等待，唤醒无副作用
+#[derive(Debug, Clone, Default)]
+pub struct ArchiverHandle(Arc<Notify>);
+
+impl ArchiverHandle {
+    /// 通知抓取任务有新的收藏需要保存快照
+    pub fn wake(&self) {
+        self.0.notify_one();
+    }
+}
+
+/// 抓取到的原网页
+struct Fetched {
+    final_url: String,
+    status: u16,
+    content_type: Option<String>,
+    body: Vec<u8>,
+}
+
+/// 请求网页并读取响应体，超过大小上限时中止
+async fn fetch(client: &outbound::Client, url: &str, max_bytes: usize) -> Result<Fetched, String> {
+    let mut response = client.send(reqwest::Method::GET, url).await?;
+
+    let too_large = || format!("响应超过 {} 字节上限", max_bytes);
+    if response.content_length().is_some_and(|len| len > max_bytes as u64) {
+        return Err(too_large());
+    }
+
+    let final_url = response.url().to_string();
+    let status = response.status().as_u16();
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|err| outbound::describe(&err))? {
+        if body.len() + chunk.len() > max_bytes {
+            return Err(too_large());
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    Ok(Fetched { final_url, status, content_type, body })
+}
+
+/// 收集正文文本的分词器回调
+#[derive(Default)]
+struct TextSink {
+    text: RefCell<String>,
+    skip_depth: RefCell<usize>,
+}
+
+impl TextSink {
+    fn push_break(&self) {
+        let mut text = self.text.borrow_mut();
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+    }
+}
+
+impl TokenSink for TextSink {
+    type Handle = ();
+
+    fn process_token(&self, token: Token, _line_number: u64) -> TokenSinkResult<()> {
+        match token {
+            Token::TagToken(tag) => {
+                let name = tag.name.as_ref();
+                if SKIPPED_TAGS.contains(&name) && !tag.self_closing {
+                    let mut depth = self.skip_depth.borrow_mut();
+                    match tag.kind {
+                        TagKind::StartTag => *depth += 1,
+                        TagKind::EndTag => *depth = depth.saturating_sub(1),
+                    }
+                } else if BLOCK_TAGS.contains(&name) {
+                    self.push_break();
+                }
+
+                // 脚本和样式按原始文本读取，避免其中的 `<` 被当作标签
+                if tag.kind == TagKind::StartTag {
+                    match name {
+                        "script" => return TokenSinkResult::RawData(RawKind::ScriptData),
+                        "style" | "noscript" | "iframe" => return TokenSinkResult::RawData(RawKind::Rawtext),
+                        "title" | "textarea" => return TokenSinkResult::RawData(RawKind::Rcdata),
+                        _ => {}
+                    }
+                }
+            }
+            Token::CharacterTokens(chars) if *self.skip_depth.borrow() == 0 => {
+                let mut text = self.text.borrow_mut();
+                for word in chars.split_whitespace() {
+                    if !text.is_empty() && !text.ends_with('\n') {
+                        text.push(' ');
+                    }
+                    text.push_str(word);
+                }
+            }
+            _ => {}
+        }
+        TokenSinkResult::Continue
+    }
+}
+
+/// 从 HTML 中提取可读的正文，去掉脚本、样式和导航等页面框架
+pub fn extract_text(html: &str) -> String {
+    let input = BufferQueue::default();
+    input.push_back(html.into());
+
+    let tokenizer = Tokenizer::new(TextSink::default(), TokenizerOpts::default());
+    let _ = tokenizer.feed(&input);
+    tokenizer.end();
+
+    let text = tokenizer.sink.text.take();
+    text.trim().to_string()
+}
+
+/// 以 gzip 压缩网页内容
+pub fn compress(data: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
+/// 解压 gzip 压缩的网页内容
+pub fn decompress(data: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut decoder = GzDecoder::new(data);
+    let mut output = Vec::new();
+    decoder.read_to_end(&mut output)?;
+    Ok(output)
+}
+
+/// 抓取一条收藏的来源网页并保存快照，抓取失败时记录错误原因，不再重试
+async fn archive(
+    db: &SqlitePool,
+    client: &outbound::Client,
+    config: &ArchiverConfig,
+    favorite_id: i64,
+    url: &str,
+) -> Result<(), sqlx::Error> {
+    let result = fetch(client, url, config.max_bytes).await.and_then(|page| {
+        let content = compress(&page.body).map_err(|err| err.to_string())?;
+        Ok((page, content))
+    });
+    let (page, content, error) = match result {
+        Ok((page, content)) => (Some(page), Some(content), None),
+        Err(err) => (None, None, Some(err)),
+    };
+
+    let hash = page.as_ref().map(|page| {
+        Sha256::digest(&page.body)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>()
+    });
+    let text = page.as_ref().map(|page| {
+        let body = String::from_utf8_lossy(&page.body);
+        let is_html = page
+            .content_type
+            .as_deref()
+            .is_none_or(|value| value.contains("html"));
+        if is_html { extract_text(&body) } else { body.trim().to_string() }
+    });
+
+    sqlx::query(
+        "INSERT OR REPLACE INTO snapshots
+            (favorite_id, url, final_url, status, content_type, content_hash, content, size, text, error, fetched_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(favorite_id)
+    .bind(url)
+    .bind(page.as_ref().map(|page| &page.final_url))
+    .bind(page.as_ref().map(|page| page.status))
+    .bind(page.as_ref().and_then(|page| page.content_type.as_ref()))
+    .bind(hash)
+    .bind(content)
+    .bind(page.as_ref().map(|page| page.body.len() as i64))
+    .bind(text)
+    .bind(&error)
+    .bind(timezone::now())
+    .execute(db)
+    .await?;
+
+    match error {
+        Some(error) => tracing::warn!("Failed to snapshot favorite {} ({}): {}", favorite_id, url, error),
+        None => tracing::info!("Saved snapshot of favorite {} ({})", favorite_id, url),
+    }
+
+    Ok(())
+}
+
+/// 抓取尚无快照或网址已修改的收藏，返回处理的数量
+pub async fn archive_pending(
+    db: &SqlitePool,
+    client: &outbound::Client,
+    config: &ArchiverConfig,
+) -> Result<usize, sqlx::Error> {
+    let pending: Vec<(i64, String)> = sqlx::query_as(
+        "SELECT f.id, f.url FROM favorites f
+         LEFT JOIN snapshots s ON s.favorite_id = f.id
+         WHERE f.deleted_at IS NULL AND (s.favorite_id IS NULL OR s.url != f.url)
+         ORDER BY f.id
+         LIMIT ?"
+    )
+    .bind(BATCH_SIZE)
+    .fetch_all(db)
+    .await?;
+
+    for (id, url) in &pending {
+        archive(db, client, config, *id, url).await?;
+    }
+
+    Ok(pending.len())
+}
+
+/// 在后台任务中持续为新收藏保存网页快照，未启用时直接返回
+/// 两轮之间等待 `handle` 的唤醒或轮询间隔
+pub async fn run_archiver(db: SqlitePool, config: ArchiverConfig, handle: ArchiverHandle) {
+    if !config.enabled {
+        return;
+    }
+
+    let client = match outbound::Client::new(
+        Duration::from_secs(config.timeout_secs.max(1)),
+        &config.user_agent,
+        config.allow_private_network,
+    ) {
+        Ok(client) => client,
+        Err(err) => {
+            tracing::error!("Failed to create archiver HTTP client: {}", err);
+            return;
+        }
+    };
+
+    let poll_interval = Duration::from_secs(config.poll_interval_secs.max(1));
+    loop {
+        match archive_pending(&db, &client, &config).await {
+            // 一轮没有处理完时立即继续
+            Ok(count) if count as i64 == BATCH_SIZE => continue,
+            Ok(_) => {}
+            Err(err) => tracing::error!("Failed to archive favorites: {}", err),
+        }
+
+        tokio::select! {
+            _ = handle.0.notified() => {}
+            _ = tokio::time::sleep(poll_interval) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::get, Router};
+    use crate::tests::serve;
+
+    fn client(timeout: Duration) -> outbound::Client {
+        outbound::Client::new(timeout, "test", true).unwrap()
+    }
+
+    #[test]
+    fn test_extract_text() {
+        let html = r#"<html><head><title>标题</title><style>p { color: red }</style></head>
+            <body><nav>导航</nav><h1>正文标题</h1><p>第一段   文字</p>
+            <script>if (a < b) { document.write("<p>脚本</p>") }</script>
+            <p>第二段<br>换行</p><footer>页脚</footer></body></html>"#;
+        assert_eq!(extract_text(html), "正文标题\n第一段 文字\n第二段\n换行");
+    }
+
+    #[test]
+    fn test_compress_roundtrip() {
+        let data = "网页内容".repeat(100).into_bytes();
+        assert_eq!(decompress(&compress(&data).unwrap()).unwrap(), data);
+    }
+
+    #[tokio::test]
+    async fn test_fetch() {
+        let router = Router::new().route(
+            "/page",
+            get(|| async { ([(reqwest::header::CONTENT_TYPE, "text/html")], "<p>hello</p>") }),
+        );
+        let addr = serve(router).await;
+
+        let page = fetch(&client(Duration::from_secs(5)), &format!("http://{}/page", addr), 1024)
+            .await
+            .unwrap();
+        assert_eq!(page.status, 200);
+        assert_eq!(page.content_type.as_deref(), Some("text/html"));
+        assert_eq!(page.body, b"<p>hello</p>");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_size_limit() {
+        let router = Router::new()
+            .route("/fixed", get(|| async { "x".repeat(2048) }))
+            // 分块传输没有 Content-Length，读取时累计判断
+            .route(
+                "/chunked",
+                get(|| async {
+                    let chunks = (0..4).map(|_| Ok::<_, std::io::Error>("x".repeat(512)));
+                    Body::from_stream(futures_util::stream::iter(chunks))
+                }),
+            );
+        let addr = serve(router).await;
+        let client = client(Duration::from_secs(5));
+
+        for path in ["fixed", "chunked"] {
+            let err = fetch(&client, &format!("http://{}/{}", addr, path), 1024).await.err().unwrap();
+            assert!(err.contains("1024 字节上限"), "{}: {}", path, err);
+        }
+        assert!(fetch(&client, &format!("http://{}/fixed", addr), 2048).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_timeout() {
+        let router = Router::new().route(
+            "/slow",
+            get(|| async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                "late"
+            }),
+        );
+        let addr = serve(router).await;
+
+        let err = fetch(&client(Duration::from_millis(200)), &format!("http://{}/slow", addr), 1024)
+            .await
+            .err()
+            .unwrap();
+        assert!(err.contains("timed out"), "{}", err);
+    }
+}
\ No newline at end of file
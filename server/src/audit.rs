@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::sqlite::SqliteConnection;
+use utoipa::ToSchema;
+use crate::timezone;
+
+/// 审计日志记录的实体类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Entity {
+    Favorite,
+    Note,
+    Tag,
+    Category,
+}
+
+impl Entity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Entity::Favorite => "favorite",
+            Entity::Note => "note",
+            Entity::Tag => "tag",
+            Entity::Category => "category",
+        }
+    }
+}
+
+/// 将实体序列化为审计日志中保存的 JSON
+pub fn snapshot<T: Serialize>(value: &T) -> Option<Value> {
+    serde_json::to_value(value).ok()
+}
+
+/// 写入一条审计日志，返回该实体的新版本号
+/// 应与被记录的修改在同一事务中调用
+pub async fn record(
+    conn: &mut SqliteConnection,
+    actor_id: i64,
+    entity: Entity,
+    entity_id: i64,
+    action: &str,
+    before: Option<Value>,
+    after: Option<Value>,
+) -> Result<i64, sqlx::Error> {
+    let version: i64 = sqlx::query_scalar(
+        "SELECT COALESCE(MAX(version), 0) + 1 FROM audit_log WHERE entity_type = ? AND entity_id = ?"
+    )
+    .bind(entity.as_str())
+    .bind(entity_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO audit_log (actor_id, entity_type, entity_id, version, action, before, after, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(actor_id)
+    .bind(entity.as_str())
+    .bind(entity_id)
+    .bind(version)
+    .bind(action)
+    .bind(before.map(|value| value.to_string()))
+    .bind(after.map(|value| value.to_string()))
+    .bind(timezone::now())
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(version)
+}
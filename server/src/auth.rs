@@ -0,0 +1,165 @@
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts},
+    http::{header::AUTHORIZATION, request::Parts},
+};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use sqlx::sqlite::SqlitePool;
+use sqlx::FromRow;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use crate::error::AppError;
+use crate::timezone;
+
+/// API Key 前缀，便于识别
+const KEY_PREFIX: &str = "fav_";
+
+/// 当前请求的用户，通过 `Authorization: Bearer <api key>` 解析
+#[derive(Debug, Clone, FromRow)]
+pub struct AuthUser {
+    pub id: i64,
+    pub name: String,
+    pub is_admin: bool,
+}
+
+/// 仅允许管理员访问的用户
+#[derive(Debug, Clone)]
+pub struct AdminUser(pub AuthUser);
+
+/// 计算 API Key 的哈希值，数据库中只保存哈希
+pub fn hash_key(key: &str) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 生成新的 API Key，返回 (明文, 用于展示的前缀)
+pub fn generate_key() -> (String, String) {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let secret: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    let key = format!("{}{}", KEY_PREFIX, secret);
+    let prefix = key[..KEY_PREFIX.len() + 8].to_string();
+    (key, prefix)
+}
+
+/// 为用户签发 API Key，返回 (key id, 明文)
+pub async fn issue_key(db: &SqlitePool, user_id: i64) -> Result<(i64, String), sqlx::Error> {
+    let (key, prefix) = generate_key();
+    let result = sqlx::query(
+        "INSERT INTO api_keys (user_id, key_hash, key_prefix, created_at) VALUES (?, ?, ?, ?)"
+    )
+    .bind(user_id)
+    .bind(hash_key(&key))
+    .bind(prefix)
+    .bind(timezone::now())
+    .execute(db)
+    .await?;
+    Ok((result.last_insert_rowid(), key))
+}
+
+/// 将明文 Key 写入仅所有者可读写的文件，已存在时覆盖
+fn write_key_file(path: &Path, key: &str) -> std::io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        // 文件已存在时 mode 不生效，单独收紧权限
+        if path.exists() {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        }
+    }
+    let mut file = options.open(path)?;
+    writeln!(file, "{}", key)
+}
+
+/// 若没有任何可用的管理员 Key，则为默认管理员签发一个并写入 `key_file`
+/// 明文 Key 不输出到日志，写入失败时撤回该 Key，下次启动重新签发
+pub async fn ensure_admin_key(db: &SqlitePool, key_file: &Path) -> Result<(), sqlx::Error> {
+    let active: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM api_keys k JOIN users u ON u.id = k.user_id
+         WHERE u.is_admin = 1 AND k.revoked_at IS NULL"
+    )
+    .fetch_one(db)
+    .await?;
+
+    if active > 0 {
+        return Ok(());
+    }
+
+    let admin_id: i64 = sqlx::query_scalar(
+        "SELECT id FROM users WHERE is_admin = 1 ORDER BY id LIMIT 1"
+    )
+    .fetch_one(db)
+    .await?;
+
+    let (id, key) = issue_key(db, admin_id).await?;
+    if let Err(err) = write_key_file(key_file, &key) {
+        sqlx::query("DELETE FROM api_keys WHERE id = ?")
+            .bind(id)
+            .execute(db)
+            .await?;
+        return Err(sqlx::Error::Io(err));
+    }
+    tracing::warn!(
+        "No active admin API key found, issued a new one and wrote it to {}",
+        key_file.display()
+    );
+
+    Ok(())
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AuthUser
+where
+    SqlitePool: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let key = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .ok_or(AppError::Unauthorized)?;
+
+        let db = SqlitePool::from_ref(state);
+        sqlx::query_as::<_, AuthUser>(
+            "SELECT u.id, u.name, u.is_admin
+             FROM api_keys k
+             JOIN users u ON u.id = k.user_id
+             WHERE k.key_hash = ? AND k.revoked_at IS NULL"
+        )
+        .bind(hash_key(key))
+        .fetch_optional(&db)
+        .await
+        .map_err(AppError::Database)?
+        .ok_or(AppError::Unauthorized)
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AdminUser
+where
+    SqlitePool: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let user = AuthUser::from_request_parts(parts, state).await?;
+        if !user.is_admin {
+            return Err(AppError::Forbidden);
+        }
+        Ok(AdminUser(user))
+    }
+}
@@ -1,14 +1,152 @@
 use serde::Deserialize;
 use std::env;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::PathBuf;
 
 #[derive(Debug, Deserialize)]
 pub struct ServerConfig {
+    #[serde(default = "default_host")]
+    pub host: IpAddr,
     pub port: u16,
+    #[serde(default = "default_admin_key_file")]
+    pub admin_key_file: PathBuf, // 首次启动时签发的管理员 API Key 写入该文件，仅所有者可读
+}
+
+fn default_host() -> IpAddr {
+    IpAddr::V4(Ipv4Addr::LOCALHOST)
+}
+
+fn default_admin_key_file() -> PathBuf {
+    PathBuf::from("admin_key.txt")
+}
+
+/// 回收站配置
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrashConfig {
+    #[serde(default = "default_retention_days")]
+    pub retention_days: i64, // 回收站中的收藏保留天数，超过后彻底删除
+    #[serde(default = "default_purge_interval_secs")]
+    pub purge_interval_secs: u64, // 清理任务的执行间隔
+}
+
+fn default_retention_days() -> i64 {
+    30
+}
+
+fn default_purge_interval_secs() -> u64 {
+    3600
+}
+
+impl Default for TrashConfig {
+    fn default() -> Self {
+        TrashConfig {
+            retention_days: default_retention_days(),
+            purge_interval_secs: default_purge_interval_secs(),
+        }
+    }
+}
+
+/// 网页快照配置，默认关闭
+#[derive(Debug, Clone, Deserialize)]
+pub struct ArchiverConfig {
+    #[serde(default)]
+    pub enabled: bool, // 是否在保存收藏后抓取原网页
+    #[serde(default = "default_archive_timeout_secs")]
+    pub timeout_secs: u64, // 单次抓取的超时时间
+    #[serde(default = "default_archive_max_bytes")]
+    pub max_bytes: usize, // 响应体的大小上限，超过时放弃抓取
+    #[serde(default = "default_archive_poll_interval_secs")]
+    pub poll_interval_secs: u64, // 检查待抓取收藏的间隔
+    #[serde(default = "default_archive_user_agent")]
+    pub user_agent: String,
+    #[serde(default)]
+    pub allow_private_network: bool, // 是否允许抓取内网地址，默认只访问公网地址
+}
+
+fn default_archive_timeout_secs() -> u64 {
+    15
+}
+
+fn default_archive_max_bytes() -> usize {
+    5 * 1024 * 1024
+}
+
+fn default_archive_poll_interval_secs() -> u64 {
+    60
+}
+
+fn default_archive_user_agent() -> String {
+    concat!("chrome-ex-get-text/", env!("CARGO_PKG_VERSION")).to_string()
+}
+
+impl Default for ArchiverConfig {
+    fn default() -> Self {
+        ArchiverConfig {
+            enabled: false,
+            timeout_secs: default_archive_timeout_secs(),
+            max_bytes: default_archive_max_bytes(),
+            poll_interval_secs: default_archive_poll_interval_secs(),
+            user_agent: default_archive_user_agent(),
+            allow_private_network: false,
+        }
+    }
+}
+
+/// 失效链接检查配置，默认关闭
+#[derive(Debug, Clone, Deserialize)]
+pub struct LinkCheckConfig {
+    #[serde(default)]
+    pub enabled: bool, // 是否定期检查收藏的网址
+    #[serde(default = "default_link_check_interval_secs")]
+    pub interval_secs: u64, // 两次检查之间的间隔
+    #[serde(default = "default_link_check_concurrency")]
+    pub concurrency: usize, // 同时检查的主机数量
+    #[serde(default = "default_link_check_per_host_delay_ms")]
+    pub per_host_delay_ms: u64, // 对同一主机连续请求的最小间隔
+    #[serde(default = "default_link_check_timeout_secs")]
+    pub timeout_secs: u64, // 单个网址的超时时间
+    #[serde(default)]
+    pub allow_private_network: bool, // 是否允许检查内网地址，默认只访问公网地址
+}
+
+fn default_link_check_interval_secs() -> u64 {
+    86400
+}
+
+fn default_link_check_concurrency() -> usize {
+    8
+}
+
+fn default_link_check_per_host_delay_ms() -> u64 {
+    1000
+}
+
+fn default_link_check_timeout_secs() -> u64 {
+    15
+}
+
+impl Default for LinkCheckConfig {
+    fn default() -> Self {
+        LinkCheckConfig {
+            enabled: false,
+            interval_secs: default_link_check_interval_secs(),
+            concurrency: default_link_check_concurrency(),
+            per_host_delay_ms: default_link_check_per_host_delay_ms(),
+            timeout_secs: default_link_check_timeout_secs(),
+            allow_private_network: false,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct Config {
     pub server: ServerConfig,
+    #[serde(default)]
+    pub trash: TrashConfig,
+    #[serde(default)]
+    pub archiver: ArchiverConfig,
+    #[serde(default)]
+    pub link_check: LinkCheckConfig,
 }
 
 impl Config {
@@ -1,13 +1,27 @@
-use sqlx::sqlite::{SqlitePool, SqlitePoolOptions};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use sqlx::Connection;
 use std::env;
+use std::str::FromStr;
+use crate::normalize;
+use crate::text_fragment;
+use crate::timezone;
+use crate::url_normalize;
 
 pub async fn init_db() -> Result<SqlitePool, sqlx::Error> {
     let database_url = env::var("DATABASE_URL")
         .unwrap_or_else(|_| "sqlite:data.db".to_string());
 
+    connect(&database_url).await
+}
+
+/// 连接指定的数据库并运行迁移
+pub async fn connect(database_url: &str) -> Result<SqlitePool, sqlx::Error> {
+    // 显式启用外键约束，保证分类、标签等引用的完整性
+    let options = SqliteConnectOptions::from_str(database_url)?.foreign_keys(true);
+
     let pool = SqlitePoolOptions::new()
         .max_connections(5)
-        .connect(&database_url)
+        .connect_with(options)
         .await?;
 
     // 运行数据库迁移
@@ -79,5 +93,831 @@ async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
         tx.commit().await?;
     }
 
+    // 版本2：标签关联表，替换 favorites.tags 中的 JSON 字符串
+    if current_version < 2 {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS favorite_tags (
+                favorite_id INTEGER NOT NULL,
+                tag_id INTEGER NOT NULL,
+                PRIMARY KEY (favorite_id, tag_id),
+                FOREIGN KEY (favorite_id) REFERENCES favorites (id) ON DELETE CASCADE,
+                FOREIGN KEY (tag_id) REFERENCES tags (id) ON DELETE CASCADE
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_favorite_tags_tag_id ON favorite_tags (tag_id);
+
+            -- 补全旧数据中存在但 tags 表中没有的标签
+            INSERT OR IGNORE INTO tags (name)
+            SELECT DISTINCT TRIM(j.value)
+            FROM favorites f, json_each(f.tags) j
+            WHERE json_valid(f.tags) AND TRIM(j.value) <> '';
+
+            -- 根据标签名称回填关联关系
+            INSERT OR IGNORE INTO favorite_tags (favorite_id, tag_id)
+            SELECT f.id, t.id
+            FROM favorites f, json_each(f.tags) j
+            JOIN tags t ON t.name = TRIM(j.value)
+            WHERE json_valid(f.tags);
+
+            ALTER TABLE favorites DROP COLUMN tags;
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("INSERT INTO migrations (version) VALUES (?)")
+            .bind(2)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+    }
+
+    // 版本3：基于 FTS5 的全文索引，trigram 分词以支持无空格的中文
+    if current_version < 3 {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            CREATE VIRTUAL TABLE IF NOT EXISTS favorites_fts USING fts5(
+                text,
+                url,
+                tags,
+                tokenize = 'trigram'
+            );
+
+            CREATE TRIGGER IF NOT EXISTS favorites_fts_insert AFTER INSERT ON favorites
+            BEGIN
+                INSERT INTO favorites_fts (rowid, text, url, tags)
+                VALUES (new.id, new.text, new.url, '');
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS favorites_fts_update AFTER UPDATE OF text, url ON favorites
+            BEGIN
+                UPDATE favorites_fts SET text = new.text, url = new.url WHERE rowid = new.id;
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS favorites_fts_delete AFTER DELETE ON favorites
+            BEGIN
+                DELETE FROM favorites_fts WHERE rowid = old.id;
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS favorites_fts_tag_insert AFTER INSERT ON favorite_tags
+            BEGIN
+                UPDATE favorites_fts SET tags = (
+                    SELECT COALESCE(group_concat(t.name, ' '), '')
+                    FROM favorite_tags ft JOIN tags t ON t.id = ft.tag_id
+                    WHERE ft.favorite_id = new.favorite_id
+                ) WHERE rowid = new.favorite_id;
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS favorites_fts_tag_delete AFTER DELETE ON favorite_tags
+            BEGIN
+                UPDATE favorites_fts SET tags = (
+                    SELECT COALESCE(group_concat(t.name, ' '), '')
+                    FROM favorite_tags ft JOIN tags t ON t.id = ft.tag_id
+                    WHERE ft.favorite_id = old.favorite_id
+                ) WHERE rowid = old.favorite_id;
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS favorites_fts_tag_rename AFTER UPDATE OF name ON tags
+            BEGIN
+                UPDATE favorites_fts SET tags = (
+                    SELECT COALESCE(group_concat(t.name, ' '), '')
+                    FROM favorite_tags ft JOIN tags t ON t.id = ft.tag_id
+                    WHERE ft.favorite_id = favorites_fts.rowid
+                ) WHERE rowid IN (SELECT favorite_id FROM favorite_tags WHERE tag_id = new.id);
+            END;
+
+            -- 为已有数据建立索引
+            INSERT INTO favorites_fts (rowid, text, url, tags)
+            SELECT f.id, f.text, f.url, COALESCE((
+                SELECT group_concat(t.name, ' ')
+                FROM favorite_tags ft JOIN tags t ON t.id = ft.tag_id
+                WHERE ft.favorite_id = f.id
+            ), '')
+            FROM favorites f;
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("INSERT INTO migrations (version) VALUES (?)")
+            .bind(3)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+    }
+
+    // 版本4：用户与 API Key，数据按用户隔离
+    if current_version < 4 {
+        // 重建表需要临时关闭外键检查，该 PRAGMA 在事务内无效；
+        // 同时启用旧版 RENAME 行为，避免重命名时校验引用 tags 的触发器
+        let mut conn = pool.acquire().await?;
+        sqlx::query("PRAGMA foreign_keys = OFF; PRAGMA legacy_alter_table = ON")
+            .execute(&mut *conn)
+            .await?;
+
+        let mut tx = conn.begin().await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS users (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL UNIQUE,
+                is_admin INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+            );
+
+            CREATE TABLE IF NOT EXISTS api_keys (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                user_id INTEGER NOT NULL,
+                key_hash TEXT NOT NULL UNIQUE,
+                key_prefix TEXT NOT NULL,
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                revoked_at TEXT,
+                FOREIGN KEY (user_id) REFERENCES users (id)
+            );
+
+            -- 已有数据归属于默认管理员
+            INSERT OR IGNORE INTO users (id, name, is_admin) VALUES (1, 'admin', 1);
+
+            -- 分类和标签名称改为按用户唯一，需要重建表
+            CREATE TABLE categories_new (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                user_id INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                UNIQUE (user_id, name),
+                FOREIGN KEY (user_id) REFERENCES users (id)
+            );
+            INSERT INTO categories_new (id, user_id, name) SELECT id, 1, name FROM categories;
+            DROP TABLE categories;
+            ALTER TABLE categories_new RENAME TO categories;
+
+            CREATE TABLE tags_new (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                user_id INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                UNIQUE (user_id, name),
+                FOREIGN KEY (user_id) REFERENCES users (id)
+            );
+            INSERT INTO tags_new (id, user_id, name) SELECT id, 1, name FROM tags;
+            DROP TABLE tags;
+            ALTER TABLE tags_new RENAME TO tags;
+
+            -- 删除 tags 表时其触发器一并被删除，需要重新创建
+            CREATE TRIGGER IF NOT EXISTS favorites_fts_tag_rename AFTER UPDATE OF name ON tags
+            BEGIN
+                UPDATE favorites_fts SET tags = (
+                    SELECT COALESCE(group_concat(t.name, ' '), '')
+                    FROM favorite_tags ft JOIN tags t ON t.id = ft.tag_id
+                    WHERE ft.favorite_id = favorites_fts.rowid
+                ) WHERE rowid IN (SELECT favorite_id FROM favorite_tags WHERE tag_id = new.id);
+            END;
+
+            ALTER TABLE favorites ADD COLUMN user_id INTEGER REFERENCES users (id);
+            UPDATE favorites SET user_id = 1;
+
+            CREATE INDEX IF NOT EXISTS idx_favorites_user_id ON favorites (user_id);
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("INSERT INTO migrations (version) VALUES (?)")
+            .bind(4)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        sqlx::query("PRAGMA legacy_alter_table = OFF; PRAGMA foreign_keys = ON")
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    // 版本5：查重键，按规范化后的文本和地址识别重复收藏
+    if current_version < 5 {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            ALTER TABLE favorites ADD COLUMN dedup_key TEXT;
+            CREATE INDEX IF NOT EXISTS idx_favorites_dedup_key ON favorites (user_id, dedup_key);
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        // 查重键依赖应用层的规范化逻辑，逐条回填
+        let rows: Vec<(i64, String, String)> = sqlx::query_as("SELECT id, text, url FROM favorites")
+            .fetch_all(&mut *tx)
+            .await?;
+        for (id, text, url) in rows {
+            sqlx::query("UPDATE favorites SET dedup_key = ? WHERE id = ?")
+                .bind(normalize::dedup_key(&text, &url))
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        sqlx::query("INSERT INTO migrations (version) VALUES (?)")
+            .bind(5)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+    }
+
+    // 版本6：定位到原文段落的文本片段链接
+    if current_version < 6 {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query("ALTER TABLE favorites ADD COLUMN deep_link TEXT")
+            .execute(&mut *tx)
+            .await?;
+
+        // 为已有收藏生成文本片段链接
+        let rows: Vec<(i64, String, String)> = sqlx::query_as("SELECT id, text, url FROM favorites")
+            .fetch_all(&mut *tx)
+            .await?;
+        for (id, text, url) in rows {
+            sqlx::query("UPDATE favorites SET deep_link = ? WHERE id = ?")
+                .bind(text_fragment::deep_link(&url, &text, None, None))
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        sqlx::query("INSERT INTO migrations (version) VALUES (?)")
+            .bind(6)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+    }
+
+    // 版本7：选区的页面标题、前后文、HTML 和语言
+    if current_version < 7 {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            ALTER TABLE favorites ADD COLUMN title TEXT;
+            ALTER TABLE favorites ADD COLUMN context_before TEXT;
+            ALTER TABLE favorites ADD COLUMN context_after TEXT;
+            ALTER TABLE favorites ADD COLUMN html TEXT;
+            ALTER TABLE favorites ADD COLUMN lang TEXT;
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("INSERT INTO migrations (version) VALUES (?)")
+            .bind(7)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+    }
+
+    // 版本8：收藏笔记，并加入全文索引
+    if current_version < 8 {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS notes (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                favorite_id INTEGER NOT NULL,
+                body TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (favorite_id) REFERENCES favorites (id) ON DELETE CASCADE
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_notes_favorite_id ON notes (favorite_id);
+
+            -- FTS5 表不支持新增列，重建索引表以加入笔记内容
+            DROP TABLE IF EXISTS favorites_fts;
+            CREATE VIRTUAL TABLE favorites_fts USING fts5(
+                text,
+                url,
+                tags,
+                notes,
+                tokenize = 'trigram'
+            );
+
+            CREATE TRIGGER IF NOT EXISTS favorites_fts_note_insert AFTER INSERT ON notes
+            BEGIN
+                UPDATE favorites_fts SET notes = (
+                    SELECT COALESCE(group_concat(body, ' '), '')
+                    FROM notes WHERE favorite_id = new.favorite_id
+                ) WHERE rowid = new.favorite_id;
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS favorites_fts_note_update AFTER UPDATE ON notes
+            BEGIN
+                UPDATE favorites_fts SET notes = (
+                    SELECT COALESCE(group_concat(body, ' '), '')
+                    FROM notes WHERE favorite_id = favorites_fts.rowid
+                ) WHERE rowid IN (old.favorite_id, new.favorite_id);
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS favorites_fts_note_delete AFTER DELETE ON notes
+            BEGIN
+                UPDATE favorites_fts SET notes = (
+                    SELECT COALESCE(group_concat(body, ' '), '')
+                    FROM notes WHERE favorite_id = old.favorite_id
+                ) WHERE rowid = old.favorite_id;
+            END;
+
+            -- 为已有数据重建索引
+            INSERT INTO favorites_fts (rowid, text, url, tags, notes)
+            SELECT f.id, f.text, f.url, COALESCE((
+                SELECT group_concat(t.name, ' ')
+                FROM favorite_tags ft JOIN tags t ON t.id = ft.tag_id
+                WHERE ft.favorite_id = f.id
+            ), ''), ''
+            FROM favorites f;
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("INSERT INTO migrations (version) VALUES (?)")
+            .bind(8)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+    }
+
+    // 版本9：分类支持层级，名称改为在同级分类中唯一
+    if current_version < 9 {
+        let mut conn = pool.acquire().await?;
+        sqlx::query("PRAGMA foreign_keys = OFF; PRAGMA legacy_alter_table = ON")
+            .execute(&mut *conn)
+            .await?;
+
+        let mut tx = conn.begin().await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE categories_new (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                user_id INTEGER NOT NULL,
+                parent_id INTEGER,
+                name TEXT NOT NULL,
+                FOREIGN KEY (user_id) REFERENCES users (id),
+                FOREIGN KEY (parent_id) REFERENCES categories (id)
+            );
+            INSERT INTO categories_new (id, user_id, name) SELECT id, user_id, name FROM categories;
+            DROP TABLE categories;
+            ALTER TABLE categories_new RENAME TO categories;
+
+            -- 顶级分类的 parent_id 为 NULL，唯一索引中以 0 代替，避免 NULL 互不相等
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_categories_sibling_name
+                ON categories (user_id, COALESCE(parent_id, 0), name);
+            CREATE INDEX IF NOT EXISTS idx_categories_parent_id ON categories (parent_id);
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("INSERT INTO migrations (version) VALUES (?)")
+            .bind(9)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        sqlx::query("PRAGMA legacy_alter_table = OFF; PRAGMA foreign_keys = ON")
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    // 版本10：收藏的修改时间，以及按各排序键分页用的索引
+    if current_version < 10 {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            ALTER TABLE favorites ADD COLUMN updated_at TEXT NOT NULL DEFAULT '';
+            UPDATE favorites SET updated_at = created_at;
+            CREATE INDEX IF NOT EXISTS idx_favorites_created_at ON favorites (user_id, created_at, id);
+            CREATE INDEX IF NOT EXISTS idx_favorites_updated_at ON favorites (user_id, updated_at, id);
+            CREATE INDEX IF NOT EXISTS idx_favorites_url ON favorites (user_id, url, id);
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("INSERT INTO migrations (version) VALUES (?)")
+            .bind(10)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+    }
+
+    // 版本11：收藏地址的主机名，用于按域名筛选
+    if current_version < 11 {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            ALTER TABLE favorites ADD COLUMN domain TEXT NOT NULL DEFAULT '';
+            CREATE INDEX IF NOT EXISTS idx_favorites_domain ON favorites (user_id, domain);
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let rows: Vec<(i64, String)> = sqlx::query_as("SELECT id, url FROM favorites")
+            .fetch_all(&mut *tx)
+            .await?;
+        for (id, url) in rows {
+            sqlx::query("UPDATE favorites SET domain = ? WHERE id = ?")
+                .bind(url_normalize::host(&url))
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        sqlx::query("INSERT INTO migrations (version) VALUES (?)")
+            .bind(11)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+    }
+
+    // 版本12：时间改为 UTC 的 RFC 3339 格式，不再依赖服务器所在时区
+    if current_version < 12 {
+        let mut tx = pool.begin().await?;
+
+        // 旧数据按服务器本地时间保存，需在应用层换算，逐条转换
+        for table in ["favorites", "notes"] {
+            let rows: Vec<(i64, String, String)> =
+                sqlx::query_as(&format!("SELECT id, created_at, updated_at FROM {}", table))
+                    .fetch_all(&mut *tx)
+                    .await?;
+            for (id, created_at, updated_at) in rows {
+                sqlx::query(&format!("UPDATE {} SET created_at = ?, updated_at = ? WHERE id = ?", table))
+                    .bind(timezone::from_legacy(&created_at).unwrap_or(created_at))
+                    .bind(timezone::from_legacy(&updated_at).unwrap_or(updated_at))
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+
+        sqlx::query("INSERT INTO migrations (version) VALUES (?)")
+            .bind(12)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+    }
+
+    // 版本13：收藏归档，归档后默认不在列表中显示
+    if current_version < 13 {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query("ALTER TABLE favorites ADD COLUMN archived_at TEXT")
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("INSERT INTO migrations (version) VALUES (?)")
+            .bind(13)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+    }
+
+    // 版本14：软删除，删除的收藏先移入回收站
+    if current_version < 14 {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            ALTER TABLE favorites ADD COLUMN deleted_at TEXT;
+            CREATE INDEX IF NOT EXISTS idx_favorites_deleted_at ON favorites (user_id, deleted_at);
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("INSERT INTO migrations (version) VALUES (?)")
+            .bind(14)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+    }
+
+    // 版本15：审计日志，记录收藏、笔记、标签和分类每次修改前后的内容
+    if current_version < 15 {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS audit_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                actor_id INTEGER NOT NULL,
+                entity_type TEXT NOT NULL,
+                entity_id INTEGER NOT NULL,
+                version INTEGER NOT NULL,
+                action TEXT NOT NULL,
+                before TEXT,
+                after TEXT,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (actor_id) REFERENCES users (id)
+            );
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_audit_log_entity ON audit_log (entity_type, entity_id, version);
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("INSERT INTO migrations (version) VALUES (?)")
+            .bind(15)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+    }
+
+    // 版本16：网页快照，保存收藏来源页面的压缩内容和提取出的正文
+    if current_version < 16 {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS snapshots (
+                favorite_id INTEGER PRIMARY KEY,
+                url TEXT NOT NULL,
+                final_url TEXT,
+                status INTEGER,
+                content_type TEXT,
+                content_hash TEXT,
+                content BLOB,
+                size INTEGER,
+                text TEXT,
+                error TEXT,
+                fetched_at TEXT NOT NULL,
+                FOREIGN KEY (favorite_id) REFERENCES favorites (id) ON DELETE CASCADE
+            )
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("INSERT INTO migrations (version) VALUES (?)")
+            .bind(16)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+    }
+
+    // 版本17：失效链接检查结果，按网址记录最近一次检查的状态
+    if current_version < 17 {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS link_checks (
+                url TEXT PRIMARY KEY,
+                final_url TEXT,
+                last_status INTEGER,
+                error TEXT,
+                moved BOOLEAN NOT NULL DEFAULT 0,
+                last_checked_at TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("INSERT INTO migrations (version) VALUES (?)")
+            .bind(17)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+    }
+
+    // 版本18：规范化网址，去掉跟踪参数等差异，同一页面的不同写法归为一处
+    if current_version < 18 {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            ALTER TABLE favorites ADD COLUMN canonical_url TEXT NOT NULL DEFAULT '';
+            CREATE INDEX IF NOT EXISTS idx_favorites_canonical_url ON favorites (user_id, canonical_url);
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        // 规范化规则有变化，主机名和查重键一并重新计算
+        let rows: Vec<(i64, String, String)> = sqlx::query_as("SELECT id, text, url FROM favorites")
+            .fetch_all(&mut *tx)
+            .await?;
+        for (id, text, url) in rows {
+            sqlx::query("UPDATE favorites SET canonical_url = ?, domain = ?, dedup_key = ? WHERE id = ?")
+                .bind(url_normalize::canonical_url(&url))
+                .bind(url_normalize::host(&url))
+                .bind(normalize::dedup_key(&text, &url))
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        sqlx::query("INSERT INTO migrations (version) VALUES (?)")
+            .bind(18)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+    }
+
+    // 版本19：来源网页，同一规范化网址下的收藏归到一个页面
+    if current_version < 19 {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS pages (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                user_id INTEGER NOT NULL,
+                canonical_url TEXT NOT NULL,
+                title TEXT,
+                first_seen_at TEXT NOT NULL,
+                UNIQUE (user_id, canonical_url),
+                FOREIGN KEY (user_id) REFERENCES users (id)
+            );
+            ALTER TABLE favorites ADD COLUMN page_id INTEGER REFERENCES pages (id);
+            CREATE INDEX IF NOT EXISTS idx_favorites_page_id ON favorites (page_id);
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        // 已有收藏按规范化网址建立页面，标题取最早一条带标题的收藏
+        sqlx::query(
+            r#"
+            INSERT INTO pages (user_id, canonical_url, title, first_seen_at)
+            SELECT f.user_id, f.canonical_url,
+                   (SELECT t.title FROM favorites t
+                     WHERE t.user_id = f.user_id AND t.canonical_url = f.canonical_url AND t.title IS NOT NULL
+                     ORDER BY t.created_at, t.id LIMIT 1),
+                   MIN(f.created_at)
+            FROM favorites f
+            WHERE f.user_id IS NOT NULL
+            GROUP BY f.user_id, f.canonical_url;
+            UPDATE favorites SET page_id = (
+                SELECT p.id FROM pages p
+                WHERE p.user_id = favorites.user_id AND p.canonical_url = favorites.canonical_url
+            );
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("INSERT INTO migrations (version) VALUES (?)")
+            .bind(19)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+    }
+
+    // 版本20：用户和 API Key 的时间同样改为 UTC 的 RFC 3339 格式
+    if current_version < 20 {
+        let mut tx = pool.begin().await?;
+
+        // 这些时间由 CURRENT_TIMESTAMP 生成，本身就是 UTC，只需改写格式
+        sqlx::query(
+            r#"
+            UPDATE users SET created_at = strftime('%Y-%m-%dT%H:%M:%SZ', created_at)
+             WHERE created_at NOT LIKE '%T%';
+            UPDATE api_keys SET created_at = strftime('%Y-%m-%dT%H:%M:%SZ', created_at)
+             WHERE created_at NOT LIKE '%T%';
+            UPDATE api_keys SET revoked_at = strftime('%Y-%m-%dT%H:%M:%SZ', revoked_at)
+             WHERE revoked_at NOT LIKE '%T%';
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("INSERT INTO migrations (version) VALUES (?)")
+            .bind(20)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+    }
+
     Ok(())
-} 
\ No newline at end of file
+} 
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_migrate_json_tags() {
+        let options = SqliteConnectOptions::from_str("sqlite::memory:").unwrap().foreign_keys(true);
+        let pool = SqlitePoolOptions::new().max_connections(5).connect_with(options).await.unwrap();
+
+        // 版本1的结构，标签以 JSON 字符串保存在收藏中
+        sqlx::query(
+            r#"
+            CREATE TABLE migrations (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                version INTEGER NOT NULL UNIQUE,
+                applied_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            );
+            INSERT INTO migrations (version) VALUES (1);
+
+            CREATE TABLE categories (id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT NOT NULL UNIQUE);
+            CREATE TABLE tags (id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT NOT NULL UNIQUE);
+            CREATE TABLE favorites (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                text TEXT NOT NULL,
+                url TEXT NOT NULL,
+                category_id INTEGER,
+                tags TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (category_id) REFERENCES categories (id)
+            );
+
+            INSERT INTO tags (name) VALUES ('rust');
+            INSERT INTO favorites (id, text, url, tags, created_at) VALUES
+                (1, 'one', 'https://example.com/1', '["rust", " rust ", "web", "", "rust"]', '2024-01-01 00:00:00'),
+                (2, 'two', 'https://example.com/2', '["web", "   ", " 数据库 "]', '2024-01-02 00:00:00'),
+                (3, 'three', 'https://example.com/3', 'not json', '2024-01-03 00:00:00'),
+                (4, 'four', 'https://example.com/4', '[]', '2024-01-04 00:00:00');
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        run_migrations(&pool).await.unwrap();
+
+        // 标签按名称去重，空白标签被忽略，已有标签保留原ID
+        let tags: Vec<(i64, String)> = sqlx::query_as("SELECT id, name FROM tags ORDER BY id")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        let names: Vec<&str> = tags.iter().map(|(_, name)| name.as_str()).collect();
+        assert_eq!(names, vec!["rust", "web", "数据库"]);
+        assert_eq!(tags[0].0, 1);
+
+        let favorite_tags = |id: i64| {
+            sqlx::query_scalar::<_, String>(
+                "SELECT t.name FROM favorite_tags ft JOIN tags t ON t.id = ft.tag_id
+                 WHERE ft.favorite_id = ? ORDER BY t.id"
+            )
+            .bind(id)
+            .fetch_all(&pool)
+        };
+        assert_eq!(favorite_tags(1).await.unwrap(), vec!["rust", "web"]);
+        assert_eq!(favorite_tags(2).await.unwrap(), vec!["web", "数据库"]);
+        assert!(favorite_tags(3).await.unwrap().is_empty());
+        assert!(favorite_tags(4).await.unwrap().is_empty());
+
+        // 旧数据归属默认管理员，标签同样可以全文检索
+        let owners: Vec<i64> = sqlx::query_scalar("SELECT DISTINCT user_id FROM favorites")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(owners, vec![1]);
+        let matched: Vec<i64> =
+            sqlx::query_scalar("SELECT rowid FROM favorites_fts WHERE favorites_fts MATCH 'web' ORDER BY rowid")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(matched, vec![1, 2]);
+        let matched: Vec<i64> = sqlx::query_scalar("SELECT rowid FROM favorites_fts WHERE favorites_fts MATCH '数据库'")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(matched, vec![2]);
+
+        // 重复运行迁移不会改变数据
+        run_migrations(&pool).await.unwrap();
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM favorite_tags").fetch_one(&pool).await.unwrap();
+        assert_eq!(count, 4);
+    }
+}
\ No newline at end of file
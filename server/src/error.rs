@@ -3,28 +3,90 @@ use axum::{
     response::{IntoResponse, Response},
 };
 use serde::Serialize;
+use sqlx::error::ErrorKind;
+use std::collections::BTreeMap;
+use validator::ValidationErrors;
+
+/// 字段名到错误提示的映射
+pub type FieldErrors = BTreeMap<String, String>;
 
 #[derive(Debug)]
 pub enum AppError {
     Database(sqlx::Error),
     NotFound,
     BadRequest(String),
+    Conflict(String),
+    Validation(FieldErrors),
+    Unauthorized,
+    Forbidden,
 }
 
 #[derive(Serialize)]
 struct ErrorResponse {
     error: String,
     code: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<FieldErrors>, // 字段级的错误详情
+}
+
+impl AppError {
+    /// 单个字段的校验错误
+    pub fn field(field: &str, message: impl Into<String>) -> Self {
+        AppError::Validation(FieldErrors::from([(field.to_string(), message.into())]))
+    }
+
+    /// 唯一约束冲突时返回带提示的 409，其余数据库错误原样返回
+    pub fn unique_conflict(err: sqlx::Error, message: impl Into<String>) -> Self {
+        match err.as_database_error().map(|e| e.kind()) {
+            Some(ErrorKind::UniqueViolation) => AppError::Conflict(message.into()),
+            _ => AppError::Database(err),
+        }
+    }
+}
+
+/// 按字段汇总校验错误，同一字段的多条提示以分号连接
+pub fn field_errors(errors: &ValidationErrors) -> FieldErrors {
+    errors
+        .field_errors()
+        .into_iter()
+        .map(|(field, errors)| {
+            let messages: Vec<String> = errors
+                .iter()
+                .map(|e| e.message.as_ref().map_or_else(|| e.code.to_string(), |m| m.to_string()))
+                .collect();
+            (field.to_string(), messages.join("；"))
+        })
+        .collect()
+}
+
+impl From<ValidationErrors> for AppError {
+    fn from(errors: ValidationErrors) -> Self {
+        AppError::Validation(field_errors(&errors))
+    }
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
+        let mut details = None;
         let (status, error_code, message) = match self {
-            AppError::Database(err) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "DATABASE_ERROR",
-                format!("Database error: {}", err),
+            // 记录不存在和约束冲突不属于服务器错误
+            AppError::Database(sqlx::Error::RowNotFound) => (
+                StatusCode::NOT_FOUND,
+                "NOT_FOUND",
+                "Resource not found".to_string(),
             ),
+            AppError::Database(err) => match err.as_database_error().map(|e| e.kind()) {
+                Some(ErrorKind::UniqueViolation | ErrorKind::ForeignKeyViolation) => (
+                    StatusCode::CONFLICT,
+                    "CONFLICT",
+                    format!("Constraint violation: {}", err),
+                ),
+                _ => (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "DATABASE_ERROR",
+                    format!("Database error: {}", err),
+                ),
+            },
             AppError::NotFound => (
                 StatusCode::NOT_FOUND,
                 "NOT_FOUND",
@@ -35,13 +97,37 @@ impl IntoResponse for AppError {
                 "BAD_REQUEST",
                 msg,
             ),
+            AppError::Conflict(msg) => (
+                StatusCode::CONFLICT,
+                "CONFLICT",
+                msg,
+            ),
+            AppError::Validation(errors) => {
+                details = Some(errors);
+                (
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    "VALIDATION_ERROR",
+                    "Validation failed".to_string(),
+                )
+            }
+            AppError::Unauthorized => (
+                StatusCode::UNAUTHORIZED,
+                "UNAUTHORIZED",
+                "Missing or invalid API key".to_string(),
+            ),
+            AppError::Forbidden => (
+                StatusCode::FORBIDDEN,
+                "FORBIDDEN",
+                "Admin privileges required".to_string(),
+            ),
         };
 
         let body = ErrorResponse {
             error: message,
             code: error_code.to_string(),
+            details,
         };
 
         (status, axum::Json(body)).into_response()
     }
-} 
\ No newline at end of file
+}
@@ -0,0 +1,176 @@
+use rand::RngCore;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+
+/// 保留的最近事件数量，断线重连时从中补发
+const BUFFER_SIZE: usize = 1024;
+
+/// 广播通道的容量，订阅者积压超过该数量时丢失事件并收到 reset
+const CHANNEL_CAPACITY: usize = 256;
+
+/// 订阅凭证的有效期
+pub const TOKEN_TTL: Duration = Duration::from_secs(60);
+
+/// 事件涉及的数据类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Topic {
+    Favorite,
+    Tag,
+    Category,
+}
+
+/// 数据的变化
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Created,
+    Updated,
+    Deleted,
+}
+
+/// 一条数据变更事件，只推送给数据的所有者
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub id: u64, // 事件ID，单调递增
+    pub user_id: i64,
+    pub topic: Topic,
+    pub action: Action,
+    pub entity_id: i64, // 变化的收藏、标签或分类的ID
+}
+
+impl Event {
+    /// 事件名称，如 `favorite.created`
+    pub fn name(&self) -> &'static str {
+        match (self.topic, self.action) {
+            (Topic::Favorite, Action::Created) => "favorite.created",
+            (Topic::Favorite, Action::Updated) => "favorite.updated",
+            (Topic::Favorite, Action::Deleted) => "favorite.deleted",
+            (Topic::Tag, Action::Created) => "tag.created",
+            (Topic::Tag, Action::Updated) => "tag.updated",
+            (Topic::Tag, Action::Deleted) => "tag.deleted",
+            (Topic::Category, Action::Created) => "category.created",
+            (Topic::Category, Action::Updated) => "category.updated",
+            (Topic::Category, Action::Deleted) => "category.deleted",
+        }
+    }
+}
+
+/// 事务中随收藏一起自动创建的标签和分类，提交后统一发布 created 事件
+#[derive(Debug, Default)]
+pub struct Implicit {
+    pub tags: Vec<i64>,
+    pub categories: Vec<i64>,
+}
+
+/// 最近事件的缓冲区
+struct Buffer {
+    next_id: u64,
+    events: VecDeque<Event>,
+}
+
+/// 事件总线，发布和订阅都在缓冲区的锁内完成，补发与实时推送之间不会遗漏或重复
+struct Bus {
+    sender: broadcast::Sender<Event>,
+    buffer: Mutex<Buffer>,
+    tokens: Mutex<HashMap<String, (i64, Instant)>>, // 订阅凭证到用户ID和过期时间
+}
+
+/// 事件总线的句柄，由 main 创建后放入路由状态，克隆后指向同一个总线
+#[derive(Clone)]
+pub struct EventBus(Arc<Bus>);
+
+impl Default for EventBus {
+    fn default() -> Self {
+        // 以启动时间作为起始ID，服务重启后旧的 Last-Event-ID 会被识别为过期
+        let next_id = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_micros() as u64)
+            .unwrap_or(1);
+        EventBus(Arc::new(Bus {
+            sender: broadcast::channel(CHANNEL_CAPACITY).0,
+            buffer: Mutex::new(Buffer { next_id, events: VecDeque::with_capacity(BUFFER_SIZE) }),
+            tokens: Mutex::new(HashMap::new()),
+        }))
+    }
+}
+
+impl EventBus {
+    /// 发布一条数据变更事件，应在事务提交之后调用
+    pub fn publish(&self, user_id: i64, topic: Topic, action: Action, entity_id: i64) {
+        let mut buffer = self.0.buffer.lock().unwrap_or_else(|err| err.into_inner());
+        let event = Event { id: buffer.next_id, user_id, topic, action, entity_id };
+        buffer.next_id += 1;
+        if buffer.events.len() == BUFFER_SIZE {
+            buffer.events.pop_front();
+        }
+        buffer.events.push_back(event.clone());
+        // 没有订阅者时发送失败，忽略即可
+        let _ = self.0.sender.send(event);
+    }
+
+    /// 为多条同类数据发布事件
+    pub fn publish_all(&self, user_id: i64, topic: Topic, action: Action, entity_ids: impl IntoIterator<Item = i64>) {
+        for entity_id in entity_ids {
+            self.publish(user_id, topic, action, entity_id);
+        }
+    }
+
+    /// 为自动创建的标签和分类发布事件，应在事务提交之后调用
+    pub fn publish_implicit(&self, user_id: i64, implicit: Implicit) {
+        self.publish_all(user_id, Topic::Tag, Action::Created, implicit.tags);
+        self.publish_all(user_id, Topic::Category, Action::Created, implicit.categories);
+    }
+
+    /// 订阅用户的事件，同时返回 `last_event_id` 之后需要补发的事件
+    /// 之后的事件已超出缓冲区或 ID 来自重启前的服务时，补发列表为 None，客户端需重新加载全部数据
+    pub fn subscribe(&self, user_id: i64, last_event_id: Option<u64>) -> (Option<Vec<Event>>, broadcast::Receiver<Event>) {
+        let buffer = self.0.buffer.lock().unwrap_or_else(|err| err.into_inner());
+        let receiver = self.0.sender.subscribe();
+
+        let replay = match last_event_id {
+            None => Some(Vec::new()),
+            Some(last) => {
+                let first = buffer.events.front().map_or(buffer.next_id, |event| event.id);
+                if last >= buffer.next_id || last.saturating_add(1) < first {
+                    None
+                } else {
+                    Some(
+                        buffer
+                            .events
+                            .iter()
+                            .filter(|event| event.id > last && event.user_id == user_id)
+                            .cloned()
+                            .collect(),
+                    )
+                }
+            }
+        };
+
+        (replay, receiver)
+    }
+
+    /// 为用户签发短期有效的订阅凭证
+    /// 浏览器的 EventSource 无法设置请求头，改由查询参数传递凭证，避免 API Key 出现在网址和日志中；
+    /// 有效期内可重复使用，EventSource 断线后可以直接重连
+    pub fn issue_token(&self, user_id: i64) -> String {
+        let mut bytes = [0u8; 24];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let token: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+
+        let now = Instant::now();
+        let mut tokens = self.0.tokens.lock().unwrap_or_else(|err| err.into_inner());
+        tokens.retain(|_, (_, expires_at)| *expires_at > now);
+        tokens.insert(token.clone(), (user_id, now + TOKEN_TTL));
+        token
+    }
+
+    /// 凭证有效时返回签发给的用户ID
+    pub fn verify_token(&self, token: &str) -> Option<i64> {
+        let tokens = self.0.tokens.lock().unwrap_or_else(|err| err.into_inner());
+        tokens
+            .get(token)
+            .filter(|(_, expires_at)| *expires_at > Instant::now())
+            .map(|(user_id, _)| *user_id)
+    }
+}
\ No newline at end of file
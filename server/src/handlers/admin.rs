@@ -0,0 +1,177 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+    http::StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqlitePool;
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use validator::Validate;
+use crate::auth::{self, AdminUser};
+use crate::error::AppError;
+use crate::timezone::{self, ClientTimezone};
+use crate::validation::{self, ValidatedJson};
+
+/// API Key 信息（不含明文）
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ApiKeyInfo {
+    pub id: i64,
+    pub user_id: i64,
+    pub user_name: String,
+    pub key_prefix: String,
+    pub created_at: String,
+    pub revoked_at: Option<String>,
+}
+
+/// 签发 API Key 请求
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct IssueKey {
+    #[validate(custom = "validation::not_blank", length(max = 100, message = "不能超过 100 个字符"))]
+    pub user_name: String,      // 用户名，不存在时自动创建
+    pub is_admin: Option<bool>, // 新建用户是否为管理员
+}
+
+/// 签发结果，明文 Key 仅在此返回一次
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct IssuedKey {
+    pub id: i64,
+    pub user_id: i64,
+    pub user_name: String,
+    pub key: String,
+}
+
+/// 获取 API Key 列表
+#[utoipa::path(
+    get,
+    path = "/api/admin/keys",
+    tag = "admin",
+    params(
+        ("tz" = Option<String>, Query, description = "IANA 时区名，如 Asia/Shanghai，也可通过 X-Timezone 请求头指定，默认为 UTC")
+    ),
+    responses(
+        (status = 200, description = "成功获取 API Key 列表", body = Vec<ApiKeyInfo>),
+        (status = 401, description = "未认证"),
+        (status = 403, description = "需要管理员权限"),
+        (status = 500, description = "服务器内部错误")
+    )
+)]
+pub async fn list_keys(
+    _admin: AdminUser,
+    tz: ClientTimezone,
+    State(db): State<SqlitePool>,
+) -> Result<Json<Vec<ApiKeyInfo>>, AppError> {
+    let keys = sqlx::query_as::<_, ApiKeyInfo>(
+        "SELECT k.id, k.user_id, u.name as user_name, k.key_prefix, k.created_at, k.revoked_at
+         FROM api_keys k
+         JOIN users u ON u.id = k.user_id
+         ORDER BY k.id"
+    )
+    .fetch_all(&db)
+    .await
+    .map_err(AppError::Database)?;
+
+    let keys = keys
+        .into_iter()
+        .map(|mut key| {
+            tz.apply(&mut key.created_at);
+            if let Some(revoked_at) = &mut key.revoked_at {
+                tz.apply(revoked_at);
+            }
+            key
+        })
+        .collect();
+
+    Ok(Json(keys))
+}
+
+/// 签发 API Key
+#[utoipa::path(
+    post,
+    path = "/api/admin/keys",
+    tag = "admin",
+    request_body = IssueKey,
+    responses(
+        (status = 201, description = "成功签发 API Key", body = IssuedKey),
+        (status = 400, description = "无效的请求"),
+        (status = 401, description = "未认证"),
+        (status = 403, description = "需要管理员权限"),
+        (status = 500, description = "服务器内部错误")
+    )
+)]
+pub async fn issue_key(
+    AdminUser(admin): AdminUser,
+    State(db): State<SqlitePool>,
+    ValidatedJson(payload): ValidatedJson<IssueKey>,
+) -> Result<(StatusCode, Json<IssuedKey>), AppError> {
+    let user_name = payload.user_name.trim();
+
+    sqlx::query("INSERT OR IGNORE INTO users (name, is_admin, created_at) VALUES (?, ?, ?)")
+        .bind(user_name)
+        .bind(payload.is_admin.unwrap_or(false))
+        .bind(timezone::now())
+        .execute(&db)
+        .await
+        .map_err(AppError::Database)?;
+
+    let user_id: i64 = sqlx::query_scalar("SELECT id FROM users WHERE name = ?")
+        .bind(user_name)
+        .fetch_one(&db)
+        .await
+        .map_err(AppError::Database)?;
+
+    let (id, key) = auth::issue_key(&db, user_id)
+        .await
+        .map_err(AppError::Database)?;
+
+    tracing::info!("API key {} issued for user {} by {}", id, user_name, admin.name);
+
+    Ok((
+        StatusCode::CREATED,
+        Json(IssuedKey {
+            id,
+            user_id,
+            user_name: user_name.to_string(),
+            key,
+        }),
+    ))
+}
+
+/// 吊销 API Key
+#[utoipa::path(
+    delete,
+    path = "/api/admin/keys/{id}",
+    tag = "admin",
+    params(
+        ("id" = i64, Path, description = "API Key ID")
+    ),
+    responses(
+        (status = 200, description = "成功吊销 API Key"),
+        (status = 401, description = "未认证"),
+        (status = 403, description = "需要管理员权限"),
+        (status = 404, description = "API Key 不存在或已吊销"),
+        (status = 500, description = "服务器内部错误")
+    )
+)]
+pub async fn revoke_key(
+    AdminUser(admin): AdminUser,
+    Path(id): Path<i64>,
+    State(db): State<SqlitePool>,
+) -> Result<StatusCode, AppError> {
+    let result = sqlx::query(
+        "UPDATE api_keys SET revoked_at = ? WHERE id = ? AND revoked_at IS NULL"
+    )
+    .bind(timezone::now())
+    .bind(id)
+    .execute(&db)
+    .await
+    .map_err(AppError::Database)?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    tracing::info!("API key {} revoked by {}", id, admin.name);
+
+    Ok(StatusCode::OK)
+}
@@ -0,0 +1,275 @@
+use axum::{extract::State, Json};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{SqliteConnection, SqlitePool};
+use utoipa::ToSchema;
+use validator::{Validate, ValidationErrors};
+use crate::audit::{self, Entity};
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::events::{Action, EventBus, Implicit, Topic};
+use crate::handlers::favorite::{
+    add_favorite_tags, ensure_category_owned, favorite_snapshot, remove_favorite_tags, ListFavoriteQuery,
+};
+use crate::timezone;
+use crate::validation::{self, ValidatedJson};
+
+/// 单次批量操作最多涉及的收藏数量
+const MAX_BATCH_SIZE: usize = 1000;
+
+/// 批量操作的动作
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum BatchAction {
+    Delete,                                    // 移入回收站
+    SetCategory { category_id: Option<i64> },  // 设置分类，null 表示未分类
+    AddTags { tags: Vec<String> },             // 追加标签
+    RemoveTags { tags: Vec<String> },          // 移除标签
+    Archive,                                   // 归档
+    Unarchive,                                 // 取消归档
+}
+
+/// 批量操作请求，`ids` 与 `filter` 二选一
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BatchRequest {
+    pub ids: Option<Vec<i64>>, // 收藏ID列表
+    pub filter: Option<String>, // 与收藏列表相同的查询字符串，如 `category_id=2&tags_any=1,3`
+    #[serde(flatten)]
+    pub action: BatchAction,
+}
+
+impl BatchAction {
+    /// 审计日志中记录的动作名称
+    fn audit_action(&self) -> &'static str {
+        match self {
+            BatchAction::Delete => "delete",
+            BatchAction::SetCategory { .. } | BatchAction::AddTags { .. } | BatchAction::RemoveTags { .. } => "update",
+            BatchAction::Archive => "archive",
+            BatchAction::Unarchive => "unarchive",
+        }
+    }
+}
+
+impl Validate for BatchRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+
+        match (&self.ids, &self.filter) {
+            (Some(_), Some(_)) | (None, None) => {
+                errors.add("ids", validation::error("target", "ids 和 filter 必须且只能提供一个"));
+            }
+            (Some(ids), None) if ids.is_empty() || ids.len() > MAX_BATCH_SIZE => {
+                errors.add(
+                    "ids",
+                    validation::error("ids_count", format!("需要 1 到 {} 个收藏ID", MAX_BATCH_SIZE)),
+                );
+            }
+            (None, Some(filter)) if filter.trim().is_empty() => {
+                errors.add("filter", validation::error("blank", "不能为空"));
+            }
+            _ => {}
+        }
+
+        if let BatchAction::AddTags { tags } | BatchAction::RemoveTags { tags } = &self.action {
+            if tags.iter().all(|tag| tag.trim().is_empty()) {
+                errors.add("tags", validation::error("blank", "不能为空"));
+            } else if let Err(err) = validation::tag_list(tags) {
+                errors.add("tags", err);
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// 单个收藏的处理状态
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStatus {
+    Ok,       // 已处理
+    NotFound, // 收藏不存在或不属于当前用户
+}
+
+/// 单个收藏的处理结果
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BatchItemResult {
+    pub id: i64,
+    pub status: BatchStatus,
+}
+
+/// 批量操作结果
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BatchResponse {
+    pub succeeded: usize,
+    pub not_found: usize,
+    pub results: Vec<BatchItemResult>,
+}
+
+/// 解析操作目标，返回 (请求的ID, 属于当前用户的ID)
+async fn resolve_targets(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+    request: &BatchRequest,
+) -> Result<(Vec<i64>, Vec<i64>), AppError> {
+    if let Some(ids) = &request.ids {
+        let mut requested = ids.clone();
+        requested.sort_unstable();
+        requested.dedup();
+
+        let sql = format!(
+            "SELECT id FROM favorites WHERE user_id = ? AND deleted_at IS NULL AND id IN ({})",
+            vec!["?"; requested.len()].join(", ")
+        );
+        let mut query = sqlx::query_scalar::<_, i64>(&sql).bind(user_id);
+        for id in &requested {
+            query = query.bind(id);
+        }
+        let owned = query.fetch_all(&mut *conn).await.map_err(AppError::Database)?;
+        return Ok((requested, owned));
+    }
+
+    // 按筛选条件选取，复用收藏列表的筛选逻辑
+    let filter = request.filter.as_deref().unwrap_or_default();
+    let params: ListFavoriteQuery = serde_urlencoded::from_str(filter)
+        .map_err(|err| AppError::field("filter", err.to_string()))?;
+    let (conditions, params_values) = params.conditions(user_id);
+
+    let sql = format!("SELECT f.id FROM favorites f WHERE {} ORDER BY f.id LIMIT ?", conditions);
+    let mut query = sqlx::query_scalar::<_, i64>(&sql);
+    for param in &params_values {
+        query = query.bind(param);
+    }
+    let owned = query
+        .bind(MAX_BATCH_SIZE as i64 + 1)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::Database)?;
+    if owned.len() > MAX_BATCH_SIZE {
+        return Err(AppError::field(
+            "filter",
+            format!("匹配的收藏超过 {} 条，请缩小筛选范围", MAX_BATCH_SIZE),
+        ));
+    }
+    Ok((owned.clone(), owned))
+}
+
+/// 对单个收藏执行动作
+async fn apply(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+    id: i64,
+    action: &BatchAction,
+    now: &str,
+    implicit: &mut Implicit,
+) -> Result<(), sqlx::Error> {
+    match action {
+        BatchAction::Delete => {
+            sqlx::query("UPDATE favorites SET deleted_at = ? WHERE id = ?")
+                .bind(now)
+                .bind(id)
+                .execute(&mut *conn)
+                .await?;
+            return Ok(());
+        }
+        BatchAction::SetCategory { category_id } => {
+            sqlx::query("UPDATE favorites SET category_id = ? WHERE id = ?")
+                .bind(category_id)
+                .bind(id)
+                .execute(&mut *conn)
+                .await?;
+        }
+        BatchAction::AddTags { tags } => add_favorite_tags(&mut *conn, user_id, id, tags, implicit).await?,
+        BatchAction::RemoveTags { tags } => remove_favorite_tags(&mut *conn, user_id, id, tags).await?,
+        BatchAction::Archive => {
+            sqlx::query("UPDATE favorites SET archived_at = COALESCE(archived_at, ?) WHERE id = ?")
+                .bind(now)
+                .bind(id)
+                .execute(&mut *conn)
+                .await?;
+        }
+        BatchAction::Unarchive => {
+            sqlx::query("UPDATE favorites SET archived_at = NULL WHERE id = ?")
+                .bind(id)
+                .execute(&mut *conn)
+                .await?;
+        }
+    }
+
+    sqlx::query("UPDATE favorites SET updated_at = ? WHERE id = ?")
+        .bind(now)
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+/// 批量操作收藏
+/// 所有修改在同一事务中完成，不存在的收藏在结果中标记为 not_found
+#[utoipa::path(
+    post,
+    path = "/api/favorites/batch",
+    tag = "favorites",
+    request_body = BatchRequest,
+    responses(
+        (status = 200, description = "逐个收藏的处理结果", body = BatchResponse),
+        (status = 400, description = "分类不存在"),
+        (status = 422, description = "请求参数无效或匹配的收藏过多"),
+        (status = 500, description = "服务器内部错误")
+    )
+)]
+pub async fn batch_favorites(
+    user: AuthUser,
+    State(db): State<SqlitePool>,
+    State(events): State<EventBus>,
+    ValidatedJson(request): ValidatedJson<BatchRequest>,
+) -> Result<Json<BatchResponse>, AppError> {
+    let now = timezone::now();
+
+    let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+    if let BatchAction::SetCategory { category_id } = &request.action {
+        ensure_category_owned(&mut tx, user.id, *category_id).await?;
+    }
+
+    let (requested, owned) = resolve_targets(&mut tx, user.id, &request).await?;
+
+    let mut results = Vec::with_capacity(requested.len());
+    let mut implicit = Implicit::default();
+    for id in requested {
+        let status = if owned.contains(&id) {
+            let before = favorite_snapshot(&mut tx, id).await.map_err(AppError::Database)?;
+            apply(&mut tx, user.id, id, &request.action, &now, &mut implicit)
+                .await
+                .map_err(AppError::Database)?;
+            let after = favorite_snapshot(&mut tx, id).await.map_err(AppError::Database)?;
+            audit::record(&mut tx, user.id, Entity::Favorite, id, request.action.audit_action(), before, after)
+                .await
+                .map_err(AppError::Database)?;
+            BatchStatus::Ok
+        } else {
+            BatchStatus::NotFound
+        };
+        results.push(BatchItemResult { id, status });
+    }
+
+    tx.commit().await.map_err(AppError::Database)?;
+
+    events.publish_implicit(user.id, implicit);
+    let action = match request.action {
+        BatchAction::Delete => Action::Deleted,
+        _ => Action::Updated,
+    };
+    let changed = results.iter().filter(|r| r.status == BatchStatus::Ok).map(|r| r.id);
+    events.publish_all(user.id, Topic::Favorite, action, changed);
+
+    let succeeded = results.iter().filter(|r| r.status == BatchStatus::Ok).count();
+    Ok(Json(BatchResponse {
+        succeeded,
+        not_found: results.len() - succeeded,
+        results,
+    }))
+}
@@ -1,23 +1,162 @@
 use axum::{
-    extract::{Path, State},
-    response::IntoResponse,
+    extract::{Path, Query, State},
     Json,
     http::StatusCode,
 };
 use serde::{Deserialize, Serialize};
-use sqlx::{sqlite::SqlitePool, FromRow};
+use sqlx::{sqlite::{SqliteConnection, SqlitePool}, FromRow};
+use std::collections::HashMap;
 use utoipa::ToSchema;
+use validator::Validate;
+use crate::audit::{self, Entity};
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::events::{Action, EventBus, Topic};
+use crate::handlers::favorite::favorite_snapshot;
+use crate::timezone;
+use crate::validation::{self, ValidatedJson};
 
-#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+/// 以某个分类为根的子树（含自身）的ID，参数为根分类ID
+pub(crate) const CATEGORY_SUBTREE_IDS: &str = "WITH RECURSIVE subtree(id) AS (
+        SELECT ?
+        UNION ALL
+        SELECT c.id FROM categories c JOIN subtree s ON c.parent_id = s.id
+    ) SELECT id FROM subtree";
+
+#[derive(Debug, Serialize, Deserialize, FromRow, Validate, ToSchema)]
 pub struct Category {
     pub id: i64,
+    #[validate(custom = "validation::not_blank", length(max = 100, message = "不能超过 100 个字符"))]
     pub name: String,
+    pub parent_id: Option<i64>, // 上级分类ID，顶级分类为 null
 }
 
 // 添加新的结构体用于创建分类
-#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct CreateCategory {
+    #[validate(custom = "validation::not_blank", length(max = 100, message = "不能超过 100 个字符"))]
     pub name: String,
+    pub parent_id: Option<i64>, // 上级分类ID（可选）
+}
+
+/// 移动分类请求
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct MoveCategory {
+    pub parent_id: Option<i64>, // 新的上级分类ID，为 null 时移到顶级
+}
+
+/// 删除非空分类时对其中收藏的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DeleteStrategy {
+    Reassign,     // 收藏移到 target 分类
+    Uncategorize, // 收藏变为未分类
+    Cascade,      // 连同子分类一起删除，其中的收藏移入回收站
+}
+
+/// 删除分类查询参数
+#[derive(Debug, Deserialize)]
+pub struct DeleteCategoryQuery {
+    pub strategy: Option<String>, // 处理方式，取值见 DeleteStrategy
+    pub target: Option<i64>, // strategy=reassign 时的目标分类ID
+}
+
+/// 删除分类的结果
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DeleteCategoryResult {
+    pub strategy: Option<DeleteStrategy>,
+    pub categories_deleted: u64,      // 删除的分类数量
+    pub favorites_reassigned: u64,    // 移到目标分类的收藏数量
+    pub favorites_uncategorized: u64, // 变为未分类的收藏数量
+    pub favorites_deleted: u64,       // 移入回收站的收藏数量
+}
+
+/// 分类树节点
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CategoryNode {
+    pub id: i64,
+    pub name: String,
+    pub parent_id: Option<i64>,
+    pub favorite_count: i64, // 直接属于该分类的收藏数量
+    pub children: Vec<CategoryNode>,
+}
+
+/// 分类树查询结果的单行
+#[derive(FromRow)]
+struct CategoryRow {
+    id: i64,
+    name: String,
+    parent_id: Option<i64>,
+    favorite_count: i64,
+}
+
+/// 确认分类属于当前用户，返回其上级分类ID
+async fn fetch_parent_id(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+    id: i64,
+) -> Result<Option<i64>, AppError> {
+    sqlx::query_scalar::<_, Option<i64>>("SELECT parent_id FROM categories WHERE id = ? AND user_id = ?")
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(AppError::Database)?
+        .ok_or(AppError::NotFound)
+}
+
+/// 查询当前用户的单个分类
+async fn fetch_category(conn: &mut SqliteConnection, user_id: i64, id: i64) -> Result<Category, AppError> {
+    sqlx::query_as::<_, Category>("SELECT id, name, parent_id FROM categories WHERE id = ? AND user_id = ?")
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(AppError::Database)?
+        .ok_or(AppError::NotFound)
+}
+
+/// 确认同级分类中没有同名分类
+async fn ensure_sibling_name_free(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+    parent_id: Option<i64>,
+    name: &str,
+    exclude_id: Option<i64>,
+) -> Result<(), AppError> {
+    let exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM categories
+         WHERE user_id = ? AND parent_id IS ? AND name = ? AND id IS NOT ?)"
+    )
+    .bind(user_id)
+    .bind(parent_id)
+    .bind(name)
+    .bind(exclude_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(AppError::Database)?;
+
+    if exists {
+        return Err(AppError::Conflict(format!("同级已存在名为 {} 的分类", name)));
+    }
+
+    Ok(())
+}
+
+/// 将扁平的分类列表组装为树
+fn build_tree(parent_id: Option<i64>, children: &mut HashMap<Option<i64>, Vec<CategoryRow>>) -> Vec<CategoryNode> {
+    children
+        .remove(&parent_id)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|row| CategoryNode {
+            children: build_tree(Some(row.id), children),
+            id: row.id,
+            name: row.name,
+            parent_id: row.parent_id,
+            favorite_count: row.favorite_count,
+        })
+        .collect()
 }
 
 /// 获取分类列表
@@ -30,14 +169,17 @@ pub struct CreateCategory {
         (status = 500, description = "服务器内部错误")
     )
 )]
-pub async fn list_categories(State(db): State<SqlitePool>) -> impl IntoResponse {
-    match sqlx::query_as::<_, Category>("SELECT id, name FROM categories")
+pub async fn list_categories(
+    user: AuthUser,
+    State(db): State<SqlitePool>,
+) -> Result<Json<Vec<Category>>, AppError> {
+    let categories = sqlx::query_as::<_, Category>("SELECT id, name, parent_id FROM categories WHERE user_id = ?")
+        .bind(user.id)
         .fetch_all(&db)
         .await
-    {
-        Ok(categories) => Json(categories).into_response(),
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
-    }
+        .map_err(AppError::Database)?;
+
+    Ok(Json(categories))
 }
 
 /// 创建分类
@@ -48,21 +190,156 @@ pub async fn list_categories(State(db): State<SqlitePool>) -> impl IntoResponse
     request_body = CreateCategory,
     responses(
         (status = 201, description = "成功创建分类"),
+        (status = 400, description = "上级分类不存在"),
+        (status = 409, description = "同级已有同名分类"),
+        (status = 422, description = "分类名称为空或过长"),
         (status = 500, description = "服务器内部错误")
     )
 )]
 pub async fn create_category(
+    user: AuthUser,
     State(db): State<SqlitePool>,
-    Json(category): Json<CreateCategory>,
-) -> impl IntoResponse {
-    match sqlx::query("INSERT INTO categories (name) VALUES (?)")
-        .bind(&category.name)
-        .execute(&db)
+    State(events): State<EventBus>,
+    ValidatedJson(category): ValidatedJson<CreateCategory>,
+) -> Result<StatusCode, AppError> {
+    let name = category.name.trim();
+    let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+    if let Some(parent_id) = category.parent_id {
+        fetch_parent_id(&mut tx, user.id, parent_id)
+            .await
+            .map_err(|_| AppError::BadRequest(format!("上级分类 {} 不存在", parent_id)))?;
+    }
+
+    let result = sqlx::query("INSERT INTO categories (user_id, parent_id, name) VALUES (?, ?, ?)")
+        .bind(user.id)
+        .bind(category.parent_id)
+        .bind(name)
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| AppError::unique_conflict(err, format!("同级已存在名为 {} 的分类", name)))?;
+
+    let after = Category {
+        id: result.last_insert_rowid(),
+        name: name.to_string(),
+        parent_id: category.parent_id,
+    };
+    audit::record(&mut tx, user.id, Entity::Category, after.id, "create", None, audit::snapshot(&after))
         .await
-    {
-        Ok(_) => StatusCode::CREATED.into_response(),
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        .map_err(AppError::Database)?;
+
+    tx.commit().await.map_err(AppError::Database)?;
+
+    events.publish(user.id, Topic::Category, Action::Created, after.id);
+
+    Ok(StatusCode::CREATED)
+}
+
+/// 获取分类树
+#[utoipa::path(
+    get,
+    path = "/api/categories/tree",
+    tag = "categories",
+    responses(
+        (status = 200, description = "成功获取分类树", body = Vec<CategoryNode>),
+        (status = 500, description = "服务器内部错误")
+    )
+)]
+pub async fn get_category_tree(
+    user: AuthUser,
+    State(db): State<SqlitePool>,
+) -> Result<Json<Vec<CategoryNode>>, AppError> {
+    let rows = sqlx::query_as::<_, CategoryRow>(
+        "SELECT c.id, c.name, c.parent_id,
+                (SELECT COUNT(*) FROM favorites f
+                  WHERE f.category_id = c.id AND f.deleted_at IS NULL) as favorite_count
+         FROM categories c
+         WHERE c.user_id = ?
+         ORDER BY c.name, c.id"
+    )
+    .bind(user.id)
+    .fetch_all(&db)
+    .await
+    .map_err(AppError::Database)?;
+
+    let mut children: HashMap<Option<i64>, Vec<CategoryRow>> = HashMap::new();
+    for row in rows {
+        children.entry(row.parent_id).or_default().push(row);
     }
+
+    Ok(Json(build_tree(None, &mut children)))
+}
+
+/// 移动分类
+#[utoipa::path(
+    post,
+    path = "/api/categories/{id}/move",
+    tag = "categories",
+    params(
+        ("id" = i64, Path, description = "分类ID")
+    ),
+    request_body = MoveCategory,
+    responses(
+        (status = 200, description = "成功移动分类", body = Category),
+        (status = 400, description = "上级分类不存在"),
+        (status = 404, description = "分类不存在"),
+        (status = 409, description = "同级已有同名分类"),
+        (status = 422, description = "上级分类是自身或其子分类，移动后会形成循环"),
+        (status = 500, description = "服务器内部错误")
+    )
+)]
+pub async fn move_category(
+    user: AuthUser,
+    Path(id): Path<i64>,
+    State(db): State<SqlitePool>,
+    State(events): State<EventBus>,
+    ValidatedJson(payload): ValidatedJson<MoveCategory>,
+) -> Result<Json<Category>, AppError> {
+    let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+    let before = fetch_category(&mut tx, user.id, id).await?;
+
+    if let Some(parent_id) = payload.parent_id {
+        fetch_parent_id(&mut tx, user.id, parent_id)
+            .await
+            .map_err(|_| AppError::BadRequest(format!("上级分类 {} 不存在", parent_id)))?;
+
+        // 新的上级分类不能是自身或其子孙，否则会形成循环
+        let sql = format!("SELECT EXISTS(SELECT 1 FROM ({}) WHERE id = ?)", CATEGORY_SUBTREE_IDS);
+        let cyclic: bool = sqlx::query_scalar(&sql)
+            .bind(id)
+            .bind(parent_id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(AppError::Database)?;
+        if cyclic {
+            return Err(AppError::field("parent_id", "不能将分类移动到自身或其子分类下"));
+        }
+    }
+
+    ensure_sibling_name_free(&mut tx, user.id, payload.parent_id, &before.name, Some(id)).await?;
+
+    sqlx::query("UPDATE categories SET parent_id = ? WHERE id = ?")
+        .bind(payload.parent_id)
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::Database)?;
+
+    let after = Category {
+        id,
+        name: before.name.clone(),
+        parent_id: payload.parent_id,
+    };
+    audit::record(&mut tx, user.id, Entity::Category, id, "move", audit::snapshot(&before), audit::snapshot(&after))
+        .await
+        .map_err(AppError::Database)?;
+
+    tx.commit().await.map_err(AppError::Database)?;
+
+    events.publish(user.id, Topic::Category, Action::Updated, id);
+
+    Ok(Json(after))
 }
 
 /// 获取单个分类
@@ -80,18 +357,19 @@ pub async fn create_category(
     )
 )]
 pub async fn get_category(
+    user: AuthUser,
     Path(id): Path<i64>,
     State(db): State<SqlitePool>,
-) -> impl IntoResponse {
-    match sqlx::query_as::<_, Category>("SELECT id, name FROM categories WHERE id = ?")
+) -> Result<Json<Category>, AppError> {
+    let category = sqlx::query_as::<_, Category>("SELECT id, name, parent_id FROM categories WHERE id = ? AND user_id = ?")
         .bind(id)
+        .bind(user.id)
         .fetch_optional(&db)
         .await
-    {
-        Ok(Some(category)) => Json(category).into_response(),
-        Ok(None) => StatusCode::NOT_FOUND.into_response(),
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
-    }
+        .map_err(AppError::Database)?
+        .ok_or(AppError::NotFound)?;
+
+    Ok(Json(category))
 }
 
 /// 更新分类
@@ -106,23 +384,45 @@ pub async fn get_category(
     responses(
         (status = 200, description = "成功更新分类"),
         (status = 404, description = "分类不存在"),
+        (status = 409, description = "同级已有同名分类"),
+        (status = 422, description = "分类名称为空或过长"),
         (status = 500, description = "服务器内部错误")
     )
 )]
 pub async fn update_category(
+    user: AuthUser,
     Path(id): Path<i64>,
     State(db): State<SqlitePool>,
-    Json(category): Json<Category>,
-) -> impl IntoResponse {
-    match sqlx::query("UPDATE categories SET name = ? WHERE id = ?")
-        .bind(&category.name)
+    State(events): State<EventBus>,
+    ValidatedJson(category): ValidatedJson<Category>,
+) -> Result<StatusCode, AppError> {
+    let name = category.name.trim();
+    let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+    let before = fetch_category(&mut tx, user.id, id).await?;
+
+    sqlx::query("UPDATE categories SET name = ? WHERE id = ? AND user_id = ?")
+        .bind(name)
         .bind(id)
-        .execute(&db)
+        .bind(user.id)
+        .execute(&mut *tx)
         .await
-    {
-        Ok(_) => StatusCode::OK.into_response(),
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
-    }
+        .map_err(|err| AppError::unique_conflict(err, format!("同级已存在名为 {} 的分类", name)))?;
+
+    let after = Category {
+        id,
+        name: name.to_string(),
+        parent_id: before.parent_id,
+    };
+    audit::record(&mut tx, user.id, Entity::Category, id, "update", audit::snapshot(&before), audit::snapshot(&after))
+        .await
+        .map_err(AppError::Database)?;
+
+    tx.commit().await.map_err(AppError::Database)?;
+
+    events.publish(user.id, Topic::Category, Action::Updated, id);
+
+    Ok(StatusCode::OK)
 }
 
 /// 删除分类
@@ -131,24 +431,256 @@ pub async fn update_category(
     path = "/api/categories/{id}",
     tag = "categories",
     params(
-        ("id" = i64, Path, description = "分类ID")
+        ("id" = i64, Path, description = "分类ID"),
+        ("strategy" = Option<DeleteStrategy>, Query, description = "分类不为空时的处理方式：reassign 移到 target 分类、uncategorize 变为未分类、cascade 连同子分类一起删除并将其中的收藏移入回收站"),
+        ("target" = Option<i64>, Query, description = "strategy=reassign 时收藏移入的分类ID")
     ),
     responses(
-        (status = 200, description = "成功删除分类"),
+        (status = 200, description = "成功删除分类，除 cascade 外子分类移到上一级", body = DeleteCategoryResult),
+        (status = 400, description = "缺少或无效的 target"),
         (status = 404, description = "分类不存在"),
+        (status = 409, description = "分类不为空且未指定 strategy，或子分类与上一级分类重名"),
+        (status = 422, description = "未知的 strategy"),
         (status = 500, description = "服务器内部错误")
     )
 )]
 pub async fn delete_category(
+    user: AuthUser,
     Path(id): Path<i64>,
+    Query(params): Query<DeleteCategoryQuery>,
     State(db): State<SqlitePool>,
-) -> impl IntoResponse {
-    match sqlx::query("DELETE FROM categories WHERE id = ?")
+    State(events): State<EventBus>,
+) -> Result<Json<DeleteCategoryResult>, AppError> {
+    // 处理方式写错时直接报错，避免误按空分类处理
+    let strategy = match params.strategy.as_deref() {
+        None | Some("") => None,
+        Some("reassign") => Some(DeleteStrategy::Reassign),
+        Some("uncategorize") => Some(DeleteStrategy::Uncategorize),
+        Some("cascade") => Some(DeleteStrategy::Cascade),
+        Some(other) => return Err(AppError::field("strategy", format!("未知的处理方式: {}", other))),
+    };
+
+    let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+    let before = fetch_category(&mut tx, user.id, id).await?;
+    let parent_id = before.parent_id;
+
+    let mut result = DeleteCategoryResult {
+        strategy,
+        categories_deleted: 0,
+        favorites_reassigned: 0,
+        favorites_uncategorized: 0,
+        favorites_deleted: 0,
+    };
+
+    // 分类下的收藏和子分类会随之改变，提交后逐个发布变更事件
+    let moved_favorites: Vec<i64> = sqlx::query_scalar(
+        "SELECT id FROM favorites WHERE category_id = ? AND deleted_at IS NULL"
+    )
+    .bind(id)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(AppError::Database)?;
+    let children: Vec<i64> = sqlx::query_scalar("SELECT id FROM categories WHERE parent_id = ?")
         .bind(id)
-        .execute(&db)
+        .fetch_all(&mut *tx)
         .await
-    {
-        Ok(_) => StatusCode::OK.into_response(),
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        .map_err(AppError::Database)?;
+
+    match strategy {
+        None => {
+            // 未指定处理方式时只允许删除空分类，回收站中的收藏不计在内
+            let (favorites, children): (i64, i64) = sqlx::query_as(
+                "SELECT (SELECT COUNT(*) FROM favorites WHERE category_id = ? AND deleted_at IS NULL),
+                        (SELECT COUNT(*) FROM categories WHERE parent_id = ?)"
+            )
+            .bind(id)
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(AppError::Database)?;
+            if favorites > 0 || children > 0 {
+                return Err(AppError::Conflict(format!(
+                    "分类下还有 {} 条收藏和 {} 个子分类，请指定 strategy",
+                    favorites, children
+                )));
+            }
+
+            // 回收站中的收藏改为未分类，恢复后不再指向已删除的分类
+            sqlx::query("UPDATE favorites SET category_id = NULL WHERE category_id = ?")
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .map_err(AppError::Database)?;
+        }
+        Some(DeleteStrategy::Reassign) => {
+            let target = params
+                .target
+                .filter(|target| *target != id)
+                .ok_or_else(|| AppError::BadRequest("reassign 需要指定其他分类作为 target".to_string()))?;
+            fetch_parent_id(&mut tx, user.id, target)
+                .await
+                .map_err(|_| AppError::BadRequest(format!("目标分类 {} 不存在", target)))?;
+
+            result.favorites_reassigned = sqlx::query(
+                "UPDATE favorites SET category_id = ? WHERE category_id = ? AND deleted_at IS NULL"
+            )
+            .bind(target)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Database)?
+            .rows_affected();
+
+            // 回收站中的收藏一并移动，但不计入数量
+            sqlx::query("UPDATE favorites SET category_id = ? WHERE category_id = ?")
+                .bind(target)
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .map_err(AppError::Database)?;
+        }
+        Some(DeleteStrategy::Uncategorize) => {
+            result.favorites_uncategorized = sqlx::query(
+                "UPDATE favorites SET category_id = NULL WHERE category_id = ? AND deleted_at IS NULL"
+            )
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Database)?
+            .rows_affected();
+
+            // 回收站中的收藏一并改为未分类，但不计入数量
+            sqlx::query("UPDATE favorites SET category_id = NULL WHERE category_id = ?")
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .map_err(AppError::Database)?;
+        }
+        Some(DeleteStrategy::Cascade) => {
+            // 被删除的分类和收藏先写入审计日志
+            let sql = format!(
+                "SELECT id, name, parent_id FROM categories WHERE id IN ({})",
+                CATEGORY_SUBTREE_IDS
+            );
+            let categories = sqlx::query_as::<_, Category>(&sql)
+                .bind(id)
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(AppError::Database)?;
+            let sql = format!(
+                "SELECT id FROM favorites WHERE category_id IN ({}) AND deleted_at IS NULL",
+                CATEGORY_SUBTREE_IDS
+            );
+            let favorite_ids: Vec<i64> = sqlx::query_scalar(&sql)
+                .bind(id)
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(AppError::Database)?;
+            let mut befores = Vec::with_capacity(favorite_ids.len());
+            for &favorite_id in &favorite_ids {
+                befores.push((favorite_id, favorite_snapshot(&mut tx, favorite_id).await.map_err(AppError::Database)?));
+            }
+            for category in &categories {
+                audit::record(&mut tx, user.id, Entity::Category, category.id, "delete", audit::snapshot(category), None)
+                    .await
+                    .map_err(AppError::Database)?;
+            }
+
+            // 子树中的收藏移入回收站，与删除收藏相同
+            let sql = format!(
+                "UPDATE favorites SET deleted_at = ? WHERE category_id IN ({}) AND deleted_at IS NULL",
+                CATEGORY_SUBTREE_IDS
+            );
+            result.favorites_deleted = sqlx::query(&sql)
+                .bind(timezone::now())
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .map_err(AppError::Database)?
+                .rows_affected();
+
+            // 包括原本就在回收站中的收藏，全部改为未分类，恢复后不再指向已删除的分类
+            let sql = format!("UPDATE favorites SET category_id = NULL WHERE category_id IN ({})", CATEGORY_SUBTREE_IDS);
+            sqlx::query(&sql)
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .map_err(AppError::Database)?;
+
+            for (favorite_id, before) in befores {
+                let after = favorite_snapshot(&mut tx, favorite_id).await.map_err(AppError::Database)?;
+                audit::record(&mut tx, user.id, Entity::Favorite, favorite_id, "delete", before, after)
+                    .await
+                    .map_err(AppError::Database)?;
+            }
+
+            let sql = format!("DELETE FROM categories WHERE id IN ({})", CATEGORY_SUBTREE_IDS);
+            result.categories_deleted = sqlx::query(&sql)
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .map_err(AppError::Database)?
+                .rows_affected();
+
+            tx.commit().await.map_err(AppError::Database)?;
+
+            events.publish_all(user.id, Topic::Favorite, Action::Deleted, favorite_ids);
+            events.publish_all(user.id, Topic::Category, Action::Deleted, categories.iter().map(|c| c.id));
+
+            return Ok(Json(result));
+        }
+    }
+
+    // 子分类将移到上一级，不能与上一级中已有的分类重名
+    let conflict: Option<String> = sqlx::query_scalar(
+        "SELECT c.name FROM categories c
+         WHERE c.parent_id = ? AND EXISTS(
+             SELECT 1 FROM categories s
+             WHERE s.user_id = c.user_id AND s.parent_id IS ? AND s.name = c.name AND s.id != ?
+         )
+         LIMIT 1"
+    )
+    .bind(id)
+    .bind(parent_id)
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(AppError::Database)?;
+    if let Some(name) = conflict {
+        return Err(AppError::Conflict(format!("上一级已存在名为 {} 的分类", name)));
     }
-} 
\ No newline at end of file
+
+    // 先删除分类再移动子分类，避免子分类与自身重名；外键检查推迟到提交时
+    sqlx::query("PRAGMA defer_foreign_keys = ON")
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::Database)?;
+
+    result.categories_deleted = sqlx::query("DELETE FROM categories WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::Database)?
+        .rows_affected();
+
+    // 子分类移到被删除分类的上一级
+    sqlx::query("UPDATE categories SET parent_id = ? WHERE parent_id = ?")
+        .bind(parent_id)
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::Database)?;
+
+    audit::record(&mut tx, user.id, Entity::Category, id, "delete", audit::snapshot(&before), None)
+        .await
+        .map_err(AppError::Database)?;
+
+    tx.commit().await.map_err(AppError::Database)?;
+
+    events.publish_all(user.id, Topic::Favorite, Action::Updated, moved_favorites);
+    events.publish_all(user.id, Topic::Category, Action::Updated, children);
+    events.publish(user.id, Topic::Category, Action::Deleted, id);
+
+    Ok(Json(result))
+}
@@ -0,0 +1,113 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqlitePool;
+use sqlx::FromRow;
+use std::collections::HashMap;
+use utoipa::ToSchema;
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::timezone::ClientTimezone;
+
+/// 每个域名返回的常用标签数量上限
+const MAX_TOP_TAGS: usize = 20;
+
+/// 域名统计查询参数
+#[derive(Debug, Deserialize)]
+pub struct DomainQuery {
+    pub top_tags: Option<usize>, // 每个域名返回的常用标签数量，默认为5
+    pub limit: Option<i64>,      // 最多返回的域名数量，默认全部返回
+}
+
+/// 标签及其使用次数
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TagCount {
+    pub name: String,
+    pub count: i64,
+}
+
+/// 单个域名的收藏统计
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DomainStats {
+    pub domain: String,
+    pub count: i64,             // 收藏数量
+    pub first_saved_at: String, // 最早的收藏时间
+    pub last_saved_at: String,  // 最近的收藏时间
+    #[sqlx(skip)]
+    pub top_tags: Vec<TagCount>, // 最常用的标签，按使用次数倒序
+}
+
+/// 按域名统计收藏，按收藏数量倒序
+#[utoipa::path(
+    get,
+    path = "/api/domains",
+    tag = "domains",
+    params(
+        ("top_tags" = Option<usize>, Query, description = "每个域名返回的常用标签数量，默认为5，最多20"),
+        ("limit" = Option<i64>, Query, description = "最多返回的域名数量，默认全部返回"),
+        ("tz" = Option<String>, Query, description = "IANA 时区名，如 Asia/Shanghai，也可通过 X-Timezone 请求头指定，默认为 UTC")
+    ),
+    responses(
+        (status = 200, description = "成功获取域名统计", body = Vec<DomainStats>),
+        (status = 500, description = "服务器内部错误")
+    )
+)]
+pub async fn list_domains(
+    user: AuthUser,
+    tz: ClientTimezone,
+    Query(params): Query<DomainQuery>,
+    State(db): State<SqlitePool>,
+) -> Result<Json<Vec<DomainStats>>, AppError> {
+    let top_tags = params.top_tags.unwrap_or(5).min(MAX_TOP_TAGS);
+
+    let mut domains = sqlx::query_as::<_, DomainStats>(
+        "SELECT domain, COUNT(*) as count, MIN(created_at) as first_saved_at, MAX(created_at) as last_saved_at
+         FROM favorites
+         WHERE user_id = ? AND deleted_at IS NULL AND domain != ''
+         GROUP BY domain
+         ORDER BY count DESC, domain
+         LIMIT ?"
+    )
+    .bind(user.id)
+    .bind(params.limit.filter(|limit| *limit > 0).unwrap_or(-1))
+    .fetch_all(&db)
+    .await
+    .map_err(AppError::Database)?;
+
+    if top_tags > 0 {
+        // 按域名和使用次数排序，逐个域名取前几个
+        let rows: Vec<(String, String, i64)> = sqlx::query_as(
+            "SELECT f.domain, t.name, COUNT(*) as count
+             FROM favorites f
+             JOIN favorite_tags ft ON ft.favorite_id = f.id
+             JOIN tags t ON t.id = ft.tag_id
+             WHERE f.user_id = ? AND f.deleted_at IS NULL AND f.domain != ''
+             GROUP BY f.domain, t.id
+             ORDER BY f.domain, count DESC, t.name"
+        )
+        .bind(user.id)
+        .fetch_all(&db)
+        .await
+        .map_err(AppError::Database)?;
+
+        let mut tags: HashMap<String, Vec<TagCount>> = HashMap::new();
+        for (domain, name, count) in rows {
+            let entry = tags.entry(domain).or_default();
+            if entry.len() < top_tags {
+                entry.push(TagCount { name, count });
+            }
+        }
+        for stats in &mut domains {
+            stats.top_tags = tags.remove(&stats.domain).unwrap_or_default();
+        }
+    }
+
+    for stats in &mut domains {
+        tz.apply(&mut stats.first_saved_at);
+        tz.apply(&mut stats.last_saved_at);
+    }
+
+    Ok(Json(domains))
+}
\ No newline at end of file
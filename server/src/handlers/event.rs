@@ -0,0 +1,120 @@
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    response::sse::{Event as SseEvent, KeepAlive, Sse},
+    Json,
+};
+use futures_util::stream::{self, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::convert::Infallible;
+use tokio::sync::broadcast::error::RecvError;
+use utoipa::ToSchema;
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::events::{Event, EventBus, TOKEN_TTL};
+
+/// 事件流查询参数，供无法设置请求头的浏览器 EventSource 使用
+#[derive(Debug, Deserialize)]
+pub struct EventQuery {
+    pub token: Option<String>,         // 通过 POST /api/events/token 获取的订阅凭证
+    pub last_event_id: Option<String>, // 重新创建 EventSource 时代替 Last-Event-ID 请求头
+}
+
+/// 订阅凭证
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EventToken {
+    pub token: String,
+    pub expires_in: u64, // 有效秒数，过期后需重新获取
+}
+
+/// 转为 SSE 消息，`id` 供客户端重连时通过 Last-Event-ID 带回
+fn to_sse(event: &Event) -> SseEvent {
+    SseEvent::default()
+        .id(event.id.to_string())
+        .event(event.name())
+        .data(json!({ "id": event.entity_id }).to_string())
+}
+
+/// 事件有遗漏时发送，提示客户端重新加载全部数据
+fn reset() -> SseEvent {
+    SseEvent::default().event("reset").data("{}")
+}
+
+/// 获取订阅事件流的短期凭证
+/// 浏览器的 EventSource 无法携带 Authorization 请求头，可先用 API Key 获取凭证，再以 `?token=` 订阅
+#[utoipa::path(
+    post,
+    path = "/api/events/token",
+    tag = "events",
+    responses(
+        (status = 200, description = "成功签发凭证", body = EventToken),
+        (status = 401, description = "未提供或无效的 API key")
+    )
+)]
+pub async fn issue_event_token(
+    user: AuthUser,
+    State(events): State<EventBus>,
+) -> Json<EventToken> {
+    Json(EventToken {
+        token: events.issue_token(user.id),
+        expires_in: TOKEN_TTL.as_secs(),
+    })
+}
+
+/// 订阅收藏、标签和分类的变更事件
+/// 事件名称为 `favorite.created`、`tag.updated`、`category.deleted` 等，数据为 `{"id": 变化的数据ID}`；
+/// 重连时根据 Last-Event-ID 补发错过的事件，无法补发时先发送一条 `reset` 事件
+#[utoipa::path(
+    get,
+    path = "/api/events",
+    tag = "events",
+    params(
+        ("token" = Option<String>, Query, description = "订阅凭证，未提供 Authorization 请求头时必填"),
+        ("last_event_id" = Option<String>, Query, description = "最后收到的事件ID，与 Last-Event-ID 请求头相同，请求头优先"),
+        ("Last-Event-ID" = Option<String>, Header, description = "最后收到的事件ID，浏览器重连时自动携带")
+    ),
+    responses(
+        (status = 200, description = "text/event-stream 事件流", content_type = "text/event-stream", body = String),
+        (status = 401, description = "未提供或无效的 API key 或凭证")
+    )
+)]
+pub async fn stream_events(
+    user: Result<AuthUser, AppError>,
+    State(events): State<EventBus>,
+    Query(params): Query<EventQuery>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<SseEvent, Infallible>>>, AppError> {
+    let user_id = match (user, params.token.as_deref()) {
+        (Ok(user), _) => user.id,
+        (Err(_), Some(token)) => events.verify_token(token).ok_or(AppError::Unauthorized)?,
+        (Err(err), None) => return Err(err),
+    };
+
+    // 无法解析的 ID 视为过期，由客户端重新加载
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .or(params.last_event_id.as_deref())
+        .map(|value| value.trim().parse::<u64>().unwrap_or(0));
+
+    let (replay, receiver) = events.subscribe(user_id, last_event_id);
+    let replay = match replay {
+        Some(events) => events.iter().map(to_sse).collect(),
+        None => vec![reset()],
+    };
+
+    let live = stream::unfold(receiver, move |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) if event.user_id == user_id => return Some((to_sse(&event), receiver)),
+                Ok(_) => continue,
+                Err(RecvError::Lagged(_)) => return Some((reset(), receiver)),
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    let stream = stream::iter(replay).chain(live).map(Ok);
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
\ No newline at end of file
@@ -0,0 +1,450 @@
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+};
+use chrono::DateTime;
+use futures_util::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqlitePool;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use utoipa::ToSchema;
+use crate::auth::AuthUser;
+use crate::handlers::favorite::{Favorite, ListFavoriteQuery, FAVORITE_COLUMNS};
+use crate::handlers::search::escape_html;
+use crate::timezone::ClientTimezone;
+
+/// CSV 中多个标签之间的分隔符
+pub(crate) const CSV_TAG_SEPARATOR: char = ';';
+
+/// CSV 表头
+pub(crate) const CSV_HEADER: [&str; 6] = ["id", "category", "text", "url", "tags", "created_at"];
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+    Markdown,
+    Html,
+}
+
+impl ExportFormat {
+    fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "application/json; charset=utf-8",
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Markdown => "text/markdown; charset=utf-8",
+            ExportFormat::Html => "text/html; charset=utf-8",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Markdown => "md",
+            ExportFormat::Html => "html",
+        }
+    }
+
+    /// Markdown 和书签文件按分类分组输出
+    fn grouped(self) -> bool {
+        matches!(self, ExportFormat::Markdown | ExportFormat::Html)
+    }
+}
+
+/// 导出查询参数，筛选条件与收藏列表相同
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    pub format: Option<ExportFormat>, // 导出格式，默认为 json
+}
+
+/// 导出的收藏
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ExportItem {
+    pub id: i64,
+    pub category: Option<String>, // 分类名称，未分类为 null
+    pub text: String,
+    pub url: String,
+    pub tags: Vec<String>,
+    pub created_at: String,
+}
+
+impl From<Favorite> for ExportItem {
+    fn from(favorite: Favorite) -> Self {
+        ExportItem {
+            id: favorite.id,
+            category: favorite.category_id.map(|_| favorite.category_name),
+            text: favorite.text,
+            url: favorite.url,
+            tags: serde_json::from_str(&favorite.tags).unwrap_or_default(),
+            created_at: favorite.created_at,
+        }
+    }
+}
+
+/// 按 RFC 4180 转义 CSV 字段
+fn escape_csv(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// 将 created_at 转为书签文件使用的 Unix 时间戳
+fn unix_timestamp(created_at: &str) -> Option<i64> {
+    DateTime::parse_from_rfc3339(created_at)
+        .ok()
+        .map(|time| time.timestamp())
+}
+
+/// 截取收藏文本的第一行作为书签标题
+fn bookmark_title(text: &str) -> String {
+    const MAX_TITLE_CHARS: usize = 80;
+
+    let line = text.lines().map(str::trim).find(|l| !l.is_empty()).unwrap_or_default();
+    let mut title: String = line.chars().take(MAX_TITLE_CHARS).collect();
+    if line.chars().count() > MAX_TITLE_CHARS {
+        title.push('…');
+    }
+    title
+}
+
+/// 逐条生成导出内容
+struct Exporter {
+    format: ExportFormat,
+    count: usize,
+    category: Option<Option<String>>,
+}
+
+impl Exporter {
+    fn new(format: ExportFormat) -> Self {
+        Exporter {
+            format,
+            count: 0,
+            category: None,
+        }
+    }
+
+    fn header(&self) -> String {
+        match self.format {
+            ExportFormat::Json => "[".to_string(),
+            // 带 BOM 以便 Excel 正确识别 UTF-8
+            ExportFormat::Csv => format!("\u{feff}{}\r\n", CSV_HEADER.join(",")),
+            ExportFormat::Markdown => "# 收藏导出\n".to_string(),
+            ExportFormat::Html => concat!(
+                "<!DOCTYPE NETSCAPE-Bookmark-file-1>\n",
+                "<!-- This is an automatically generated file.\n",
+                "     It will be read and overwritten.\n",
+                "     DO NOT EDIT! -->\n",
+                "<META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">\n",
+                "<TITLE>Bookmarks</TITLE>\n",
+                "<H1>Bookmarks</H1>\n",
+                "<DL><p>\n",
+            )
+            .to_string(),
+        }
+    }
+
+    fn item(&mut self, item: &ExportItem) -> String {
+        let mut out = String::new();
+
+        // 分类变化时开始新的分组
+        if self.format.grouped() && self.category.as_ref() != Some(&item.category) {
+            if let Some(Some(_)) = &self.category {
+                if self.format == ExportFormat::Html {
+                    out.push_str("    </DL><p>\n");
+                }
+            }
+            if let Some(name) = &item.category {
+                match self.format {
+                    ExportFormat::Markdown => out.push_str(&format!("\n## {}\n", name)),
+                    _ => out.push_str(&format!(
+                        "    <DT><H3>{}</H3>\n    <DL><p>\n",
+                        escape_html(name)
+                    )),
+                }
+            }
+            self.category = Some(item.category.clone());
+        }
+
+        match self.format {
+            ExportFormat::Json => {
+                if self.count > 0 {
+                    out.push(',');
+                }
+                out.push('\n');
+                out.push_str(&serde_json::to_string(item).unwrap_or_default());
+            }
+            ExportFormat::Csv => {
+                let tags = item.tags.join(&CSV_TAG_SEPARATOR.to_string());
+                let fields = [
+                    item.id.to_string(),
+                    item.category.clone().unwrap_or_default(),
+                    item.text.clone(),
+                    item.url.clone(),
+                    tags,
+                    item.created_at.clone(),
+                ];
+                let line: Vec<String> = fields.iter().map(|f| escape_csv(f)).collect();
+                out.push_str(&line.join(","));
+                out.push_str("\r\n");
+            }
+            ExportFormat::Markdown => {
+                out.push('\n');
+                for line in item.text.lines() {
+                    out.push_str(&format!("> {}\n", line));
+                }
+                out.push_str(&format!("\n[{}]({})", item.url, item.url));
+                if !item.tags.is_empty() {
+                    let tags: Vec<String> = item.tags.iter().map(|t| format!("`#{}`", t)).collect();
+                    out.push_str(&format!(" · {}", tags.join(" ")));
+                }
+                out.push_str(&format!(" · {}\n", item.created_at));
+            }
+            ExportFormat::Html => {
+                let indent = if item.category.is_some() { "        " } else { "    " };
+                let add_date = unix_timestamp(&item.created_at)
+                    .map(|ts| format!(" ADD_DATE=\"{}\"", ts))
+                    .unwrap_or_default();
+                let tags = if item.tags.is_empty() {
+                    String::new()
+                } else {
+                    format!(" TAGS=\"{}\"", escape_html(&item.tags.join(",")))
+                };
+                out.push_str(&format!(
+                    "{}<DT><A HREF=\"{}\"{}{}>{}</A>\n{}<DD>{}\n",
+                    indent,
+                    escape_html(&item.url),
+                    add_date,
+                    tags,
+                    escape_html(&bookmark_title(&item.text)),
+                    indent,
+                    escape_html(&item.text).replace('\n', "<BR>"),
+                ));
+            }
+        }
+
+        self.count += 1;
+        out
+    }
+
+    fn footer(&self) -> String {
+        match self.format {
+            ExportFormat::Json if self.count > 0 => "\n]\n".to_string(),
+            ExportFormat::Json => "]\n".to_string(),
+            ExportFormat::Html => {
+                let mut out = String::new();
+                if let Some(Some(_)) = &self.category {
+                    out.push_str("    </DL><p>\n");
+                }
+                out.push_str("</DL><p>\n");
+                out
+            }
+            _ => String::new(),
+        }
+    }
+}
+
+/// 导出收藏
+#[utoipa::path(
+    get,
+    path = "/api/export",
+    tag = "export",
+    params(
+        ("format" = Option<ExportFormat>, Query, description = "导出格式：json、csv、markdown、html（Netscape 书签文件），默认为 json"),
+        ("tz" = Option<String>, Query, description = "IANA 时区名，如 Asia/Shanghai，也可通过 X-Timezone 请求头指定，默认为 UTC"),
+        ("search" = Option<String>, Query, description = "搜索关键词"),
+        ("category_id" = Option<i64>, Query, description = "分类ID"),
+        ("include_descendants" = Option<bool>, Query, description = "为 true 时包含子分类下的收藏"),
+        ("tag_id" = Option<i64>, Query, description = "标签ID"),
+        ("uncategorized" = Option<bool>, Query, description = "为 true 时只返回未分类的收藏"),
+        ("tags_all" = Option<String>, Query, description = "逗号分隔的标签ID，须同时带有全部标签"),
+        ("tags_any" = Option<String>, Query, description = "逗号分隔的标签ID，带有其中任一标签即可"),
+        ("exclude_tag" = Option<String>, Query, description = "逗号分隔的标签ID，排除带有这些标签的收藏"),
+        ("domain" = Option<String>, Query, description = "网址主机名，同时匹配其子域名，如 example.com"),
+        ("from" = Option<String>, Query, description = "创建日期下限（含，UTC），格式为 YYYY-MM-DD"),
+        ("to" = Option<String>, Query, description = "创建日期上限（含，UTC），格式为 YYYY-MM-DD"),
+        ("archived" = Option<bool>, Query, description = "为 true 时只返回已归档的收藏，默认只返回未归档的")
+    ),
+    responses(
+        (status = 200, description = "导出文件，以流的方式返回"),
+        (status = 400, description = "无效的导出格式或筛选参数"),
+        (status = 422, description = "未知的时区"),
+        (status = 401, description = "未认证")
+    )
+)]
+pub async fn export_favorites(
+    user: AuthUser,
+    tz: ClientTimezone,
+    Query(export): Query<ExportQuery>,
+    Query(params): Query<ListFavoriteQuery>,
+    State(db): State<SqlitePool>,
+) -> Response {
+    let format = export.format.unwrap_or_default();
+    let (conditions, params_values) = params.conditions(user.id);
+
+    // 分组格式按分类排序，未分类的收藏排在最前并放在顶层
+    let order_by = if format.grouped() {
+        "f.category_id IS NOT NULL, category_name, f.category_id, f.created_at, f.id"
+    } else {
+        "f.created_at DESC, f.id DESC"
+    };
+    let sql = format!(
+        "SELECT {} FROM favorites f LEFT JOIN categories c ON f.category_id = c.id \
+         WHERE {} ORDER BY {}",
+        FAVORITE_COLUMNS, conditions, order_by
+    );
+
+    // 在后台任务中逐行读取并写入响应流，避免一次性加载全部数据
+    let (tx, rx) = mpsc::channel::<Result<String, std::io::Error>>(32);
+    tokio::spawn(async move {
+        let mut exporter = Exporter::new(format);
+        if tx.send(Ok(exporter.header())).await.is_err() {
+            return;
+        }
+
+        let mut query = sqlx::query_as::<_, Favorite>(&sql);
+        for param in &params_values {
+            query = query.bind(param);
+        }
+        let mut rows = query.fetch(&db);
+
+        loop {
+            match rows.try_next().await {
+                Ok(Some(favorite)) => {
+                    let chunk = exporter.item(&ExportItem::from(favorite.localize(&tz)));
+                    if tx.send(Ok(chunk)).await.is_err() {
+                        return;
+                    }
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    tracing::error!("Export failed: {}", err);
+                    let _ = tx.send(Err(std::io::Error::other(err))).await;
+                    return;
+                }
+            }
+        }
+
+        let _ = tx.send(Ok(exporter.footer())).await;
+    });
+
+    (
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"favorites.{}\"", format.extension()),
+            ),
+        ],
+        Body::from_stream(ReceiverStream::new(rx)),
+    )
+        .into_response()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: i64, category: Option<&str>, text: &str, tags: &[&str]) -> ExportItem {
+        ExportItem {
+            id,
+            category: category.map(str::to_string),
+            text: text.to_string(),
+            url: format!("https://example.com/{}", id),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            created_at: "2024-01-02T03:04:05+00:00".to_string(),
+        }
+    }
+
+    fn export(format: ExportFormat, items: &[ExportItem]) -> String {
+        let mut exporter = Exporter::new(format);
+        let mut out = exporter.header();
+        for item in items {
+            out.push_str(&exporter.item(item));
+        }
+        out.push_str(&exporter.footer());
+        out
+    }
+
+    #[test]
+    fn test_escape_csv() {
+        assert_eq!(escape_csv("plain text"), "plain text");
+        assert_eq!(escape_csv(""), "");
+        assert_eq!(escape_csv("a,b"), "\"a,b\"");
+        assert_eq!(escape_csv("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(escape_csv("line\nbreak"), "\"line\nbreak\"");
+        assert_eq!(escape_csv("cr\r\n"), "\"cr\r\n\"");
+    }
+
+    #[test]
+    fn test_export_json() {
+        let empty = export(ExportFormat::Json, &[]);
+        assert_eq!(empty, "[]\n");
+        assert_eq!(serde_json::from_str::<Vec<serde_json::Value>>(&empty).unwrap().len(), 0);
+
+        let items = [item(1, None, "一", &[]), item(2, Some("阅读"), "二\n\"引号\"", &["a", "b"])];
+        let out = export(ExportFormat::Json, &items);
+        let parsed: Vec<serde_json::Value> = serde_json::from_str(&out).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert!(parsed[0]["category"].is_null());
+        assert_eq!(parsed[1]["category"], "阅读");
+        assert_eq!(parsed[1]["text"], "二\n\"引号\"");
+        assert_eq!(parsed[1]["tags"], serde_json::json!(["a", "b"]));
+    }
+
+    #[test]
+    fn test_export_csv() {
+        let out = export(ExportFormat::Csv, &[item(1, Some("a,b"), "第一行\n第二行", &["x", "y"])]);
+        assert_eq!(
+            out,
+            "\u{feff}id,category,text,url,tags,created_at\r\n\
+             1,\"a,b\",\"第一行\n第二行\",https://example.com/1,x;y,2024-01-02T03:04:05+00:00\r\n"
+        );
+    }
+
+    #[test]
+    fn test_export_bookmarks() {
+        let items = [
+            item(1, None, "未分类 <b>", &[]),
+            item(2, Some("阅读"), "第一条", &["a", "b"]),
+            item(3, Some("阅读"), "第二条", &[]),
+            item(4, Some("技术"), "第三条", &[]),
+        ];
+        let out = export(ExportFormat::Html, &items);
+        let body = out.split_once("<DL><p>\n").unwrap().1;
+        assert_eq!(
+            body,
+            concat!(
+                "    <DT><A HREF=\"https://example.com/1\" ADD_DATE=\"1704164645\">未分类 &lt;b&gt;</A>\n",
+                "    <DD>未分类 &lt;b&gt;\n",
+                "    <DT><H3>阅读</H3>\n",
+                "    <DL><p>\n",
+                "        <DT><A HREF=\"https://example.com/2\" ADD_DATE=\"1704164645\" TAGS=\"a,b\">第一条</A>\n",
+                "        <DD>第一条\n",
+                "        <DT><A HREF=\"https://example.com/3\" ADD_DATE=\"1704164645\">第二条</A>\n",
+                "        <DD>第二条\n",
+                "    </DL><p>\n",
+                "    <DT><H3>技术</H3>\n",
+                "    <DL><p>\n",
+                "        <DT><A HREF=\"https://example.com/4\" ADD_DATE=\"1704164645\">第三条</A>\n",
+                "        <DD>第三条\n",
+                "    </DL><p>\n",
+                "</DL><p>\n",
+            )
+        );
+
+        // 只有未分类的收藏时不输出分组
+        let out = export(ExportFormat::Html, &items[..1]);
+        assert!(!out.contains("<H3>"));
+        assert!(out.ends_with("    <DD>未分类 &lt;b&gt;\n</DL><p>\n"));
+    }
+}
\ No newline at end of file
@@ -4,20 +4,168 @@ use axum::{
     http::StatusCode,
 };
 use serde::{Deserialize, Serialize};
-use sqlx::sqlite::SqlitePool;
+use sqlx::sqlite::{SqliteConnection, SqlitePool};
 use sqlx::FromRow;
 use utoipa::ToSchema;
+use validator::Validate;
+use crate::archiver::ArchiverHandle;
+use crate::audit::{self, Entity};
+use crate::auth::AuthUser;
 use crate::error::AppError;
-use chrono::Local;
+use crate::events::{Action, EventBus, Implicit, Topic};
+use crate::handlers::category::CATEGORY_SUBTREE_IDS;
+use crate::handlers::page::attach_page;
+use crate::handlers::search::SearchFilter;
+use crate::normalize;
+use crate::url_normalize;
+use crate::sanitize;
+use crate::text_fragment;
+use crate::timezone::{self, ClientTimezone};
+use crate::validation::{self, ValidatedJson};
+use chrono::NaiveDate;
+
+/// 每页数量的上限
+pub(crate) const MAX_PER_PAGE: i64 = 100;
+
+/// 收藏列表的排序字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FavoriteSort {
+    CreatedAt, // 创建时间
+    UpdatedAt, // 修改时间
+    Url,       // 网址
+    Category,  // 分类名称
+}
+
+impl FavoriteSort {
+    /// 排序键对应的 SQL 表达式
+    fn column(self) -> &'static str {
+        match self {
+            FavoriteSort::CreatedAt => "f.created_at",
+            FavoriteSort::UpdatedAt => "f.updated_at",
+            FavoriteSort::Url => "f.url",
+            FavoriteSort::Category => "COALESCE(c.name, '未分类')",
+        }
+    }
+
+    /// 取出收藏在该排序字段上的值，用于生成游标
+    fn key(self, favorite: &Favorite) -> &str {
+        match self {
+            FavoriteSort::CreatedAt => &favorite.created_at,
+            FavoriteSort::UpdatedAt => &favorite.updated_at,
+            FavoriteSort::Url => &favorite.url,
+            FavoriteSort::Category => &favorite.category_name,
+        }
+    }
+}
+
+/// 排序方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,  // 升序
+    Desc, // 降序
+}
+
+impl SortOrder {
+    fn reverse(self) -> Self {
+        match self {
+            SortOrder::Asc => SortOrder::Desc,
+            SortOrder::Desc => SortOrder::Asc,
+        }
+    }
+
+    fn sql(self) -> &'static str {
+        match self {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        }
+    }
+}
+
+/// 分页游标，记录一页边界处收藏的排序键和ID
+/// 对外以十六进制编码的 JSON 传递，客户端应视为不透明字符串
+#[derive(Debug, Serialize, Deserialize)]
+struct Cursor {
+    sort: FavoriteSort,
+    key: String,
+    id: i64,
+}
+
+impl Cursor {
+    fn new(sort: FavoriteSort, favorite: &Favorite) -> Self {
+        Cursor { sort, key: sort.key(favorite).to_string(), id: favorite.id }
+    }
+
+    fn encode(&self) -> String {
+        serde_json::to_vec(self)
+            .unwrap_or_default()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    /// 解析游标，格式错误或与当前排序字段不符时返回 None
+    fn decode(value: &str, sort: FavoriteSort) -> Option<Self> {
+        if !value.len().is_multiple_of(2) || !value.is_ascii() {
+            return None;
+        }
+        let bytes = (0..value.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&value[i..i + 2], 16).ok())
+            .collect::<Option<Vec<u8>>>()?;
+        serde_json::from_slice::<Cursor>(&bytes)
+            .ok()
+            .filter(|cursor| cursor.sort == sort)
+    }
+}
 
 /// 收藏列表查询参数
 #[derive(Debug, Serialize, ToSchema)]
 pub struct ListFavoriteQuery {
-    pub page: Option<i64>,      // 页码
-    pub per_page: Option<i64>,  // 每页数量
+    pub page: Option<i64>,      // 页码，未使用游标时有效
+    pub per_page: Option<i64>,  // 每页数量，最多 100
+    pub sort: Option<FavoriteSort>, // 排序字段，默认为创建时间
+    pub order: Option<SortOrder>,   // 排序方向，默认为降序
+    pub after: Option<String>,  // 游标，获取该位置之后的一页
+    pub before: Option<String>, // 游标，获取该位置之前的一页
+    pub include_total: Option<bool>, // 是否统计总数，默认仅在按页码分页时统计
     pub search: Option<String>, // 搜索关键词
     pub category_id: Option<i64>, // 分类ID
+    pub include_descendants: Option<bool>, // 按分类筛选时是否包含子分类
+    pub uncategorized: Option<bool>, // 为 true 时只查询未分类的收藏
     pub tag_id: Option<i64>,    // 标签ID
+    pub tags_all: Vec<i64>,     // 必须同时带有的标签ID
+    pub tags_any: Vec<i64>,     // 至少带有其一的标签ID
+    pub exclude_tag: Vec<i64>,  // 不能带有的标签ID
+    pub domain: Option<String>, // 网址主机名，同时匹配其子域名
+    pub from: Option<String>,   // 创建日期下限（含，UTC），格式为 YYYY-MM-DD
+    pub to: Option<String>,     // 创建日期上限（含，UTC），格式为 YYYY-MM-DD
+    pub archived: Option<bool>, // 为 true 时只查询已归档的收藏，默认只查询未归档的
+}
+
+/// 解析逗号分隔的ID列表，重复的ID只保留一个，否则 tags_all 的计数无法匹配
+fn parse_ids<E: serde::de::Error>(name: &str, value: Option<String>) -> Result<Vec<i64>, E> {
+    let mut ids = value
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse().map_err(|_| E::custom(format!("invalid id in {}: {}", name, s))))
+        .collect::<Result<Vec<i64>, E>>()?;
+    ids.sort_unstable();
+    ids.dedup();
+    Ok(ids)
+}
+
+/// 校验 YYYY-MM-DD 格式的日期
+fn parse_date<E: serde::de::Error>(name: &str, value: Option<String>) -> Result<Option<String>, E> {
+    match value.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(s) => NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .map(|date| Some(date.format("%Y-%m-%d").to_string()))
+            .map_err(|_| E::custom(format!("invalid date for {}: {}", name, s))),
+    }
 }
 
 /// 实现自定义反序列化
@@ -30,28 +178,170 @@ impl<'de> Deserialize<'de> for ListFavoriteQuery {
         struct Helper {
             page: Option<String>,
             per_page: Option<String>,
+            sort: Option<String>,
+            order: Option<String>,
+            after: Option<String>,
+            before: Option<String>,
+            include_total: Option<String>,
             search: Option<String>,
             category_id: Option<String>,
+            include_descendants: Option<String>,
+            uncategorized: Option<String>,
             tag_id: Option<String>,
+            tags_all: Option<String>,
+            tags_any: Option<String>,
+            exclude_tag: Option<String>,
+            domain: Option<String>,
+            from: Option<String>,
+            to: Option<String>,
+            archived: Option<String>,
         }
 
         let helper = Helper::deserialize(deserializer)?;
 
+        // 排序参数写错时直接报错，避免静默回退到默认排序
+        let sort = match helper.sort.as_deref() {
+            None | Some("") => None,
+            Some("created_at") => Some(FavoriteSort::CreatedAt),
+            Some("updated_at") => Some(FavoriteSort::UpdatedAt),
+            Some("url") => Some(FavoriteSort::Url),
+            Some("category") => Some(FavoriteSort::Category),
+            Some(other) => return Err(serde::de::Error::custom(format!("unknown sort field: {}", other))),
+        };
+        let order = match helper.order.as_deref() {
+            None | Some("") => None,
+            Some("asc") => Some(SortOrder::Asc),
+            Some("desc") => Some(SortOrder::Desc),
+            Some(other) => return Err(serde::de::Error::custom(format!("unknown sort order: {}", other))),
+        };
+
         Ok(ListFavoriteQuery {
             page: helper.page
                 .and_then(|s| if s.is_empty() { None } else { s.parse().ok() }),
             per_page: helper.per_page
                 .and_then(|s| if s.is_empty() { None } else { s.parse().ok() }),
+            sort,
+            order,
+            after: helper.after
+                .filter(|s| !s.is_empty()),
+            before: helper.before
+                .filter(|s| !s.is_empty()),
+            include_total: helper.include_total
+                .map(|s| s == "true" || s == "1"),
             search: helper.search
                 .filter(|s| !s.is_empty()),
             category_id: helper.category_id
                 .and_then(|s| if s.is_empty() { None } else { s.parse().ok() }),
+            include_descendants: helper.include_descendants
+                .map(|s| s == "true" || s == "1"),
+            uncategorized: helper.uncategorized
+                .map(|s| s == "true" || s == "1"),
             tag_id: helper.tag_id
                 .and_then(|s| if s.is_empty() { None } else { s.parse().ok() }),
+            tags_all: parse_ids("tags_all", helper.tags_all)?,
+            tags_any: parse_ids("tags_any", helper.tags_any)?,
+            exclude_tag: parse_ids("exclude_tag", helper.exclude_tag)?,
+            domain: helper.domain
+                .map(|s| s.trim().trim_end_matches('.').to_ascii_lowercase())
+                .filter(|s| !s.is_empty()),
+            from: parse_date("from", helper.from)?,
+            to: parse_date("to", helper.to)?,
+            archived: helper.archived
+                .map(|s| s == "true" || s == "1"),
         })
     }
 }
 
+impl ListFavoriteQuery {
+    /// 生成筛选条件（不含 WHERE 关键字）及其参数，供列表、导出等接口共用
+    pub(crate) fn conditions(&self, user_id: i64) -> (String, Vec<String>) {
+        // 只查询当前用户的收藏
+        let mut conditions = vec!["f.user_id = ?".to_string(), "f.deleted_at IS NULL".to_string()];
+        let mut params_values = vec![user_id.to_string()];
+
+        if self.archived.unwrap_or(false) {
+            conditions.push("f.archived_at IS NOT NULL".to_string());
+        } else {
+            conditions.push("f.archived_at IS NULL".to_string());
+        }
+
+        // 处理搜索条件，使用全文索引
+        if let Some(filter) = self.search.as_deref().and_then(SearchFilter::parse) {
+            let (where_clause, values) = filter.where_clause();
+            conditions.push(format!(
+                "f.id IN (SELECT favorites_fts.rowid FROM favorites_fts WHERE {})",
+                where_clause
+            ));
+            params_values.extend(values);
+        }
+
+        // 处理分类筛选
+        if let Some(category_id) = self.category_id {
+            if self.include_descendants.unwrap_or(false) {
+                conditions.push(format!("f.category_id IN ({})", CATEGORY_SUBTREE_IDS));
+            } else {
+                conditions.push("f.category_id = ?".to_string());
+            }
+            params_values.push(category_id.to_string());
+        }
+
+        // 处理标签筛选
+        if let Some(tag_id) = self.tag_id {
+            conditions.push("f.id IN (SELECT favorite_id FROM favorite_tags WHERE tag_id = ?)".to_string());
+            params_values.push(tag_id.to_string());
+        }
+
+        if self.uncategorized.unwrap_or(false) {
+            conditions.push("f.category_id IS NULL".to_string());
+        }
+
+        // 同时带有全部标签：按收藏分组后数量须与标签数一致
+        if !self.tags_all.is_empty() {
+            conditions.push(format!(
+                "f.id IN (SELECT favorite_id FROM favorite_tags WHERE tag_id IN ({})
+                 GROUP BY favorite_id HAVING COUNT(DISTINCT tag_id) = {})",
+                vec!["?"; self.tags_all.len()].join(", "),
+                self.tags_all.len()
+            ));
+            params_values.extend(self.tags_all.iter().map(i64::to_string));
+        }
+
+        if !self.tags_any.is_empty() {
+            conditions.push(format!(
+                "f.id IN (SELECT favorite_id FROM favorite_tags WHERE tag_id IN ({}))",
+                vec!["?"; self.tags_any.len()].join(", ")
+            ));
+            params_values.extend(self.tags_any.iter().map(i64::to_string));
+        }
+
+        if !self.exclude_tag.is_empty() {
+            conditions.push(format!(
+                "f.id NOT IN (SELECT favorite_id FROM favorite_tags WHERE tag_id IN ({}))",
+                vec!["?"; self.exclude_tag.len()].join(", ")
+            ));
+            params_values.extend(self.exclude_tag.iter().map(i64::to_string));
+        }
+
+        // 域名筛选，example.com 也匹配 www.example.com
+        if let Some(domain) = &self.domain {
+            conditions.push("(f.domain = ? OR substr(f.domain, -length(?) - 1) = '.' || ?)".to_string());
+            params_values.extend([domain.clone(), domain.clone(), domain.clone()]);
+        }
+
+        // 日期范围，上限当天全天都包含在内
+        if let Some(from) = &self.from {
+            conditions.push("f.created_at >= ?".to_string());
+            params_values.push(from.clone());
+        }
+        if let Some(to) = &self.to {
+            conditions.push("f.created_at < date(?, '+1 day')".to_string());
+            params_values.push(to.clone());
+        }
+
+        (conditions.join(" AND "), params_values)
+    }
+}
+
 /// 收藏数据结构
 #[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct Favorite {
@@ -60,33 +350,380 @@ pub struct Favorite {
     pub category_name: String,
     pub text: String,
     pub url: String,
+    #[serde(default)]
+    pub canonical_url: String, // 去掉跟踪参数等差异后的规范化网址
+    #[serde(default)]
+    pub domain: String,        // 网址的主机名
+    #[serde(default)]
+    pub page_id: Option<i64>,  // 所属来源网页的ID
+    pub deep_link: String, // 带 `#:~:text=` 文本片段、可直接定位到原文段落的链接
+    pub title: Option<String>,          // 页面标题
+    pub context_before: Option<String>, // 选区前的文本
+    pub context_after: Option<String>,  // 选区后的文本
+    pub html: Option<String>,           // 选区的 HTML，已按白名单清理
+    pub lang: Option<String>,           // 页面语言
     pub tags: String,
+    pub note_count: i64, // 笔记数量
+    pub has_notes: bool, // 是否有笔记
     pub created_at: String,
+    pub updated_at: String, // 最后修改时间
+    pub archived_at: Option<String>, // 归档时间，未归档时为 null
+    pub deleted_at: Option<String>,  // 移入回收站的时间，未删除时为 null
+}
+
+impl Favorite {
+    /// 将时间字段转为调用方时区
+    pub(crate) fn localize(mut self, tz: &ClientTimezone) -> Self {
+        tz.apply(&mut self.created_at);
+        tz.apply(&mut self.updated_at);
+        for time in [&mut self.archived_at, &mut self.deleted_at].into_iter().flatten() {
+            tz.apply(time);
+        }
+        self
+    }
 }
 
 /// 收藏列表响应
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct FavoriteResponse {
-    pub total: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<i64>, // 符合条件的总数，未统计时省略
     pub items: Vec<Favorite>,
+    pub next_cursor: Option<String>, // 下一页的游标，没有更多时为 null
+    pub prev_cursor: Option<String>, // 上一页的游标，已是第一页时为 null
 }
 
 /// 创建收藏请求
-#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct CreateFavorite {
     pub category_id: Option<i64>, // 分类ID（可选）
+    #[validate(custom = "validation::not_blank", length(max = 10000, message = "不能超过 10000 个字符"))]
     pub text: String,            // 收藏描述
+    #[validate(custom = "validation::http_url", length(max = 2048, message = "不能超过 2048 个字符"))]
     pub url: String,             // 收藏URL
+    #[validate(custom = "validation::tag_list")]
     pub tags: Vec<String>,       // 标签列表
+    #[validate(length(max = 500, message = "不能超过 500 个字符"))]
+    pub title: Option<String>,          // 页面标题（可选）
+    #[validate(length(max = 1000, message = "不能超过 1000 个字符"))]
+    pub context_before: Option<String>, // 选区前的文本（可选）
+    #[validate(length(max = 1000, message = "不能超过 1000 个字符"))]
+    pub context_after: Option<String>,  // 选区后的文本（可选）
+    #[validate(length(max = 100000, message = "不能超过 100000 个字符"))]
+    pub html: Option<String>,           // 选区的 HTML（可选），保存前会被清理
+    #[validate(length(max = 35, message = "不能超过 35 个字符"))]
+    pub lang: Option<String>,           // 页面语言（可选）
+    #[serde(default)]
+    pub allow_duplicate: Option<bool>, // 为 true 时跳过查重
 }
 
 /// 更新收藏请求
-#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct UpdateFavorite {
     pub category_id: Option<i64>, // 分类ID（可选）
+    #[validate(custom = "validation::not_blank", length(max = 10000, message = "不能超过 10000 个字符"))]
     pub text: String,            // 收藏描述
+    #[validate(custom = "validation::http_url", length(max = 2048, message = "不能超过 2048 个字符"))]
     pub url: String,             // 收藏URL
+    #[validate(custom = "validation::tag_list")]
     pub tags: Vec<String>,       // 标签列表
+    #[validate(length(max = 500, message = "不能超过 500 个字符"))]
+    pub title: Option<String>,          // 页面标题，未提供时保留原值
+    #[validate(length(max = 1000, message = "不能超过 1000 个字符"))]
+    pub context_before: Option<String>, // 选区前的文本，未提供时保留原值
+    #[validate(length(max = 1000, message = "不能超过 1000 个字符"))]
+    pub context_after: Option<String>,  // 选区后的文本，未提供时保留原值
+    #[validate(length(max = 100000, message = "不能超过 100000 个字符"))]
+    pub html: Option<String>,           // 选区的 HTML，未提供时保留原值
+    #[validate(length(max = 35, message = "不能超过 35 个字符"))]
+    pub lang: Option<String>,           // 页面语言，未提供时保留原值
+}
+
+/// 部分更新收藏请求，只修改提供的字段
+#[derive(Debug, Default, Serialize, Deserialize, Validate, ToSchema)]
+#[serde(default)]
+pub struct PatchFavorite {
+    #[serde(deserialize_with = "nullable")]
+    #[schema(value_type = Option<i64>, nullable)]
+    pub category_id: Option<Option<i64>>, // 分类ID，传 null 改为未分类
+    #[validate(custom = "validation::not_blank", length(max = 10000, message = "不能超过 10000 个字符"))]
+    pub text: Option<String>,
+    #[validate(custom = "validation::http_url", length(max = 2048, message = "不能超过 2048 个字符"))]
+    pub url: Option<String>,
+    #[validate(custom = "validation::tag_list")]
+    pub tags: Option<Vec<String>>,        // 替换全部标签
+    #[validate(custom = "validation::tag_list")]
+    pub add_tags: Option<Vec<String>>,    // 追加的标签
+    pub remove_tags: Option<Vec<String>>, // 移除的标签
+    #[validate(length(max = 500, message = "不能超过 500 个字符"))]
+    pub title: Option<String>,          // 以下字段传空字符串表示清空
+    #[validate(length(max = 1000, message = "不能超过 1000 个字符"))]
+    pub context_before: Option<String>,
+    #[validate(length(max = 1000, message = "不能超过 1000 个字符"))]
+    pub context_after: Option<String>,
+    #[validate(length(max = 100000, message = "不能超过 100000 个字符"))]
+    pub html: Option<String>,
+    #[validate(length(max = 35, message = "不能超过 35 个字符"))]
+    pub lang: Option<String>,
+}
+
+/// 区分字段缺失与显式的 null：缺失时为 None，null 时为 Some(None)
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// 合并收藏请求
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct MergeFavorites {
+    #[validate(length(min = 2, max = 100, message = "需要 2 到 100 个收藏ID"))]
+    pub ids: Vec<i64>,           // 待合并的收藏ID，至少两个
+    pub target_id: Option<i64>,  // 保留的收藏ID，默认为最早创建的一条
+}
+
+/// 收藏查询的公共列，标签由关联表聚合为 JSON 数组字符串
+pub(crate) const FAVORITE_COLUMNS: &str = r#"
+    f.id,
+    f.category_id,
+    COALESCE(c.name, '未分类') as category_name,
+    f.text,
+    f.url,
+    f.canonical_url,
+    f.domain,
+    f.page_id,
+    f.deep_link,
+    f.title,
+    f.context_before,
+    f.context_after,
+    f.html,
+    f.lang,
+    (SELECT json_group_array(t.name)
+       FROM favorite_tags ft
+       JOIN tags t ON t.id = ft.tag_id
+      WHERE ft.favorite_id = f.id) as tags,
+    (SELECT COUNT(*) FROM notes n WHERE n.favorite_id = f.id) as note_count,
+    EXISTS(SELECT 1 FROM notes n WHERE n.favorite_id = f.id) as has_notes,
+    f.created_at,
+    f.updated_at,
+    f.archived_at,
+    f.deleted_at
+"#;
+
+/// 将收藏的标签设置为给定的标签名称列表
+/// 不存在的标签会按名称自动创建，新标签的ID记入 `implicit`
+pub(crate) async fn set_favorite_tags(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+    favorite_id: i64,
+    tags: &[String],
+    implicit: &mut Implicit,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM favorite_tags WHERE favorite_id = ?")
+        .bind(favorite_id)
+        .execute(&mut *conn)
+        .await?;
+
+    add_favorite_tags(conn, user_id, favorite_id, tags, implicit).await
+}
+
+/// 为收藏追加标签，已有的标签保持不变
+pub(crate) async fn add_favorite_tags(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+    favorite_id: i64,
+    tags: &[String],
+    implicit: &mut Implicit,
+) -> Result<(), sqlx::Error> {
+    for name in tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        let result = sqlx::query("INSERT OR IGNORE INTO tags (user_id, name) VALUES (?, ?)")
+            .bind(user_id)
+            .bind(name)
+            .execute(&mut *conn)
+            .await?;
+        if result.rows_affected() > 0 {
+            implicit.tags.push(result.last_insert_rowid());
+        }
+
+        sqlx::query(
+            "INSERT OR IGNORE INTO favorite_tags (favorite_id, tag_id)
+             SELECT ?, id FROM tags WHERE user_id = ? AND name = ?"
+        )
+        .bind(favorite_id)
+        .bind(user_id)
+        .bind(name)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// 移除收藏上的指定标签，标签本身保留
+pub(crate) async fn remove_favorite_tags(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+    favorite_id: i64,
+    tags: &[String],
+) -> Result<(), sqlx::Error> {
+    for name in tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        sqlx::query(
+            "DELETE FROM favorite_tags
+             WHERE favorite_id = ? AND tag_id = (SELECT id FROM tags WHERE user_id = ? AND name = ?)"
+        )
+        .bind(favorite_id)
+        .bind(user_id)
+        .bind(name)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// 确认分类属于当前用户
+pub(crate) async fn ensure_category_owned(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+    category_id: Option<i64>,
+) -> Result<(), AppError> {
+    let Some(category_id) = category_id else {
+        return Ok(());
+    };
+
+    let exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM categories WHERE id = ? AND user_id = ?)"
+    )
+    .bind(category_id)
+    .bind(user_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(AppError::Database)?;
+
+    if !exists {
+        return Err(AppError::BadRequest(format!("分类 {} 不存在", category_id)));
+    }
+
+    Ok(())
+}
+
+/// 去除首尾空白，空字符串视为未提供
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+/// 清理选区的 HTML，清理后为空时视为未提供
+fn clean_html(html: &Option<String>) -> Option<String> {
+    non_empty(html)
+        .map(sanitize::sanitize_html)
+        .filter(|h| !h.trim().is_empty())
+}
+
+/// 查找与给定文本和地址重复的收藏，返回其ID
+pub(crate) async fn find_duplicate(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+    text: &str,
+    url: &str,
+) -> Result<Option<i64>, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT id FROM favorites
+         WHERE user_id = ? AND dedup_key = ? AND deleted_at IS NULL
+         ORDER BY created_at, id LIMIT 1"
+    )
+    .bind(user_id)
+    .bind(normalize::dedup_key(text, url))
+    .fetch_optional(&mut *conn)
+    .await
+}
+
+/// 查询收藏当前内容的审计快照，不存在时返回 None
+pub(crate) async fn favorite_snapshot(
+    conn: &mut SqliteConnection,
+    id: i64,
+) -> Result<Option<serde_json::Value>, sqlx::Error> {
+    let sql = format!(
+        "SELECT {} FROM favorites f LEFT JOIN categories c ON f.category_id = c.id WHERE f.id = ?",
+        FAVORITE_COLUMNS
+    );
+    let favorite = sqlx::query_as::<_, Favorite>(&sql)
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    Ok(favorite.as_ref().and_then(audit::snapshot))
+}
+
+/// 按ID查询单条收藏
+pub(crate) async fn fetch_favorite(conn: &mut SqliteConnection, id: i64) -> Result<Favorite, sqlx::Error> {
+    let sql = format!(
+        "SELECT {} FROM favorites f LEFT JOIN categories c ON f.category_id = c.id WHERE f.id = ?",
+        FAVORITE_COLUMNS
+    );
+    sqlx::query_as::<_, Favorite>(&sql)
+        .bind(id)
+        .fetch_one(&mut *conn)
+        .await
+}
+
+/// 插入一条收藏及其标签关联，返回新记录的ID
+/// 调用方负责事务的提交，并在提交后为 `implicit` 中自动创建的标签发布事件
+pub(crate) async fn insert_favorite(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+    payload: &CreateFavorite,
+    created_at: &str,
+    implicit: &mut Implicit,
+) -> Result<i64, AppError> {
+    ensure_category_owned(&mut *conn, user_id, payload.category_id).await?;
+
+    // 插入数据
+    let result = sqlx::query(
+        "INSERT INTO favorites (user_id, category_id, text, url, deep_link, dedup_key, domain, canonical_url,
+                                title, context_before, context_after, html, lang, created_at, updated_at) 
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(user_id)
+    .bind(payload.category_id)
+    .bind(&payload.text)
+    .bind(&payload.url)
+    .bind(text_fragment::deep_link(
+        &payload.url,
+        &payload.text,
+        non_empty(&payload.context_before),
+        non_empty(&payload.context_after),
+    ))
+    .bind(normalize::dedup_key(&payload.text, &payload.url))
+    .bind(url_normalize::host(&payload.url))
+    .bind(url_normalize::canonical_url(&payload.url))
+    .bind(non_empty(&payload.title))
+    .bind(non_empty(&payload.context_before))
+    .bind(non_empty(&payload.context_after))
+    .bind(clean_html(&payload.html))
+    .bind(non_empty(&payload.lang))
+    .bind(created_at)
+    .bind(created_at)
+    .execute(&mut *conn)
+    .await
+    .map_err(AppError::Database)?;
+
+    // 获取插入的记录ID
+    let id = result.last_insert_rowid();
+
+    // 写入标签关联
+    set_favorite_tags(&mut *conn, user_id, id, &payload.tags, implicit)
+        .await
+        .map_err(AppError::Database)?;
+
+    attach_page(&mut *conn, id).await.map_err(AppError::Database)?;
+
+    let after = favorite_snapshot(&mut *conn, id).await.map_err(AppError::Database)?;
+    audit::record(&mut *conn, user_id, Entity::Favorite, id, "create", None, after)
+        .await
+        .map_err(AppError::Database)?;
+
+    Ok(id)
 }
 
 /// 获取收藏列表
@@ -95,87 +732,131 @@ pub struct UpdateFavorite {
     path = "/api/favorites",
     tag = "favorites",
     params(
-        ("page" = Option<i64>, Query, description = "页码，默认为1"),
-        ("per_page" = Option<i64>, Query, description = "每页数量，默认为10"),
+        ("page" = Option<i64>, Query, description = "页码，默认为1，使用游标时忽略"),
+        ("per_page" = Option<i64>, Query, description = "每页数量，默认为10，最多100"),
+        ("sort" = Option<FavoriteSort>, Query, description = "排序字段：created_at、updated_at、url 或 category，默认为 created_at"),
+        ("order" = Option<SortOrder>, Query, description = "排序方向：asc 或 desc，默认为 desc"),
+        ("after" = Option<String>, Query, description = "游标，取自上次响应的 next_cursor，获取其后的一页"),
+        ("before" = Option<String>, Query, description = "游标，取自上次响应的 prev_cursor，获取其前的一页"),
+        ("include_total" = Option<bool>, Query, description = "是否返回总数，默认仅在按页码分页时返回"),
         ("search" = Option<String>, Query, description = "搜索关键词"),
         ("category_id" = Option<i64>, Query, description = "分类ID"),
-        ("tag_id" = Option<i64>, Query, description = "标签ID")
+        ("include_descendants" = Option<bool>, Query, description = "为 true 时包含子分类下的收藏"),
+        ("tag_id" = Option<i64>, Query, description = "标签ID"),
+        ("uncategorized" = Option<bool>, Query, description = "为 true 时只返回未分类的收藏"),
+        ("tags_all" = Option<String>, Query, description = "逗号分隔的标签ID，须同时带有全部标签"),
+        ("tags_any" = Option<String>, Query, description = "逗号分隔的标签ID，带有其中任一标签即可"),
+        ("exclude_tag" = Option<String>, Query, description = "逗号分隔的标签ID，排除带有这些标签的收藏"),
+        ("domain" = Option<String>, Query, description = "网址主机名，同时匹配其子域名，如 example.com"),
+        ("from" = Option<String>, Query, description = "创建日期下限（含，UTC），格式为 YYYY-MM-DD"),
+        ("to" = Option<String>, Query, description = "创建日期上限（含，UTC），格式为 YYYY-MM-DD"),
+        ("archived" = Option<bool>, Query, description = "为 true 时只返回已归档的收藏，默认只返回未归档的"),
+        ("tz" = Option<String>, Query, description = "IANA 时区名，如 Asia/Shanghai，也可通过 X-Timezone 请求头指定，默认为 UTC")
     ),
     responses(
         (status = 200, description = "成功获取收藏列表", body = FavoriteResponse),
+        (status = 400, description = "排序或筛选参数无效"),
+        (status = 422, description = "游标无效或与排序字段不符，或时区未知"),
         (status = 500, description = "服务器内部错误")
     )
 )]
 pub async fn list_favorites(
+    user: AuthUser,
+    tz: ClientTimezone,
     Query(params): Query<ListFavoriteQuery>,
     State(db): State<SqlitePool>,
 ) -> Result<Json<FavoriteResponse>, AppError> {
-    let page = params.page.unwrap_or(1);
-    let per_page = params.per_page.unwrap_or(10);
-    let offset = (page - 1) * per_page;
-
-    // 构建基础SQL查询
-    let mut sql = String::from(
-        "SELECT f.id, f.category_id, COALESCE(c.name, '未分类') as category_name, 
-         f.text, f.url, f.tags, f.created_at 
-         FROM favorites f 
-         LEFT JOIN categories c ON f.category_id = c.id"
-    );
-    let mut count_sql = String::from("SELECT COUNT(*) FROM favorites f");
-    let mut conditions = Vec::new();
-    let mut params_values = Vec::new();
-
-    // 处理搜索条件
-    if let Some(search) = &params.search {
-        conditions.push("f.text LIKE ?");
-        params_values.push(format!("%{}%", search));
-    }
-
-    // 处理分类筛选
-    if let Some(category_id) = params.category_id {
-        conditions.push("f.category_id = ?");
-        params_values.push(category_id.to_string());
-    }
+    let per_page = params.per_page.unwrap_or(10).clamp(1, MAX_PER_PAGE);
+    let sort = params.sort.unwrap_or(FavoriteSort::CreatedAt);
+    let order = params.order.unwrap_or(SortOrder::Desc);
 
-    // 处理标签筛选
-    if let Some(tag_id) = params.tag_id {
-        conditions.push("f.tags LIKE ?");
-        params_values.push(format!("%\"{}\":%", tag_id));
-    }
+    // 解析游标，before 优先于 after
+    let (cursor, backward) = match (params.before.as_deref(), params.after.as_deref()) {
+        (Some(value), _) => (
+            Some(Cursor::decode(value, sort).ok_or_else(|| AppError::field("before", "无效的游标"))?),
+            true,
+        ),
+        (None, Some(value)) => (
+            Some(Cursor::decode(value, sort).ok_or_else(|| AppError::field("after", "无效的游标"))?),
+            false,
+        ),
+        (None, None) => (None, false),
+    };
+    // 没有游标时按页码分页
+    let offset = if cursor.is_none() {
+        (params.page.unwrap_or(1).max(1) - 1) * per_page
+    } else {
+        0
+    };
 
-    // 添加WHERE子句
-    if !conditions.is_empty() {
-        let where_clause = format!(" WHERE {}", conditions.join(" AND "));
-        sql.push_str(&where_clause);
-        count_sql.push_str(&where_clause);
-    }
+    let (conditions, params_values) = params.conditions(user.id);
 
-    // 添加排序和分页
-    sql.push_str(" ORDER BY f.created_at DESC LIMIT ? OFFSET ?");
+    // 执行总数查询，游标分页默认跳过以保证深度翻页的速度
+    let total = if params.include_total.unwrap_or(cursor.is_none()) {
+        let count_sql = format!("SELECT COUNT(*) FROM favorites f WHERE {}", conditions);
+        let mut query = sqlx::query_scalar(&count_sql);
+        for param in &params_values {
+            query = query.bind(param);
+        }
+        Some(query.fetch_one(&db).await.map_err(AppError::Database)?)
+    } else {
+        None
+    };
 
-    // 执行总数查询
-    let mut query = sqlx::query_scalar(&count_sql);
-    for param in &params_values {
-        query = query.bind(param);
+    // 向前翻页时反向查询，取出后再恢复顺序
+    let scan_order = if backward { order.reverse() } else { order };
+    let mut sql = format!(
+        "SELECT {} FROM favorites f LEFT JOIN categories c ON f.category_id = c.id WHERE {}",
+        FAVORITE_COLUMNS, conditions
+    );
+    if cursor.is_some() {
+        let op = if scan_order == SortOrder::Asc { ">" } else { "<" };
+        sql.push_str(&format!(
+            " AND ({column} {op} ? OR ({column} = ? AND f.id {op} ?))",
+            column = sort.column(),
+            op = op
+        ));
     }
-    let total: i64 = query.fetch_one(&db)
-        .await
-        .map_err(AppError::Database)?;
+    // 多取一条用于判断是否还有更多
+    sql.push_str(&format!(
+        " ORDER BY {column} {dir}, f.id {dir} LIMIT ? OFFSET ?",
+        column = sort.column(),
+        dir = scan_order.sql()
+    ));
 
     // 执行列表查询
     let mut query = sqlx::query_as::<_, Favorite>(&sql);
     for param in &params_values {
         query = query.bind(param);
     }
-    query = query.bind(per_page).bind(offset);
+    if let Some(cursor) = &cursor {
+        query = query.bind(&cursor.key).bind(&cursor.key).bind(cursor.id);
+    }
+    query = query.bind(per_page + 1).bind(offset);
 
-    let items = query.fetch_all(&db)
+    let mut items = query.fetch_all(&db)
         .await
         .map_err(AppError::Database)?;
+    let has_more = items.len() as i64 > per_page;
+    items.truncate(per_page as usize);
+    if backward {
+        items.reverse();
+    }
+
+    // 生成前后页游标
+    let first = items.first().map(|favorite| Cursor::new(sort, favorite).encode());
+    let last = items.last().map(|favorite| Cursor::new(sort, favorite).encode());
+    let (next_cursor, prev_cursor) = if backward {
+        (last, first.filter(|_| has_more))
+    } else {
+        (last.filter(|_| has_more), first.filter(|_| cursor.is_some() || offset > 0))
+    };
 
     Ok(Json(FavoriteResponse {
         total,
-        items,
+        items: items.into_iter().map(|favorite| favorite.localize(&tz)).collect(),
+        next_cursor,
+        prev_cursor,
     }))
 }
 
@@ -184,66 +865,93 @@ pub async fn list_favorites(
     post,
     path = "/api/favorites",
     tag = "favorites",
+    params(
+        ("tz" = Option<String>, Query, description = "IANA 时区名，如 Asia/Shanghai，也可通过 X-Timezone 请求头指定，默认为 UTC")
+    ),
     request_body = CreateFavorite,
     responses(
-        (status = 201, description = "成功创建收藏", body = Favorite),
+        (status = 200, description = "成功创建收藏", body = Favorite),
         (status = 400, description = "无效的请求"),
+        (status = 409, description = "已存在重复的收藏，返回已有记录", body = Favorite),
         (status = 500, description = "服务器内部错误")
     )
 )]
 pub async fn create_favorite(
+    user: AuthUser,
+    tz: ClientTimezone,
     State(db): State<SqlitePool>,
-    Json(payload): Json<CreateFavorite>,
-) -> Result<Json<Favorite>, AppError> {
-    let tags_json = serde_json::to_string(&payload.tags)
-        .map_err(|e| AppError::BadRequest(e.to_string()))?;
-
+    State(events): State<EventBus>,
+    State(archiver): State<ArchiverHandle>,
+    ValidatedJson(payload): ValidatedJson<CreateFavorite>,
+) -> Result<(StatusCode, Json<Favorite>), AppError> {
     // 使用当前时间作为创建时间
-    let now = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let now = timezone::now();
 
     // 使用事务来确保数据一致性
     let mut tx = db.begin().await.map_err(AppError::Database)?;
 
-    // 插入数据
-    let result = sqlx::query(
-        "INSERT INTO favorites (category_id, text, url, tags, created_at) 
-         VALUES (?, ?, ?, ?, ?)"
-    )
-    .bind(payload.category_id)
-    .bind(&payload.text)
-    .bind(&payload.url)
-    .bind(&tags_json)
-    .bind(&now)
-    .execute(&mut *tx)
-    .await
-    .map_err(AppError::Database)?;
+    // 规范化后的文本和地址都相同时视为重复，返回已有记录
+    if !payload.allow_duplicate.unwrap_or(false) {
+        let existing = find_duplicate(&mut tx, user.id, &payload.text, &payload.url)
+            .await
+            .map_err(AppError::Database)?;
+        if let Some(id) = existing {
+            let favorite = fetch_favorite(&mut tx, id).await.map_err(AppError::Database)?;
+            return Ok((StatusCode::CONFLICT, Json(favorite.localize(&tz))));
+        }
+    }
 
-    // 获取插入的记录ID
-    let id = result.last_insert_rowid();
+    let mut implicit = Implicit::default();
+    let id = insert_favorite(&mut tx, user.id, &payload, &now, &mut implicit).await?;
 
     // 查询完整的收藏信息
-    let favorite = sqlx::query_as::<_, Favorite>(
-        "SELECT 
-            f.id,
-            f.category_id,
-            COALESCE(c.name, '未分类') as category_name,
-            f.text,
-            f.url,
-            f.tags,
-            f.created_at
-         FROM favorites f
-         LEFT JOIN categories c ON f.category_id = c.id
-         WHERE f.id = ?"
-    )
-    .bind(id)
-    .fetch_one(&mut *tx)
-    .await
-    .map_err(AppError::Database)?;
+    let favorite = fetch_favorite(&mut tx, id).await.map_err(AppError::Database)?;
 
     // 提交事务
     tx.commit().await.map_err(AppError::Database)?;
 
-    Ok(Json(favorite))
+    archiver.wake();
+    events.publish_implicit(user.id, implicit);
+    events.publish(user.id, Topic::Favorite, Action::Created, id);
+
+    Ok((StatusCode::OK, Json(favorite.localize(&tz))))
+}
+
+/// 获取单个收藏
+#[utoipa::path(
+    get,
+    path = "/api/favorites/{id}",
+    tag = "favorites",
+    params(
+        ("id" = i64, Path, description = "收藏ID"),
+        ("tz" = Option<String>, Query, description = "IANA 时区名，如 Asia/Shanghai，也可通过 X-Timezone 请求头指定，默认为 UTC")
+    ),
+    responses(
+        (status = 200, description = "成功获取收藏", body = Favorite),
+        (status = 404, description = "收藏不存在"),
+        (status = 500, description = "服务器内部错误")
+    )
+)]
+pub async fn get_favorite(
+    user: AuthUser,
+    tz: ClientTimezone,
+    Path(id): Path<i64>,
+    State(db): State<SqlitePool>,
+) -> Result<Json<Favorite>, AppError> {
+    let sql = format!(
+        "SELECT {} FROM favorites f LEFT JOIN categories c ON f.category_id = c.id
+         WHERE f.id = ? AND f.user_id = ? AND f.deleted_at IS NULL",
+        FAVORITE_COLUMNS
+    );
+    let favorite = sqlx::query_as::<_, Favorite>(&sql)
+        .bind(id)
+        .bind(user.id)
+        .fetch_optional(&db)
+        .await
+        .map_err(AppError::Database)?
+        .ok_or(AppError::NotFound)?;
+
+    Ok(Json(favorite.localize(&tz)))
 }
 
 /// 更新收藏
@@ -263,33 +971,205 @@ pub async fn create_favorite(
     )
 )]
 pub async fn update_favorite(
+    user: AuthUser,
     Path(id): Path<i64>,
     State(db): State<SqlitePool>,
-    Json(payload): Json<UpdateFavorite>,
+    State(events): State<EventBus>,
+    ValidatedJson(payload): ValidatedJson<UpdateFavorite>,
 ) -> Result<StatusCode, AppError> {
-    let tags_json = serde_json::to_string(&payload.tags)
-        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+    let mut tx = db.begin().await.map_err(AppError::Database)?;
 
-    let result = sqlx::query(
-        "UPDATE favorites SET category_id = ?, text = ?, url = ?, tags = ? WHERE id = ?"
+    ensure_category_owned(&mut tx, user.id, payload.category_id).await?;
+
+    // 未提供的前后文沿用原值，用于重新生成文本片段链接
+    let (context_before, context_after): (Option<String>, Option<String>) = sqlx::query_as(
+        "SELECT context_before, context_after FROM favorites
+         WHERE id = ? AND user_id = ? AND deleted_at IS NULL"
+    )
+    .bind(id)
+    .bind(user.id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(AppError::Database)?
+    .ok_or(AppError::NotFound)?;
+    let before = favorite_snapshot(&mut tx, id).await.map_err(AppError::Database)?;
+    let context_before = non_empty(&payload.context_before).or(context_before.as_deref());
+    let context_after = non_empty(&payload.context_after).or(context_after.as_deref());
+
+    sqlx::query(
+        "UPDATE favorites SET category_id = ?, text = ?, url = ?, deep_link = ?, dedup_key = ?, domain = ?, canonical_url = ?,
+                title = COALESCE(?, title),
+                context_before = ?,
+                context_after = ?,
+                html = COALESCE(?, html),
+                lang = COALESCE(?, lang),
+                updated_at = ?
+         WHERE id = ? AND user_id = ?"
     )
     .bind(payload.category_id)
-    .bind(payload.text)
-    .bind(payload.url)
-    .bind(tags_json)
+    .bind(&payload.text)
+    .bind(&payload.url)
+    .bind(text_fragment::deep_link(&payload.url, &payload.text, context_before, context_after))
+    .bind(normalize::dedup_key(&payload.text, &payload.url))
+    .bind(url_normalize::host(&payload.url))
+    .bind(url_normalize::canonical_url(&payload.url))
+    .bind(non_empty(&payload.title))
+    .bind(context_before)
+    .bind(context_after)
+    .bind(clean_html(&payload.html))
+    .bind(non_empty(&payload.lang))
+    .bind(timezone::now())
     .bind(id)
-    .execute(&db)
+    .bind(user.id)
+    .execute(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
-    if result.rows_affected() == 0 {
-        return Err(AppError::NotFound);
-    }
+    let mut implicit = Implicit::default();
+    set_favorite_tags(&mut tx, user.id, id, &payload.tags, &mut implicit)
+        .await
+        .map_err(AppError::Database)?;
+
+    attach_page(&mut tx, id).await.map_err(AppError::Database)?;
+
+    let after = favorite_snapshot(&mut tx, id).await.map_err(AppError::Database)?;
+    audit::record(&mut tx, user.id, Entity::Favorite, id, "update", before, after)
+        .await
+        .map_err(AppError::Database)?;
+
+    tx.commit().await.map_err(AppError::Database)?;
+
+    events.publish_implicit(user.id, implicit);
+    events.publish(user.id, Topic::Favorite, Action::Updated, id);
 
     Ok(StatusCode::OK)
 }
 
+/// 部分更新收藏
+/// 未提供的字段保持不变，标签可整体替换，也可逐个追加或移除
+#[utoipa::path(
+    patch,
+    path = "/api/favorites/{id}",
+    tag = "favorites",
+    params(
+        ("id" = i64, Path, description = "收藏ID"),
+        ("tz" = Option<String>, Query, description = "IANA 时区名，如 Asia/Shanghai，也可通过 X-Timezone 请求头指定，默认为 UTC")
+    ),
+    request_body = PatchFavorite,
+    responses(
+        (status = 200, description = "成功更新收藏，返回更新后的记录", body = Favorite),
+        (status = 400, description = "无效的请求"),
+        (status = 404, description = "收藏不存在"),
+        (status = 422, description = "字段校验失败"),
+        (status = 500, description = "服务器内部错误")
+    )
+)]
+pub async fn patch_favorite(
+    user: AuthUser,
+    tz: ClientTimezone,
+    Path(id): Path<i64>,
+    State(db): State<SqlitePool>,
+    State(events): State<EventBus>,
+    ValidatedJson(payload): ValidatedJson<PatchFavorite>,
+) -> Result<Json<Favorite>, AppError> {
+    let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+    let (old_category_id, old_text, old_url, old_context_before, old_context_after) =
+        sqlx::query_as::<_, (Option<i64>, String, String, Option<String>, Option<String>)>(
+        "SELECT category_id, text, url, context_before, context_after FROM favorites
+         WHERE id = ? AND user_id = ? AND deleted_at IS NULL"
+    )
+    .bind(id)
+    .bind(user.id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(AppError::Database)?
+    .ok_or(AppError::NotFound)?;
+
+    let before = favorite_snapshot(&mut tx, id).await.map_err(AppError::Database)?;
+
+    let category_id = payload.category_id.unwrap_or(old_category_id);
+    ensure_category_owned(&mut tx, user.id, category_id).await?;
+
+    // 合并新旧值，文本片段链接、查重键和域名据此重新生成
+    let text = payload.text.clone().unwrap_or(old_text);
+    let url = payload.url.clone().unwrap_or(old_url);
+    let context_before = match &payload.context_before {
+        Some(_) => non_empty(&payload.context_before).map(str::to_string),
+        None => old_context_before,
+    };
+    let context_after = match &payload.context_after {
+        Some(_) => non_empty(&payload.context_after).map(str::to_string),
+        None => old_context_after,
+    };
+
+    // 对可选字段，第一个参数标记是否提供，未提供时沿用原值
+    sqlx::query(
+        "UPDATE favorites SET category_id = ?, text = ?, url = ?, deep_link = ?, dedup_key = ?, domain = ?, canonical_url = ?,
+                context_before = ?, context_after = ?,
+                title = CASE WHEN ? THEN ? ELSE title END,
+                html = CASE WHEN ? THEN ? ELSE html END,
+                lang = CASE WHEN ? THEN ? ELSE lang END,
+                updated_at = ?
+         WHERE id = ? AND user_id = ?"
+    )
+    .bind(category_id)
+    .bind(&text)
+    .bind(&url)
+    .bind(text_fragment::deep_link(&url, &text, context_before.as_deref(), context_after.as_deref()))
+    .bind(normalize::dedup_key(&text, &url))
+    .bind(url_normalize::host(&url))
+    .bind(url_normalize::canonical_url(&url))
+    .bind(&context_before)
+    .bind(&context_after)
+    .bind(payload.title.is_some())
+    .bind(non_empty(&payload.title))
+    .bind(payload.html.is_some())
+    .bind(clean_html(&payload.html))
+    .bind(payload.lang.is_some())
+    .bind(non_empty(&payload.lang))
+    .bind(timezone::now())
+    .bind(id)
+    .bind(user.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(AppError::Database)?;
+
+    // 先整体替换，再处理追加和移除
+    let mut implicit = Implicit::default();
+    if let Some(tags) = &payload.tags {
+        set_favorite_tags(&mut tx, user.id, id, tags, &mut implicit)
+            .await
+            .map_err(AppError::Database)?;
+    }
+    if let Some(tags) = &payload.add_tags {
+        add_favorite_tags(&mut tx, user.id, id, tags, &mut implicit)
+            .await
+            .map_err(AppError::Database)?;
+    }
+    if let Some(tags) = &payload.remove_tags {
+        remove_favorite_tags(&mut tx, user.id, id, tags)
+            .await
+            .map_err(AppError::Database)?;
+    }
+
+    attach_page(&mut tx, id).await.map_err(AppError::Database)?;
+
+    let favorite = fetch_favorite(&mut tx, id).await.map_err(AppError::Database)?;
+    audit::record(&mut tx, user.id, Entity::Favorite, id, "update", before, audit::snapshot(&favorite))
+        .await
+        .map_err(AppError::Database)?;
+
+    tx.commit().await.map_err(AppError::Database)?;
+
+    events.publish_implicit(user.id, implicit);
+    events.publish(user.id, Topic::Favorite, Action::Updated, id);
+
+    Ok(Json(favorite.localize(&tz)))
+}
+
 /// 删除收藏
+/// 收藏移入回收站，超过保留期限后由后台任务彻底删除
 #[utoipa::path(
     delete,
     path = "/api/favorites/{id}",
@@ -298,23 +1178,168 @@ pub async fn update_favorite(
         ("id" = i64, Path, description = "收藏ID")
     ),
     responses(
-        (status = 200, description = "成功删除收藏"),
+        (status = 200, description = "成功将收藏移入回收站"),
+        (status = 404, description = "收藏不存在"),
         (status = 500, description = "服务器内部错误")
     )
 )]
 pub async fn delete_favorite(
+    user: AuthUser,
     Path(id): Path<i64>,
     State(db): State<SqlitePool>,
+    State(events): State<EventBus>,
 ) -> Result<StatusCode, AppError> {
-    let result = sqlx::query("DELETE FROM favorites WHERE id = ?")
-        .bind(id)
-        .execute(&db)
-        .await
-        .map_err(AppError::Database)?;
+    let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+    let before = favorite_snapshot(&mut tx, id).await.map_err(AppError::Database)?;
+
+    let result = sqlx::query(
+        "UPDATE favorites SET deleted_at = ? WHERE id = ? AND user_id = ? AND deleted_at IS NULL"
+    )
+    .bind(timezone::now())
+    .bind(id)
+    .bind(user.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(AppError::Database)?;
 
     if result.rows_affected() == 0 {
         return Err(AppError::NotFound);
     }
 
+    let after = favorite_snapshot(&mut tx, id).await.map_err(AppError::Database)?;
+    audit::record(&mut tx, user.id, Entity::Favorite, id, "delete", before, after)
+        .await
+        .map_err(AppError::Database)?;
+
+    tx.commit().await.map_err(AppError::Database)?;
+
+    events.publish(user.id, Topic::Favorite, Action::Deleted, id);
+
     Ok(StatusCode::OK)
-} 
\ No newline at end of file
+}
+
+/// 合并重复的收藏
+/// 标签取并集，笔记移到保留的收藏下，保留最早的创建时间，其余收藏移入回收站
+#[utoipa::path(
+    post,
+    path = "/api/favorites/merge",
+    tag = "favorites",
+    params(
+        ("tz" = Option<String>, Query, description = "IANA 时区名，如 Asia/Shanghai，也可通过 X-Timezone 请求头指定，默认为 UTC")
+    ),
+    request_body = MergeFavorites,
+    responses(
+        (status = 200, description = "成功合并收藏，返回合并后的记录", body = Favorite),
+        (status = 400, description = "无效的请求"),
+        (status = 404, description = "收藏不存在"),
+        (status = 500, description = "服务器内部错误")
+    )
+)]
+pub async fn merge_favorites(
+    user: AuthUser,
+    tz: ClientTimezone,
+    State(db): State<SqlitePool>,
+    State(events): State<EventBus>,
+    ValidatedJson(payload): ValidatedJson<MergeFavorites>,
+) -> Result<Json<Favorite>, AppError> {
+    let mut ids = payload.ids;
+    ids.sort_unstable();
+    ids.dedup();
+    if ids.len() < 2 {
+        return Err(AppError::BadRequest("至少需要两个不同的收藏ID".to_string()));
+    }
+    if payload.target_id.is_some_and(|id| !ids.contains(&id)) {
+        return Err(AppError::BadRequest("target_id 必须包含在 ids 中".to_string()));
+    }
+
+    let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+    // 查询待合并的收藏，按创建时间排序
+    let placeholders = vec!["?"; ids.len()].join(", ");
+    let sql = format!(
+        "SELECT id, created_at FROM favorites
+         WHERE user_id = ? AND deleted_at IS NULL AND id IN ({})
+         ORDER BY created_at, id",
+        placeholders
+    );
+    let mut query = sqlx::query_as::<_, (i64, String)>(&sql).bind(user.id);
+    for id in &ids {
+        query = query.bind(id);
+    }
+    let rows = query.fetch_all(&mut *tx).await.map_err(AppError::Database)?;
+    if rows.len() != ids.len() {
+        return Err(AppError::NotFound);
+    }
+
+    let (earliest_id, earliest_created_at) = rows[0].clone();
+    let target_id = payload.target_id.unwrap_or(earliest_id);
+    let others: Vec<i64> = ids.iter().copied().filter(|id| *id != target_id).collect();
+    let placeholders = vec!["?"; others.len()].join(", ");
+
+    // 合并前的内容写入审计日志
+    let mut befores = Vec::with_capacity(ids.len());
+    for id in &ids {
+        befores.push((*id, favorite_snapshot(&mut tx, *id).await.map_err(AppError::Database)?));
+    }
+
+    // 合并标签
+    let sql = format!(
+        "INSERT OR IGNORE INTO favorite_tags (favorite_id, tag_id)
+         SELECT ?, tag_id FROM favorite_tags WHERE favorite_id IN ({})",
+        placeholders
+    );
+    let mut query = sqlx::query(&sql).bind(target_id);
+    for id in &others {
+        query = query.bind(id);
+    }
+    query.execute(&mut *tx).await.map_err(AppError::Database)?;
+
+    // 保留最早的创建时间
+    sqlx::query("UPDATE favorites SET created_at = ?, updated_at = ? WHERE id = ?")
+        .bind(&earliest_created_at)
+        .bind(timezone::now())
+        .bind(target_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::Database)?;
+
+    // 笔记移动到保留的收藏下
+    let sql = format!("UPDATE notes SET favorite_id = ? WHERE favorite_id IN ({})", placeholders);
+    let mut query = sqlx::query(&sql).bind(target_id);
+    for id in &others {
+        query = query.bind(id);
+    }
+    query.execute(&mut *tx).await.map_err(AppError::Database)?;
+
+    // 其余收藏移入回收站，与删除收藏相同
+    let sql = format!("UPDATE favorites SET deleted_at = ? WHERE id IN ({})", placeholders);
+    let mut query = sqlx::query(&sql).bind(timezone::now());
+    for id in &others {
+        query = query.bind(id);
+    }
+    query.execute(&mut *tx).await.map_err(AppError::Database)?;
+
+    let favorite = fetch_favorite(&mut tx, target_id).await.map_err(AppError::Database)?;
+
+    // 保留的收藏记为合并，其余记为删除，可从回收站恢复
+    for (id, before) in befores {
+        if id == target_id {
+            audit::record(&mut tx, user.id, Entity::Favorite, id, "merge", before, audit::snapshot(&favorite))
+                .await
+                .map_err(AppError::Database)?;
+        } else {
+            let after = favorite_snapshot(&mut tx, id).await.map_err(AppError::Database)?;
+            audit::record(&mut tx, user.id, Entity::Favorite, id, "delete", before, after)
+                .await
+                .map_err(AppError::Database)?;
+        }
+    }
+
+    tx.commit().await.map_err(AppError::Database)?;
+
+    events.publish_all(user.id, Topic::Favorite, Action::Deleted, others);
+    events.publish(user.id, Topic::Favorite, Action::Updated, target_id);
+
+    Ok(Json(favorite.localize(&tz)))
+}
@@ -0,0 +1,219 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::sqlite::SqlitePool;
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use crate::audit::{self, Entity};
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::events::{Action, EventBus, Implicit, Topic};
+use crate::handlers::favorite::{
+    ensure_category_owned, favorite_snapshot, fetch_favorite, set_favorite_tags, Favorite,
+};
+use crate::handlers::page::attach_page;
+use crate::normalize;
+use crate::url_normalize;
+use crate::text_fragment;
+use crate::timezone::{self, ClientTimezone};
+
+/// 审计日志中的一条修改记录
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct HistoryEntry {
+    pub version: i64,       // 版本号，从 1 开始递增
+    pub action: String,     // 动作：create、update、delete、restore、archive 等
+    pub actor_id: i64,      // 执行修改的用户ID
+    pub actor_name: String, // 执行修改的用户名
+    #[schema(value_type = Option<Object>)]
+    pub before: Option<Value>, // 修改前的内容，创建时为 null
+    #[schema(value_type = Option<Object>)]
+    pub after: Option<Value>,  // 修改后的内容，彻底删除时为 null
+    pub created_at: String,
+}
+
+/// 审计日志查询结果的单行
+#[derive(FromRow)]
+struct HistoryRow {
+    version: i64,
+    action: String,
+    actor_id: i64,
+    actor_name: String,
+    before: Option<String>,
+    after: Option<String>,
+    created_at: String,
+}
+
+impl HistoryRow {
+    fn into_entry(self, tz: &ClientTimezone) -> HistoryEntry {
+        let parse = |json: Option<String>| json.and_then(|json| serde_json::from_str(&json).ok());
+        let mut entry = HistoryEntry {
+            version: self.version,
+            action: self.action,
+            actor_id: self.actor_id,
+            actor_name: self.actor_name,
+            before: parse(self.before),
+            after: parse(self.after),
+            created_at: self.created_at,
+        };
+        tz.apply(&mut entry.created_at);
+        entry
+    }
+}
+
+/// 获取收藏的修改历史，按版本升序
+/// 回收站中和已彻底删除的收藏同样可以查询
+#[utoipa::path(
+    get,
+    path = "/api/favorites/{id}/history",
+    tag = "favorites",
+    params(
+        ("id" = i64, Path, description = "收藏ID"),
+        ("tz" = Option<String>, Query, description = "IANA 时区名，如 Asia/Shanghai，也可通过 X-Timezone 请求头指定，默认为 UTC")
+    ),
+    responses(
+        (status = 200, description = "成功获取修改历史", body = Vec<HistoryEntry>),
+        (status = 404, description = "收藏不存在或没有修改记录"),
+        (status = 500, description = "服务器内部错误")
+    )
+)]
+pub async fn favorite_history(
+    user: AuthUser,
+    tz: ClientTimezone,
+    Path(id): Path<i64>,
+    State(db): State<SqlitePool>,
+) -> Result<Json<Vec<HistoryEntry>>, AppError> {
+    // 收藏只能由其所有者修改，按执行人筛选即可限定在当前用户的数据内
+    let rows = sqlx::query_as::<_, HistoryRow>(
+        "SELECT a.version, a.action, a.actor_id, u.name as actor_name, a.before, a.after, a.created_at
+         FROM audit_log a
+         JOIN users u ON u.id = a.actor_id
+         WHERE a.entity_type = ? AND a.entity_id = ? AND a.actor_id = ?
+         ORDER BY a.version"
+    )
+    .bind(Entity::Favorite.as_str())
+    .bind(id)
+    .bind(user.id)
+    .fetch_all(&db)
+    .await
+    .map_err(AppError::Database)?;
+
+    if rows.is_empty() {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(Json(rows.into_iter().map(|row| row.into_entry(&tz)).collect()))
+}
+
+/// 将收藏恢复为某个历史版本的内容
+/// 恢复本身也会作为新版本写入历史，回收站中的收藏需先从回收站恢复
+#[utoipa::path(
+    post,
+    path = "/api/favorites/{id}/revert/{version}",
+    tag = "favorites",
+    params(
+        ("id" = i64, Path, description = "收藏ID"),
+        ("version" = i64, Path, description = "要恢复到的版本号"),
+        ("tz" = Option<String>, Query, description = "IANA 时区名，如 Asia/Shanghai，也可通过 X-Timezone 请求头指定，默认为 UTC")
+    ),
+    responses(
+        (status = 200, description = "成功恢复，返回恢复后的记录", body = Favorite),
+        (status = 400, description = "该版本没有可恢复的内容，或其分类已不存在"),
+        (status = 404, description = "收藏或版本不存在"),
+        (status = 500, description = "服务器内部错误")
+    )
+)]
+pub async fn revert_favorite(
+    user: AuthUser,
+    tz: ClientTimezone,
+    Path((id, version)): Path<(i64, i64)>,
+    State(db): State<SqlitePool>,
+    State(events): State<EventBus>,
+) -> Result<Json<Favorite>, AppError> {
+    let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+    let exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM favorites WHERE id = ? AND user_id = ? AND deleted_at IS NULL)"
+    )
+    .bind(id)
+    .bind(user.id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(AppError::Database)?;
+    if !exists {
+        return Err(AppError::NotFound);
+    }
+
+    // 版本的内容即该次修改之后的状态
+    let after: Option<String> = sqlx::query_scalar(
+        "SELECT after FROM audit_log
+         WHERE entity_type = ? AND entity_id = ? AND actor_id = ? AND version = ?"
+    )
+    .bind(Entity::Favorite.as_str())
+    .bind(id)
+    .bind(user.id)
+    .bind(version)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(AppError::Database)?
+    .ok_or(AppError::NotFound)?;
+    let target: Favorite = after
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .ok_or_else(|| AppError::BadRequest(format!("版本 {} 没有可恢复的内容", version)))?;
+
+    ensure_category_owned(&mut tx, user.id, target.category_id).await?;
+
+    let before = favorite_snapshot(&mut tx, id).await.map_err(AppError::Database)?;
+
+    sqlx::query(
+        "UPDATE favorites SET category_id = ?, text = ?, url = ?, deep_link = ?, dedup_key = ?, domain = ?, canonical_url = ?,
+                title = ?, context_before = ?, context_after = ?, html = ?, lang = ?,
+                archived_at = ?, updated_at = ?
+         WHERE id = ? AND user_id = ?"
+    )
+    .bind(target.category_id)
+    .bind(&target.text)
+    .bind(&target.url)
+    .bind(text_fragment::deep_link(
+        &target.url,
+        &target.text,
+        target.context_before.as_deref(),
+        target.context_after.as_deref(),
+    ))
+    .bind(normalize::dedup_key(&target.text, &target.url))
+    .bind(url_normalize::host(&target.url))
+    .bind(url_normalize::canonical_url(&target.url))
+    .bind(&target.title)
+    .bind(&target.context_before)
+    .bind(&target.context_after)
+    .bind(&target.html)
+    .bind(&target.lang)
+    .bind(&target.archived_at)
+    .bind(timezone::now())
+    .bind(id)
+    .bind(user.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(AppError::Database)?;
+
+    let tags: Vec<String> = serde_json::from_str(&target.tags).unwrap_or_default();
+    let mut implicit = Implicit::default();
+    set_favorite_tags(&mut tx, user.id, id, &tags, &mut implicit)
+        .await
+        .map_err(AppError::Database)?;
+    attach_page(&mut tx, id).await.map_err(AppError::Database)?;
+
+    let favorite = fetch_favorite(&mut tx, id).await.map_err(AppError::Database)?;
+    audit::record(&mut tx, user.id, Entity::Favorite, id, "revert", before, audit::snapshot(&favorite))
+        .await
+        .map_err(AppError::Database)?;
+
+    tx.commit().await.map_err(AppError::Database)?;
+
+    events.publish_implicit(user.id, implicit);
+    events.publish(user.id, Topic::Favorite, Action::Updated, id);
+
+    Ok(Json(favorite.localize(&tz)))
+}
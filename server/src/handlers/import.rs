@@ -0,0 +1,722 @@
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap},
+    Json,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{SqliteConnection, SqlitePool};
+use utoipa::ToSchema;
+use validator::Validate;
+use crate::archiver::ArchiverHandle;
+use crate::auth::AuthUser;
+use crate::error::{self, AppError};
+use crate::events::{Action, EventBus, Implicit, Topic};
+use crate::handlers::export::CSV_TAG_SEPARATOR;
+use crate::handlers::favorite::{find_duplicate, insert_favorite, CreateFavorite};
+use crate::timezone;
+
+/// 导入格式，与导出格式对应
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    Json,
+    Csv,
+    Html,
+}
+
+/// 导入查询参数
+#[derive(Debug, Deserialize)]
+pub struct ImportQuery {
+    pub format: Option<ImportFormat>, // 导入格式，缺省时根据 Content-Type 和内容判断
+    pub dry_run: Option<bool>,        // 仅校验，不提交
+}
+
+/// 待导入的一条收藏，字段与导出的 ExportItem 一致
+#[derive(Debug, Default, Deserialize)]
+struct ImportRecord {
+    #[serde(default)]
+    category: Option<String>,
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    url: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    created_at: Option<String>,
+    #[serde(skip)]
+    folders: Vec<String>, // 书签所在的文件夹路径，从外到内
+}
+
+impl ImportRecord {
+    /// 分类路径，书签按文件夹逐级对应，其他格式只有一级分类名称
+    fn category_path(&self) -> Vec<&str> {
+        if self.folders.is_empty() {
+            self.category.iter().map(|c| c.trim()).filter(|c| !c.is_empty()).collect()
+        } else {
+            self.folders.iter().map(|f| f.trim()).filter(|f| !f.is_empty()).collect()
+        }
+    }
+}
+
+/// 单行导入状态
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportStatus {
+    Created,
+    Duplicate,
+    Invalid,
+}
+
+/// 单行导入结果
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ImportRowResult {
+    pub row: usize,              // 行号，从1开始
+    pub status: ImportStatus,
+    pub id: Option<i64>,         // 新建或已存在的收藏ID，试运行时新建的记录没有ID
+    pub message: Option<String>, // 无效原因
+}
+
+/// 导入结果
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ImportResponse {
+    pub dry_run: bool,
+    pub created: usize,
+    pub duplicates: usize,
+    pub invalid: usize,
+    pub rows: Vec<ImportRowResult>,
+}
+
+/// 根据 Content-Type 和内容推断导入格式
+fn detect_format(headers: &HeaderMap, body: &str) -> ImportFormat {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+
+    if content_type.contains("json") {
+        return ImportFormat::Json;
+    }
+    if content_type.contains("csv") {
+        return ImportFormat::Csv;
+    }
+    if content_type.contains("html") {
+        return ImportFormat::Html;
+    }
+
+    let trimmed = body.trim_start_matches('\u{feff}').trim_start();
+    if trimmed.starts_with('[') {
+        ImportFormat::Json
+    } else if trimmed.starts_with('<') {
+        ImportFormat::Html
+    } else {
+        ImportFormat::Csv
+    }
+}
+
+/// 解析 JSON 数组，单条记录的结构错误记为无效行
+fn parse_json(body: &str) -> Result<Vec<Result<ImportRecord, String>>, AppError> {
+    let values: Vec<serde_json::Value> = serde_json::from_str(body)
+        .map_err(|e| AppError::BadRequest(format!("JSON 格式错误: {}", e)))?;
+
+    Ok(values
+        .into_iter()
+        .map(|value| serde_json::from_value(value).map_err(|e| e.to_string()))
+        .collect())
+}
+
+/// 按 RFC 4180 解析 CSV，支持引号内的逗号、引号和换行
+fn parse_csv_rows(input: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = input.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            if c == '"' {
+                if chars.next_if_eq(&'"').is_some() {
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            } else {
+                field.push(c);
+            }
+            continue;
+        }
+
+        match c {
+            '"' => quoted = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    // 忽略空行
+    rows.retain(|r| !(r.len() == 1 && r[0].trim().is_empty()));
+    rows
+}
+
+/// 解析带表头的 CSV
+fn parse_csv(body: &str) -> Result<Vec<Result<ImportRecord, String>>, AppError> {
+    let mut rows = parse_csv_rows(body).into_iter();
+    let header: Vec<String> = rows
+        .next()
+        .ok_or_else(|| AppError::BadRequest("CSV 内容为空".to_string()))?
+        .iter()
+        .map(|h| h.trim().to_ascii_lowercase())
+        .collect();
+
+    let column = |name: &str| header.iter().position(|h| h == name);
+    let (Some(text_col), Some(url_col)) = (column("text"), column("url")) else {
+        return Err(AppError::BadRequest("CSV 缺少 text 或 url 列".to_string()));
+    };
+    let category_col = column("category");
+    let tags_col = column("tags");
+    let created_col = column("created_at");
+
+    Ok(rows
+        .map(|row| {
+            let get = |col: Option<usize>| col.and_then(|i| row.get(i)).cloned();
+            Ok(ImportRecord {
+                category: get(category_col).filter(|c| !c.trim().is_empty()),
+                text: get(Some(text_col)).unwrap_or_default(),
+                url: get(Some(url_col)).unwrap_or_default(),
+                tags: get(tags_col)
+                    .map(|tags| tags.split(CSV_TAG_SEPARATOR).map(|t| t.trim().to_string()).collect())
+                    .unwrap_or_default(),
+                created_at: get(created_col).filter(|c| !c.trim().is_empty()),
+                folders: Vec::new(),
+            })
+        })
+        .collect())
+}
+
+/// 还原 HTML 实体
+fn unescape_html(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let decoded = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, end))
+        });
+
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// 书签文件中的标签
+struct HtmlTag {
+    name: String,
+    closing: bool,
+    attrs: Vec<(String, String)>,
+}
+
+impl HtmlTag {
+    /// 解析 `<...>` 之间的内容
+    fn parse(raw: &str) -> Self {
+        let raw = raw.trim().trim_end_matches('/');
+        let (closing, raw) = match raw.strip_prefix('/') {
+            Some(rest) => (true, rest),
+            None => (false, raw),
+        };
+        let name_end = raw.find(char::is_whitespace).unwrap_or(raw.len());
+        let name = raw[..name_end].to_ascii_uppercase();
+
+        let mut attrs = Vec::new();
+        let mut rest = raw[name_end..].trim_start();
+        while !rest.is_empty() {
+            let key_end = rest
+                .find(|c: char| c == '=' || c.is_whitespace())
+                .unwrap_or(rest.len());
+            let key = rest[..key_end].to_ascii_uppercase();
+            rest = rest[key_end..].trim_start();
+
+            let mut value = String::new();
+            if let Some(after) = rest.strip_prefix('=') {
+                let after = after.trim_start();
+                let (raw_value, remaining) = match after.chars().next() {
+                    Some(q @ ('"' | '\'')) => {
+                        let end = after[1..].find(q).map(|i| i + 1).unwrap_or(after.len());
+                        (&after[1..end], after.get(end + 1..).unwrap_or_default())
+                    }
+                    _ => {
+                        let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                        (&after[..end], &after[end..])
+                    }
+                };
+                value = unescape_html(raw_value);
+                rest = remaining.trim_start();
+            }
+
+            if !key.is_empty() {
+                attrs.push((key, value));
+            }
+        }
+
+        HtmlTag { name, closing, attrs }
+    }
+
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// 解析 Netscape 书签文件，嵌套的文件夹按层级作为分类和子分类
+fn parse_bookmarks(body: &str) -> Vec<Result<ImportRecord, String>> {
+    enum Capture {
+        None,
+        Folder,
+        Title,
+        Description,
+    }
+
+    let mut records = Vec::new();
+    let mut folders: Vec<Option<String>> = Vec::new();
+    let mut pending_folder: Option<String> = None;
+    let mut folder_name = String::new();
+    let mut current: Option<(ImportRecord, String, String)> = None;
+    let mut capture = Capture::None;
+
+    // 结束当前书签，描述优先作为收藏文本，否则使用标题
+    let finish = |current: &mut Option<(ImportRecord, String, String)>,
+                  records: &mut Vec<Result<ImportRecord, String>>| {
+        if let Some((mut record, title, description)) = current.take() {
+            let description = description.trim();
+            record.text = if description.is_empty() {
+                title.trim().to_string()
+            } else {
+                description.to_string()
+            };
+            records.push(Ok(record));
+        }
+    };
+
+    let mut rest = body;
+    loop {
+        let (text, tag) = match rest.find('<') {
+            Some(start) => {
+                let end = rest[start..].find('>').map(|i| start + i).unwrap_or(rest.len());
+                let tag = HtmlTag::parse(&rest[start + 1..end]);
+                let text = &rest[..start];
+                rest = rest.get(end + 1..).unwrap_or_default();
+                (text, Some(tag))
+            }
+            None => {
+                let text = rest;
+                rest = "";
+                (text, None)
+            }
+        };
+
+        let text = unescape_html(text);
+        match capture {
+            Capture::Folder => folder_name.push_str(&text),
+            Capture::Title => {
+                if let Some((_, title, _)) = current.as_mut() {
+                    title.push_str(&text);
+                }
+            }
+            Capture::Description => {
+                if let Some((_, _, description)) = current.as_mut() {
+                    description.push_str(text.trim_matches('\n'));
+                }
+            }
+            Capture::None => {}
+        }
+
+        let Some(tag) = tag else {
+            break;
+        };
+
+        match (tag.name.as_str(), tag.closing) {
+            ("H3", false) => {
+                finish(&mut current, &mut records);
+                folder_name.clear();
+                capture = Capture::Folder;
+            }
+            ("H3", true) => {
+                pending_folder = Some(folder_name.trim().to_string());
+                capture = Capture::None;
+            }
+            ("DL", false) => {
+                finish(&mut current, &mut records);
+                folders.push(pending_folder.take());
+                capture = Capture::None;
+            }
+            ("DL", true) => {
+                finish(&mut current, &mut records);
+                folders.pop();
+                capture = Capture::None;
+            }
+            ("DT", false) => {
+                finish(&mut current, &mut records);
+                capture = Capture::None;
+            }
+            ("A", false) => {
+                finish(&mut current, &mut records);
+                let created_at = tag
+                    .attr("ADD_DATE")
+                    .and_then(|ts| ts.trim().parse::<i64>().ok())
+                    .and_then(|ts| Utc.timestamp_opt(ts, 0).single())
+                    .map(timezone::to_storage);
+                let record = ImportRecord {
+                    category: None,
+                    text: String::new(),
+                    url: tag.attr("HREF").unwrap_or_default().to_string(),
+                    tags: tag
+                        .attr("TAGS")
+                        .map(|tags| tags.split(',').map(|t| t.trim().to_string()).collect())
+                        .unwrap_or_default(),
+                    created_at,
+                    folders: folders.iter().flatten().cloned().collect(),
+                };
+                current = Some((record, String::new(), String::new()));
+                capture = Capture::Title;
+            }
+            ("A", true) => capture = Capture::None,
+            ("DD", false) => capture = Capture::Description,
+            ("BR", false) => {
+                if let (Capture::Description, Some((_, _, description))) = (&capture, current.as_mut()) {
+                    description.push('\n');
+                }
+            }
+            _ => {}
+        }
+    }
+
+    finish(&mut current, &mut records);
+    records
+}
+
+/// 规范化导入的创建时间为 UTC，无法识别时返回 None
+/// 不带时区的 `YYYY-MM-DD HH:MM:SS` 按服务器本地时间处理，兼容旧版本的导出文件
+fn normalize_created_at(value: &str) -> Option<String> {
+    let value = value.trim();
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(timezone::to_storage)
+        .or_else(|| timezone::from_legacy(value))
+}
+
+/// 从顶级分类开始按路径逐级查找分类，不存在的层级创建为上一级的子分类，新分类的ID记入 `implicit`
+/// 路径为空时返回 None
+async fn resolve_category(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+    path: &[&str],
+    implicit: &mut Implicit,
+) -> Result<Option<i64>, sqlx::Error> {
+    let mut parent_id: Option<i64> = None;
+    for name in path {
+        let result = sqlx::query("INSERT OR IGNORE INTO categories (user_id, parent_id, name) VALUES (?, ?, ?)")
+            .bind(user_id)
+            .bind(parent_id)
+            .bind(name)
+            .execute(&mut *conn)
+            .await?;
+        if result.rows_affected() > 0 {
+            implicit.categories.push(result.last_insert_rowid());
+        }
+
+        let id = sqlx::query_scalar("SELECT id FROM categories WHERE user_id = ? AND parent_id IS ? AND name = ?")
+            .bind(user_id)
+            .bind(parent_id)
+            .bind(name)
+            .fetch_one(&mut *conn)
+            .await?;
+        parent_id = Some(id);
+    }
+    Ok(parent_id)
+}
+
+/// 导入单条记录
+async fn import_record(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+    mut record: ImportRecord,
+    now: &str,
+    implicit: &mut Implicit,
+) -> Result<(ImportStatus, Option<i64>, Option<String>), AppError> {
+    let invalid = |message: &str| Ok((ImportStatus::Invalid, None, Some(message.to_string())));
+
+    let text = record.text.trim();
+    let url = record.url.trim();
+    if text.is_empty() {
+        return invalid("text 不能为空");
+    }
+    if url.is_empty() {
+        return invalid("url 不能为空");
+    }
+    let created_at = match record.created_at.as_deref() {
+        Some(value) => match normalize_created_at(value) {
+            Some(created_at) => created_at,
+            None => return invalid("created_at 格式无效"),
+        },
+        None => now.to_string(),
+    };
+
+    let mut payload = CreateFavorite {
+        category_id: None,
+        text: text.to_string(),
+        url: url.to_string(),
+        tags: std::mem::take(&mut record.tags),
+        title: None,
+        context_before: None,
+        context_after: None,
+        html: None,
+        lang: None,
+        allow_duplicate: None,
+    };
+
+    // 与创建收藏相同的校验规则，不符合的记为无效行
+    if let Err(errors) = payload.validate() {
+        let message = error::field_errors(&errors)
+            .into_iter()
+            .map(|(field, message)| format!("{}: {}", field, message))
+            .collect::<Vec<_>>()
+            .join("；");
+        return invalid(&message);
+    }
+
+    // 与创建收藏相同的查重规则
+    let existing = find_duplicate(&mut *conn, user_id, text, url)
+        .await
+        .map_err(AppError::Database)?;
+    if let Some(id) = existing {
+        return Ok((ImportStatus::Duplicate, Some(id), None));
+    }
+
+    payload.category_id = resolve_category(&mut *conn, user_id, &record.category_path(), implicit)
+        .await
+        .map_err(AppError::Database)?;
+    let id = insert_favorite(&mut *conn, user_id, &payload, &created_at, implicit).await?;
+
+    Ok((ImportStatus::Created, Some(id), None))
+}
+
+/// 批量导入收藏
+#[utoipa::path(
+    post,
+    path = "/api/import",
+    tag = "export",
+    params(
+        ("format" = Option<ImportFormat>, Query, description = "导入格式：json、csv、html（Netscape 书签文件），缺省时自动识别"),
+        ("dry_run" = Option<bool>, Query, description = "为 true 时只返回逐行结果，不提交")
+    ),
+    request_body(content = String, description = "与导出格式相同的 JSON、CSV 或书签文件内容"),
+    responses(
+        (status = 200, description = "导入结果", body = ImportResponse),
+        (status = 400, description = "文件无法解析"),
+        (status = 401, description = "未认证"),
+        (status = 500, description = "服务器内部错误")
+    )
+)]
+pub async fn import_favorites(
+    user: AuthUser,
+    Query(params): Query<ImportQuery>,
+    State(db): State<SqlitePool>,
+    State(events): State<EventBus>,
+    State(archiver): State<ArchiverHandle>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Json<ImportResponse>, AppError> {
+    let dry_run = params.dry_run.unwrap_or(false);
+    let format = params.format.unwrap_or_else(|| detect_format(&headers, &body));
+
+    let records = match format {
+        ImportFormat::Json => parse_json(&body)?,
+        ImportFormat::Csv => parse_csv(&body)?,
+        ImportFormat::Html => parse_bookmarks(&body),
+    };
+
+    let now = timezone::now();
+
+    // 所有记录在同一事务中导入，试运行时回滚
+    let mut tx = db.begin().await.map_err(AppError::Database)?;
+    let mut response = ImportResponse {
+        dry_run,
+        created: 0,
+        duplicates: 0,
+        invalid: 0,
+        rows: Vec::with_capacity(records.len()),
+    };
+    let mut implicit = Implicit::default();
+
+    for (index, record) in records.into_iter().enumerate() {
+        let (status, id, message) = match record {
+            Ok(record) => import_record(&mut tx, user.id, record, &now, &mut implicit).await?,
+            Err(message) => (ImportStatus::Invalid, None, Some(message)),
+        };
+
+        match status {
+            ImportStatus::Created => response.created += 1,
+            ImportStatus::Duplicate => response.duplicates += 1,
+            ImportStatus::Invalid => response.invalid += 1,
+        }
+
+        response.rows.push(ImportRowResult {
+            row: index + 1,
+            status,
+            id: if dry_run && status == ImportStatus::Created { None } else { id },
+            message,
+        });
+    }
+
+    if dry_run {
+        tx.rollback().await.map_err(AppError::Database)?;
+    } else {
+        tx.commit().await.map_err(AppError::Database)?;
+        archiver.wake();
+        events.publish_implicit(user.id, implicit);
+
+        let created = response
+            .rows
+            .iter()
+            .filter(|row| row.status == ImportStatus::Created)
+            .filter_map(|row| row.id);
+        events.publish_all(user.id, Topic::Favorite, Action::Created, created);
+    }
+
+    Ok(Json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_detect_format() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(detect_format(&headers, "\u{feff} [{}]"), ImportFormat::Json);
+        assert_eq!(detect_format(&headers, "<!DOCTYPE NETSCAPE-Bookmark-file-1>"), ImportFormat::Html);
+        assert_eq!(detect_format(&headers, "text,url"), ImportFormat::Csv);
+
+        // Content-Type 优先于内容
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("text/csv; charset=utf-8"));
+        assert_eq!(detect_format(&headers, "[]"), ImportFormat::Csv);
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let records = parse_json(r#"[{"text": "a", "url": "https://example.com", "tags": ["x"]}, {"text": 1}]"#).unwrap();
+        let record = records[0].as_ref().unwrap();
+        assert_eq!((record.text.as_str(), record.url.as_str()), ("a", "https://example.com"));
+        assert_eq!(record.tags, vec!["x"]);
+        assert!(records[1].is_err());
+
+        assert!(parse_json("{").is_err());
+    }
+
+    #[test]
+    fn test_parse_csv_rows() {
+        assert_eq!(
+            parse_csv_rows("\u{feff}a,\"b,\"\"c\"\"\r\nd\"\r\n\r\nx,\n"),
+            vec![vec!["a".to_string(), "b,\"c\"\r\nd".to_string()], vec!["x".to_string(), String::new()]]
+        );
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let records = parse_csv("URL,Text,Tags,Created_At,Category\nhttps://example.com,hi, a ;b,,  \n").unwrap();
+        let record = records[0].as_ref().unwrap();
+        assert_eq!((record.text.as_str(), record.url.as_str()), ("hi", "https://example.com"));
+        assert_eq!(record.tags, vec!["a", "b"]);
+        assert_eq!(record.created_at, None);
+        assert!(record.category_path().is_empty());
+
+        assert!(parse_csv("").is_err());
+        assert!(parse_csv("text,category\nhi,x").is_err());
+    }
+
+    #[test]
+    fn test_unescape_html() {
+        assert_eq!(unescape_html("a &amp; b &lt;&#65;&#x42;&unknown; & c"), "a & b <AB&unknown; & c");
+    }
+
+    #[test]
+    fn test_parse_bookmarks() {
+        let body = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=UTF-8">
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks</H1>
+<DL><p>
+    <DT><H3 ADD_DATE="1">阅读</H3>
+    <DL><p>
+        <DT><H3>技术 &amp; 工具</H3>
+        <DL><p>
+            <DT><A HREF="https://example.com/a?x=1&amp;y=2" ADD_DATE="1700000000" TAGS="rust, web">Rust &amp; Web</A>
+            <DD>描述第一行<BR>第二行
+        </DL><p>
+        <DT><A HREF="https://example.com/b">B</A>
+    </DL><p>
+    <DT><a href='https://example.com/c'>C</a>
+</DL><p>
+"#;
+        let records: Vec<ImportRecord> = parse_bookmarks(body).into_iter().map(Result::unwrap).collect();
+        assert_eq!(records.len(), 3);
+
+        assert_eq!(records[0].url, "https://example.com/a?x=1&y=2");
+        assert_eq!(records[0].text, "描述第一行\n第二行");
+        assert_eq!(records[0].tags, vec!["rust", "web"]);
+        assert_eq!(records[0].created_at.as_deref(), Some("2023-11-14T22:13:20Z"));
+        assert_eq!(records[0].category_path(), vec!["阅读", "技术 & 工具"]);
+
+        assert_eq!((records[1].text.as_str(), records[1].category_path()), ("B", vec!["阅读"]));
+        assert_eq!((records[2].url.as_str(), records[2].text.as_str()), ("https://example.com/c", "C"));
+        assert!(records[2].category_path().is_empty());
+    }
+
+    #[test]
+    fn test_normalize_created_at() {
+        assert_eq!(normalize_created_at(" 2024-03-01T08:00:00+08:00 ").as_deref(), Some("2024-03-01T00:00:00Z"));
+        assert!(normalize_created_at("2024-03-01 08:00:00").is_some());
+        assert_eq!(normalize_created_at("yesterday"), None);
+    }
+}
@@ -0,0 +1,132 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqlitePool;
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::handlers::favorite::{Favorite, FAVORITE_COLUMNS, MAX_PER_PAGE};
+use crate::timezone::ClientTimezone;
+
+/// 失效链接的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum LinkState {
+    Gone,  // 请求失败或返回 4xx、5xx
+    Moved, // 重定向到了其他页面
+}
+
+impl LinkState {
+    /// 该类型对应的筛选条件
+    fn condition(self) -> &'static str {
+        match self {
+            LinkState::Gone => "(l.error IS NOT NULL OR l.last_status >= 400)",
+            LinkState::Moved => "(l.error IS NULL AND l.last_status < 400 AND l.moved)",
+        }
+    }
+}
+
+/// 失效链接查询参数
+#[derive(Debug, Deserialize)]
+pub struct BrokenLinkQuery {
+    pub state: Option<LinkState>, // 只返回该类型，默认两者都返回
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+/// 来源网页已失效或迁移的收藏
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct BrokenLink {
+    #[sqlx(flatten)]
+    pub favorite: Favorite,
+    pub state: LinkState,
+    pub last_status: Option<i64>,  // 最近一次检查的状态码，请求失败时为 null
+    pub final_url: Option<String>, // 跟随重定向后的网址
+    pub error: Option<String>,     // 请求失败的原因
+    pub last_checked_at: String,
+}
+
+/// 失效链接列表响应
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BrokenLinkResponse {
+    pub total: i64,
+    pub items: Vec<BrokenLink>,
+}
+
+/// 获取来源网页已失效或迁移的收藏，按最近检查时间倒序
+/// 检查结果由后台任务定期更新，需在配置中启用
+#[utoipa::path(
+    get,
+    path = "/api/links/broken",
+    tag = "links",
+    params(
+        ("state" = Option<LinkState>, Query, description = "gone 只返回无法访问的，moved 只返回已重定向的，默认两者都返回"),
+        ("page" = Option<i64>, Query, description = "页码，默认为1"),
+        ("per_page" = Option<i64>, Query, description = "每页数量，默认为10，最多100"),
+        ("tz" = Option<String>, Query, description = "IANA 时区名，如 Asia/Shanghai，也可通过 X-Timezone 请求头指定，默认为 UTC")
+    ),
+    responses(
+        (status = 200, description = "成功获取失效链接列表", body = BrokenLinkResponse),
+        (status = 400, description = "无效的 state"),
+        (status = 500, description = "服务器内部错误")
+    )
+)]
+pub async fn list_broken_links(
+    user: AuthUser,
+    tz: ClientTimezone,
+    Query(params): Query<BrokenLinkQuery>,
+    State(db): State<SqlitePool>,
+) -> Result<Json<BrokenLinkResponse>, AppError> {
+    let per_page = params.per_page.unwrap_or(10).clamp(1, MAX_PER_PAGE);
+    let offset = (params.page.unwrap_or(1).max(1) - 1) * per_page;
+
+    let condition = match params.state {
+        Some(state) => state.condition().to_string(),
+        None => format!("({} OR {})", LinkState::Gone.condition(), LinkState::Moved.condition()),
+    };
+
+    let sql = format!(
+        "SELECT COUNT(*) FROM favorites f JOIN link_checks l ON l.url = f.url
+         WHERE f.user_id = ? AND f.deleted_at IS NULL AND {}",
+        condition
+    );
+    let total: i64 = sqlx::query_scalar(&sql)
+        .bind(user.id)
+        .fetch_one(&db)
+        .await
+        .map_err(AppError::Database)?;
+
+    let sql = format!(
+        "SELECT {},
+                CASE WHEN {} THEN 'gone' ELSE 'moved' END as state,
+                l.last_status, l.final_url, l.error, l.last_checked_at
+         FROM favorites f
+         JOIN link_checks l ON l.url = f.url
+         LEFT JOIN categories c ON f.category_id = c.id
+         WHERE f.user_id = ? AND f.deleted_at IS NULL AND {}
+         ORDER BY l.last_checked_at DESC, f.id DESC LIMIT ? OFFSET ?",
+        FAVORITE_COLUMNS, LinkState::Gone.condition(), condition
+    );
+    let items = sqlx::query_as::<_, BrokenLink>(&sql)
+        .bind(user.id)
+        .bind(per_page)
+        .bind(offset)
+        .fetch_all(&db)
+        .await
+        .map_err(AppError::Database)?;
+
+    let items = items
+        .into_iter()
+        .map(|mut link| {
+            link.favorite = link.favorite.localize(&tz);
+            tz.apply(&mut link.last_checked_at);
+            link
+        })
+        .collect();
+
+    Ok(Json(BrokenLinkResponse { total, items }))
+}
\ No newline at end of file
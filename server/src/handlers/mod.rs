@@ -1,3 +0,0 @@
-pub mod favorite;
-pub mod category;
-pub mod tag;
\ No newline at end of file
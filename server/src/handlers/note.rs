@@ -0,0 +1,277 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+    http::StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{SqliteConnection, SqlitePool};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use validator::Validate;
+use crate::audit::{self, Entity};
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::events::{Action, EventBus, Topic};
+use crate::timezone::{self, ClientTimezone};
+use crate::validation::{self, ValidatedJson};
+
+/// 笔记数据结构
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Note {
+    pub id: i64,
+    pub favorite_id: i64,
+    pub body: String, // Markdown 格式的笔记内容
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl Note {
+    /// 将时间字段转为调用方时区
+    fn localize(mut self, tz: &ClientTimezone) -> Self {
+        tz.apply(&mut self.created_at);
+        tz.apply(&mut self.updated_at);
+        self
+    }
+}
+
+/// 创建或更新笔记请求
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct SaveNote {
+    #[validate(custom = "validation::not_blank", length(max = 20000, message = "不能超过 20000 个字符"))]
+    pub body: String, // Markdown 格式的笔记内容
+}
+
+/// 确认收藏属于当前用户
+async fn ensure_favorite_owned(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+    favorite_id: i64,
+) -> Result<(), AppError> {
+    let exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM favorites WHERE id = ? AND user_id = ? AND deleted_at IS NULL)"
+    )
+    .bind(favorite_id)
+    .bind(user_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(AppError::Database)?;
+
+    if !exists {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(())
+}
+
+/// 按ID查询收藏下的单条笔记
+async fn fetch_note(
+    conn: &mut SqliteConnection,
+    favorite_id: i64,
+    note_id: i64,
+) -> Result<Note, AppError> {
+    sqlx::query_as::<_, Note>(
+        "SELECT id, favorite_id, body, created_at, updated_at FROM notes WHERE id = ? AND favorite_id = ?"
+    )
+    .bind(note_id)
+    .bind(favorite_id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(AppError::Database)?
+    .ok_or(AppError::NotFound)
+}
+
+/// 获取收藏的笔记列表
+#[utoipa::path(
+    get,
+    path = "/api/favorites/{id}/notes",
+    tag = "notes",
+    params(
+        ("id" = i64, Path, description = "收藏ID"),
+        ("tz" = Option<String>, Query, description = "IANA 时区名，如 Asia/Shanghai，也可通过 X-Timezone 请求头指定，默认为 UTC")
+    ),
+    responses(
+        (status = 200, description = "成功获取笔记列表", body = Vec<Note>),
+        (status = 404, description = "收藏不存在"),
+        (status = 500, description = "服务器内部错误")
+    )
+)]
+pub async fn list_notes(
+    user: AuthUser,
+    tz: ClientTimezone,
+    Path(favorite_id): Path<i64>,
+    State(db): State<SqlitePool>,
+) -> Result<Json<Vec<Note>>, AppError> {
+    let mut conn = db.acquire().await.map_err(AppError::Database)?;
+
+    ensure_favorite_owned(&mut conn, user.id, favorite_id).await?;
+
+    let notes = sqlx::query_as::<_, Note>(
+        "SELECT id, favorite_id, body, created_at, updated_at FROM notes
+         WHERE favorite_id = ? ORDER BY created_at, id"
+    )
+    .bind(favorite_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(AppError::Database)?;
+
+    Ok(Json(notes.into_iter().map(|note| note.localize(&tz)).collect()))
+}
+
+/// 为收藏添加笔记
+#[utoipa::path(
+    post,
+    path = "/api/favorites/{id}/notes",
+    tag = "notes",
+    params(
+        ("id" = i64, Path, description = "收藏ID"),
+        ("tz" = Option<String>, Query, description = "IANA 时区名，如 Asia/Shanghai，也可通过 X-Timezone 请求头指定，默认为 UTC")
+    ),
+    request_body = SaveNote,
+    responses(
+        (status = 201, description = "成功创建笔记", body = Note),
+        (status = 422, description = "笔记内容为空或过长"),
+        (status = 404, description = "收藏不存在"),
+        (status = 500, description = "服务器内部错误")
+    )
+)]
+pub async fn create_note(
+    user: AuthUser,
+    tz: ClientTimezone,
+    Path(favorite_id): Path<i64>,
+    State(db): State<SqlitePool>,
+    State(events): State<EventBus>,
+    ValidatedJson(payload): ValidatedJson<SaveNote>,
+) -> Result<(StatusCode, Json<Note>), AppError> {
+    let body = payload.body.trim();
+    let now = timezone::now();
+
+    let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+    ensure_favorite_owned(&mut tx, user.id, favorite_id).await?;
+
+    let result = sqlx::query(
+        "INSERT INTO notes (favorite_id, body, created_at, updated_at) VALUES (?, ?, ?, ?)"
+    )
+    .bind(favorite_id)
+    .bind(body)
+    .bind(&now)
+    .bind(&now)
+    .execute(&mut *tx)
+    .await
+    .map_err(AppError::Database)?;
+
+    let note = fetch_note(&mut tx, favorite_id, result.last_insert_rowid()).await?;
+    audit::record(&mut tx, user.id, Entity::Note, note.id, "create", None, audit::snapshot(&note))
+        .await
+        .map_err(AppError::Database)?;
+
+    tx.commit().await.map_err(AppError::Database)?;
+
+    // 笔记数量是收藏的一部分
+    events.publish(user.id, Topic::Favorite, Action::Updated, favorite_id);
+
+    Ok((StatusCode::CREATED, Json(note.localize(&tz))))
+}
+
+/// 更新笔记
+#[utoipa::path(
+    put,
+    path = "/api/favorites/{id}/notes/{note_id}",
+    tag = "notes",
+    params(
+        ("id" = i64, Path, description = "收藏ID"),
+        ("note_id" = i64, Path, description = "笔记ID"),
+        ("tz" = Option<String>, Query, description = "IANA 时区名，如 Asia/Shanghai，也可通过 X-Timezone 请求头指定，默认为 UTC")
+    ),
+    request_body = SaveNote,
+    responses(
+        (status = 200, description = "成功更新笔记", body = Note),
+        (status = 422, description = "笔记内容为空或过长"),
+        (status = 404, description = "收藏或笔记不存在"),
+        (status = 500, description = "服务器内部错误")
+    )
+)]
+pub async fn update_note(
+    user: AuthUser,
+    tz: ClientTimezone,
+    Path((favorite_id, note_id)): Path<(i64, i64)>,
+    State(db): State<SqlitePool>,
+    State(events): State<EventBus>,
+    ValidatedJson(payload): ValidatedJson<SaveNote>,
+) -> Result<Json<Note>, AppError> {
+    let body = payload.body.trim();
+    let now = timezone::now();
+
+    let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+    ensure_favorite_owned(&mut tx, user.id, favorite_id).await?;
+    let before = fetch_note(&mut tx, favorite_id, note_id).await?;
+
+    let result = sqlx::query("UPDATE notes SET body = ?, updated_at = ? WHERE id = ? AND favorite_id = ?")
+        .bind(body)
+        .bind(&now)
+        .bind(note_id)
+        .bind(favorite_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::Database)?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    let note = fetch_note(&mut tx, favorite_id, note_id).await?;
+    audit::record(&mut tx, user.id, Entity::Note, note_id, "update", audit::snapshot(&before), audit::snapshot(&note))
+        .await
+        .map_err(AppError::Database)?;
+
+    tx.commit().await.map_err(AppError::Database)?;
+
+    events.publish(user.id, Topic::Favorite, Action::Updated, favorite_id);
+
+    Ok(Json(note.localize(&tz)))
+}
+
+/// 删除笔记
+#[utoipa::path(
+    delete,
+    path = "/api/favorites/{id}/notes/{note_id}",
+    tag = "notes",
+    params(
+        ("id" = i64, Path, description = "收藏ID"),
+        ("note_id" = i64, Path, description = "笔记ID")
+    ),
+    responses(
+        (status = 200, description = "成功删除笔记"),
+        (status = 404, description = "收藏或笔记不存在"),
+        (status = 500, description = "服务器内部错误")
+    )
+)]
+pub async fn delete_note(
+    user: AuthUser,
+    Path((favorite_id, note_id)): Path<(i64, i64)>,
+    State(db): State<SqlitePool>,
+    State(events): State<EventBus>,
+) -> Result<StatusCode, AppError> {
+    let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+    ensure_favorite_owned(&mut tx, user.id, favorite_id).await?;
+    let before = fetch_note(&mut tx, favorite_id, note_id).await?;
+
+    sqlx::query("DELETE FROM notes WHERE id = ? AND favorite_id = ?")
+        .bind(note_id)
+        .bind(favorite_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::Database)?;
+
+    audit::record(&mut tx, user.id, Entity::Note, note_id, "delete", audit::snapshot(&before), None)
+        .await
+        .map_err(AppError::Database)?;
+
+    tx.commit().await.map_err(AppError::Database)?;
+
+    events.publish(user.id, Topic::Favorite, Action::Updated, favorite_id);
+
+    Ok(StatusCode::OK)
+}
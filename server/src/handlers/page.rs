@@ -0,0 +1,358 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{SqliteConnection, SqlitePool};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::handlers::favorite::{Favorite, FAVORITE_COLUMNS, MAX_PER_PAGE};
+use crate::timezone::ClientTimezone;
+
+/// 用前后文辅助定位时截取的最大字符数
+const CONTEXT_PROBE_CHARS: usize = 40;
+
+/// 页面的公共列，统计只计入未删除的收藏
+const PAGE_COLUMNS: &str = r#"
+    p.id,
+    p.canonical_url,
+    (SELECT u.url FROM favorites u
+      WHERE u.page_id = p.id AND u.deleted_at IS NULL
+      ORDER BY u.created_at DESC, u.id DESC LIMIT 1) as url,
+    p.title,
+    MIN(f.domain) as domain,
+    COUNT(f.id) as favorite_count,
+    p.first_seen_at,
+    MAX(f.created_at) as last_saved_at
+"#;
+
+/// 来源网页
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Page {
+    pub id: i64,
+    pub canonical_url: String,  // 规范化网址
+    pub url: String,            // 最近一条收藏使用的原始网址
+    pub title: Option<String>,  // 页面标题，取最早带有标题的收藏
+    pub domain: String,         // 网址的主机名
+    pub favorite_count: i64,    // 收藏数量
+    pub first_seen_at: String,  // 首次收藏该页面的时间
+    pub last_saved_at: String,  // 最近的收藏时间
+}
+
+impl Page {
+    fn localize(mut self, tz: &ClientTimezone) -> Self {
+        tz.apply(&mut self.first_seen_at);
+        tz.apply(&mut self.last_saved_at);
+        self
+    }
+}
+
+/// 页面列表查询参数
+#[derive(Debug, Deserialize)]
+pub struct PageQuery {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+/// 页面列表响应
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PageListResponse {
+    pub total: i64,
+    pub items: Vec<Page>,
+}
+
+/// 单个页面的全部收藏
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PageFavorites {
+    pub page: Page,
+    pub on_page_order: bool, // 是否已按网页快照中的位置排序，否则按收藏时间排序
+    pub items: Vec<Favorite>,
+}
+
+/// 将收藏归入其规范化网址对应的页面，页面不存在时创建
+/// 网址修改后原页面不再有收藏时一并删除，需在收藏写入后调用
+pub(crate) async fn attach_page(
+    conn: &mut SqliteConnection,
+    favorite_id: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO pages (user_id, canonical_url, title, first_seen_at)
+         SELECT user_id, canonical_url, title, created_at FROM favorites WHERE id = ?
+         ON CONFLICT (user_id, canonical_url) DO UPDATE SET
+             title = COALESCE(pages.title, excluded.title),
+             first_seen_at = MIN(pages.first_seen_at, excluded.first_seen_at)"
+    )
+    .bind(favorite_id)
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "UPDATE favorites SET page_id = (
+             SELECT p.id FROM pages p
+             WHERE p.user_id = favorites.user_id AND p.canonical_url = favorites.canonical_url
+         )
+         WHERE id = ?"
+    )
+    .bind(favorite_id)
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "DELETE FROM pages
+         WHERE user_id = (SELECT user_id FROM favorites WHERE id = ?)
+           AND NOT EXISTS (SELECT 1 FROM favorites f WHERE f.page_id = pages.id)"
+    )
+    .bind(favorite_id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// 合并空白并转为小写，抵消网页正文提取和选区文本之间的格式差异
+fn collapse(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// 在网页正文中定位收藏的位置
+/// 先用前文加选区消除重复片段的歧义，找不到时依次退回选区本身、前文末尾和后文开头
+fn locate(page_text: &str, favorite: &Favorite) -> Option<usize> {
+    let text = collapse(&favorite.text);
+    let before = favorite.context_before.as_deref().map(|before| {
+        let before = collapse(before);
+        let skip = before.chars().count().saturating_sub(CONTEXT_PROBE_CHARS);
+        before.chars().skip(skip).collect::<String>()
+    });
+    let after = favorite
+        .context_after
+        .as_deref()
+        .map(|after| collapse(after).chars().take(CONTEXT_PROBE_CHARS).collect::<String>());
+
+    if let Some(before) = before.as_deref().filter(|before| !before.is_empty()) {
+        let probe = format!("{} {}", before, text);
+        if let Some(pos) = page_text.find(&probe) {
+            return Some(pos + before.len() + 1);
+        }
+    }
+    if !text.is_empty() {
+        if let Some(pos) = page_text.find(&text) {
+            return Some(pos);
+        }
+    }
+    if let Some(before) = before.as_deref().filter(|before| !before.is_empty()) {
+        if let Some(pos) = page_text.find(before) {
+            return Some(pos + before.len());
+        }
+    }
+    after
+        .as_deref()
+        .filter(|after| !after.is_empty())
+        .and_then(|after| page_text.find(after))
+}
+
+/// 获取收藏过的来源网页，按最近收藏时间倒序
+#[utoipa::path(
+    get,
+    path = "/api/pages",
+    tag = "pages",
+    params(
+        ("page" = Option<i64>, Query, description = "页码，默认为1"),
+        ("per_page" = Option<i64>, Query, description = "每页数量，默认为10，最多100"),
+        ("tz" = Option<String>, Query, description = "IANA 时区名，如 Asia/Shanghai，也可通过 X-Timezone 请求头指定，默认为 UTC")
+    ),
+    responses(
+        (status = 200, description = "成功获取页面列表", body = PageListResponse),
+        (status = 500, description = "服务器内部错误")
+    )
+)]
+pub async fn list_pages(
+    user: AuthUser,
+    tz: ClientTimezone,
+    Query(params): Query<PageQuery>,
+    State(db): State<SqlitePool>,
+) -> Result<Json<PageListResponse>, AppError> {
+    let per_page = params.per_page.unwrap_or(10).clamp(1, MAX_PER_PAGE);
+    let offset = (params.page.unwrap_or(1).max(1) - 1) * per_page;
+
+    let total: i64 = sqlx::query_scalar(
+        "SELECT COUNT(DISTINCT page_id) FROM favorites
+         WHERE user_id = ? AND deleted_at IS NULL AND page_id IS NOT NULL"
+    )
+    .bind(user.id)
+    .fetch_one(&db)
+    .await
+    .map_err(AppError::Database)?;
+
+    let sql = format!(
+        "SELECT {}
+         FROM pages p
+         JOIN favorites f ON f.page_id = p.id AND f.deleted_at IS NULL
+         WHERE p.user_id = ?
+         GROUP BY p.id
+         ORDER BY last_saved_at DESC, p.id DESC LIMIT ? OFFSET ?",
+        PAGE_COLUMNS
+    );
+    let items = sqlx::query_as::<_, Page>(&sql)
+        .bind(user.id)
+        .bind(per_page)
+        .bind(offset)
+        .fetch_all(&db)
+        .await
+        .map_err(AppError::Database)?;
+
+    let items = items.into_iter().map(|page| page.localize(&tz)).collect();
+
+    Ok(Json(PageListResponse { total, items }))
+}
+
+/// 获取一个页面下的全部收藏，便于整篇回顾
+/// 已保存网页快照时按收藏在正文中的位置排序，无法定位的排在最后，其余情况按收藏时间排序
+#[utoipa::path(
+    get,
+    path = "/api/pages/{id}/favorites",
+    tag = "pages",
+    params(
+        ("id" = i64, Path, description = "页面ID"),
+        ("tz" = Option<String>, Query, description = "IANA 时区名，如 Asia/Shanghai，也可通过 X-Timezone 请求头指定，默认为 UTC")
+    ),
+    responses(
+        (status = 200, description = "成功获取页面的收藏", body = PageFavorites),
+        (status = 404, description = "页面不存在或没有收藏"),
+        (status = 500, description = "服务器内部错误")
+    )
+)]
+pub async fn list_page_favorites(
+    user: AuthUser,
+    tz: ClientTimezone,
+    Path(id): Path<i64>,
+    State(db): State<SqlitePool>,
+) -> Result<Json<PageFavorites>, AppError> {
+    let sql = format!(
+        "SELECT {}
+         FROM pages p
+         JOIN favorites f ON f.page_id = p.id AND f.deleted_at IS NULL
+         WHERE p.id = ? AND p.user_id = ?
+         GROUP BY p.id",
+        PAGE_COLUMNS
+    );
+    let page = sqlx::query_as::<_, Page>(&sql)
+        .bind(id)
+        .bind(user.id)
+        .fetch_optional(&db)
+        .await
+        .map_err(AppError::Database)?
+        .ok_or(AppError::NotFound)?;
+
+    let sql = format!(
+        "SELECT {} FROM favorites f
+         LEFT JOIN categories c ON f.category_id = c.id
+         WHERE f.page_id = ? AND f.user_id = ? AND f.deleted_at IS NULL
+         ORDER BY f.created_at, f.id",
+        FAVORITE_COLUMNS
+    );
+    let favorites = sqlx::query_as::<_, Favorite>(&sql)
+        .bind(id)
+        .bind(user.id)
+        .fetch_all(&db)
+        .await
+        .map_err(AppError::Database)?;
+
+    // 同一页面的快照内容相同，取最近一次成功抓取的正文
+    let page_text: Option<String> = sqlx::query_scalar(
+        "SELECT s.text FROM snapshots s
+         JOIN favorites f ON f.id = s.favorite_id
+         WHERE f.page_id = ? AND f.user_id = ? AND s.error IS NULL AND s.status < 400 AND s.text IS NOT NULL
+         ORDER BY s.fetched_at DESC LIMIT 1"
+    )
+    .bind(id)
+    .bind(user.id)
+    .fetch_optional(&db)
+    .await
+    .map_err(AppError::Database)?;
+
+    let on_page_order = page_text.is_some();
+    let items = match page_text {
+        Some(page_text) => {
+            let page_text = collapse(&page_text);
+            let mut located: Vec<(Option<usize>, Favorite)> = favorites
+                .into_iter()
+                .map(|favorite| (locate(&page_text, &favorite), favorite))
+                .collect();
+            // 排序稳定，位置相同或无法定位的保持收藏时间顺序
+            located.sort_by_key(|(pos, _)| (pos.is_none(), *pos));
+            located.into_iter().map(|(_, favorite)| favorite).collect()
+        }
+        None => favorites,
+    };
+
+    Ok(Json(PageFavorites {
+        page: page.localize(&tz),
+        on_page_order,
+        items: items.into_iter().map(|favorite| favorite.localize(&tz)).collect(),
+    }))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn favorite(text: &str, before: Option<&str>, after: Option<&str>) -> Favorite {
+        Favorite {
+            id: 1,
+            category_id: None,
+            category_name: String::new(),
+            text: text.to_string(),
+            url: "https://example.com".to_string(),
+            canonical_url: String::new(),
+            domain: String::new(),
+            page_id: None,
+            deep_link: String::new(),
+            title: None,
+            context_before: before.map(str::to_string),
+            context_after: after.map(str::to_string),
+            html: None,
+            lang: None,
+            tags: "[]".to_string(),
+            note_count: 0,
+            has_notes: false,
+            created_at: String::new(),
+            updated_at: String::new(),
+            archived_at: None,
+            deleted_at: None,
+        }
+    }
+
+    #[test]
+    fn test_locate() {
+        let page = collapse("First  paragraph.\nThe cat sat.\n\nThen the dog ran. The cat sat again.");
+
+        // 忽略空白和大小写差异
+        assert_eq!(locate(&page, &favorite("first   PARAGRAPH", None, None)), Some(0));
+        assert_eq!(locate(&page, &favorite("The cat sat", None, None)), page.find("the cat sat"));
+        assert_eq!(locate(&page, &favorite("no such text", None, None)), None);
+        assert_eq!(locate(&page, &favorite("", None, None)), None);
+
+        // 重复片段用前文区分
+        let second = page.rfind("the cat sat").unwrap();
+        assert_eq!(locate(&page, &favorite("The cat sat", Some("Then the dog ran."), None)), Some(second));
+        assert_eq!(
+            locate(&page, &favorite("The cat sat", Some("First paragraph."), None)),
+            page.find("the cat sat")
+        );
+
+        // 选区已改动时退回前文末尾或后文开头
+        let dog = page.find("then the dog ran.").unwrap();
+        assert_eq!(
+            locate(&page, &favorite("edited", Some("then the dog ran."), None)),
+            Some(dog + "then the dog ran.".len())
+        );
+        assert_eq!(locate(&page, &favorite("edited", Some("missing"), Some("Then the dog"))), Some(dog));
+        assert_eq!(locate(&page, &favorite("edited", Some("missing"), Some("also missing"))), None);
+    }
+}
\ No newline at end of file
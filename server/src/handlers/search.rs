@@ -0,0 +1,504 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqlitePool;
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::handlers::favorite::{Favorite, FAVORITE_COLUMNS, MAX_PER_PAGE};
+use crate::timezone::ClientTimezone;
+
+/// trigram 分词器只能对不少于 3 个字符的子串使用 MATCH
+const MIN_MATCH_CHARS: usize = 3;
+
+/// 片段高亮标记，先用控制字符占位，转义 HTML 后再替换为 <mark>
+const MARK_START: char = '\u{2}';
+const MARK_END: char = '\u{3}';
+
+/// 全文搜索查询参数
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SearchQuery {
+    pub q: String,             // 搜索表达式
+    pub page: Option<i64>,     // 页码
+    pub per_page: Option<i64>, // 每页数量，最多 100
+}
+
+/// 搜索结果
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct SearchHit {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub favorite: Favorite,
+    pub rank: Option<f64>,       // 相关度，越小越相关
+    pub snippet: Option<String>, // 带 <mark> 高亮的匹配片段
+}
+
+/// 搜索结果响应
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SearchResponse {
+    pub total: i64,
+    pub items: Vec<SearchHit>,
+}
+
+/// 搜索字段
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Text,
+    Url,
+    Tag,
+    Note,
+}
+
+/// 搜索表达式中的单个条件
+#[derive(Debug)]
+struct Term {
+    field: Option<Field>,
+    value: String,
+    prefix: bool,
+}
+
+/// 解析搜索表达式
+/// 支持 "短语"、前缀* 以及 text:/url:/tag:/note: 字段限定
+fn parse_terms(input: &str) -> Vec<Term> {
+    let mut terms = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            break;
+        }
+
+        let mut raw = String::new();
+        let mut quoted = false;
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() && !quoted {
+                break;
+            }
+            if c == '"' {
+                quoted = !quoted;
+            } else {
+                raw.push(c);
+            }
+            chars.next();
+        }
+
+        let (field, value) = match raw.split_once(':') {
+            Some((name, rest)) => match name.to_ascii_lowercase().as_str() {
+                "text" => (Some(Field::Text), rest),
+                "url" => (Some(Field::Url), rest),
+                "tag" => (Some(Field::Tag), rest),
+                "note" => (Some(Field::Note), rest),
+                _ => (None, raw.as_str()),
+            },
+            None => (None, raw.as_str()),
+        };
+
+        let (value, prefix) = match value.strip_suffix('*') {
+            Some(stripped) => (stripped, true),
+            None => (value, false),
+        };
+
+        let value = value.trim();
+        if !value.is_empty() {
+            terms.push(Term {
+                field,
+                value: value.to_string(),
+                prefix,
+            });
+        }
+    }
+
+    terms
+}
+
+/// 转义 LIKE 通配符，配合 ESCAPE '\' 使用
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// 转义 GLOB 通配符
+fn escape_glob(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '*' | '?' | '[' => {
+                escaped.push('[');
+                escaped.push(c);
+                escaped.push(']');
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// 由搜索表达式生成的 favorites_fts 过滤条件
+#[derive(Debug, Default)]
+pub(crate) struct SearchFilter {
+    pub match_expr: Option<String>,
+    pub conditions: Vec<String>,
+    pub params: Vec<String>,
+    /// 用于高亮的关键词
+    pub keywords: Vec<String>,
+}
+
+impl SearchFilter {
+    /// 解析搜索表达式，表达式为空时返回 None
+    pub(crate) fn parse(input: &str) -> Option<Self> {
+        let terms = parse_terms(input);
+        if terms.is_empty() {
+            return None;
+        }
+
+        let mut filter = SearchFilter::default();
+        let mut matches = Vec::new();
+
+        for term in terms {
+            // 标签按名称精确匹配，前缀查询匹配标签名开头
+            if term.field == Some(Field::Tag) {
+                let (condition, value) = if term.prefix {
+                    ("t.name LIKE ? ESCAPE '\\'", format!("{}%", escape_like(&term.value)))
+                } else {
+                    ("t.name = ?", term.value.clone())
+                };
+                filter.conditions.push(format!(
+                    "favorites_fts.rowid IN (SELECT ft.favorite_id FROM favorite_tags ft \
+                     JOIN tags t ON t.id = ft.tag_id WHERE {})",
+                    condition
+                ));
+                filter.params.push(value);
+                continue;
+            }
+
+            let columns: &[&str] = match term.field {
+                Some(Field::Text) => &["text"],
+                Some(Field::Url) => &["url"],
+                Some(Field::Note) => &["notes"],
+                _ => &["text", "url", "tags", "notes"],
+            };
+
+            if term.value.chars().count() >= MIN_MATCH_CHARS {
+                let phrase = format!("\"{}\"", term.value.replace('"', "\"\""));
+                matches.push(match term.field {
+                    Some(Field::Text) => format!("text : {}", phrase),
+                    Some(Field::Url) => format!("url : {}", phrase),
+                    Some(Field::Note) => format!("notes : {}", phrase),
+                    _ => phrase,
+                });
+            }
+
+            if term.prefix {
+                // 前缀查询要求匹配位于字段开头或非字母数字字符之后
+                let value = escape_glob(&term.value.to_ascii_lowercase());
+                let globs: Vec<String> = columns
+                    .iter()
+                    .map(|col| format!(
+                        "lower(favorites_fts.{0}) GLOB ? OR lower(favorites_fts.{0}) GLOB ?",
+                        col
+                    ))
+                    .collect();
+                filter.conditions.push(format!("({})", globs.join(" OR ")));
+                for _ in columns {
+                    filter.params.push(format!("{}*", value));
+                    filter.params.push(format!("*[^0-9a-z]{}*", value));
+                }
+            } else if term.value.chars().count() < MIN_MATCH_CHARS {
+                // 过短的关键词无法使用索引，退回到 LIKE
+                let likes: Vec<String> = columns
+                    .iter()
+                    .map(|col| format!("favorites_fts.{} LIKE ? ESCAPE '\\'", col))
+                    .collect();
+                filter.conditions.push(format!("({})", likes.join(" OR ")));
+                let pattern = format!("%{}%", escape_like(&term.value));
+                filter.params.extend(std::iter::repeat_n(pattern, columns.len()));
+            }
+
+            if term.field != Some(Field::Url) {
+                filter.keywords.push(term.value);
+            }
+        }
+
+        if !matches.is_empty() {
+            filter.match_expr = Some(matches.join(" AND "));
+        }
+
+        Some(filter)
+    }
+
+    /// 生成 WHERE 条件（不含 WHERE 关键字）及其参数
+    pub(crate) fn where_clause(&self) -> (String, Vec<String>) {
+        let mut conditions = Vec::new();
+        let mut params = Vec::new();
+        if let Some(expr) = &self.match_expr {
+            conditions.push("favorites_fts MATCH ?".to_string());
+            params.push(expr.clone());
+        }
+        conditions.extend(self.conditions.iter().cloned());
+        params.extend(self.params.iter().cloned());
+        (conditions.join(" AND "), params)
+    }
+}
+
+/// 转义 HTML 特殊字符
+pub(crate) fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// 将带占位标记的片段转为安全的 HTML
+fn render_snippet(raw: &str) -> String {
+    escape_html(raw)
+        .replace(MARK_START, "<mark>")
+        .replace(MARK_END, "</mark>")
+}
+
+/// 未使用全文索引时，在文本中查找关键词并截取片段
+fn fallback_snippet(text: &str, keywords: &[String]) -> Option<String> {
+    const CONTEXT_CHARS: usize = 24;
+
+    let lower = text.to_ascii_lowercase();
+    let keywords: Vec<String> = keywords.iter().map(|k| k.to_ascii_lowercase()).collect();
+    let first = keywords.iter().filter_map(|k| lower.find(k.as_str())).min()?;
+
+    // 以字符为单位截取关键词前后的上下文
+    let start = lower[..first]
+        .char_indices()
+        .rev()
+        .nth(CONTEXT_CHARS - 1)
+        .map(|(i, _)| i)
+        .unwrap_or(0);
+    let end = lower[first..]
+        .char_indices()
+        .nth(CONTEXT_CHARS * 2)
+        .map(|(i, _)| first + i)
+        .unwrap_or(text.len());
+
+    let mut marked = String::new();
+    let mut pos = start;
+    while pos < end {
+        let hit = keywords
+            .iter()
+            .filter(|k| lower[pos..end].starts_with(k.as_str()))
+            .map(|k| k.len())
+            .max();
+        match hit {
+            Some(len) => {
+                marked.push(MARK_START);
+                marked.push_str(&text[pos..pos + len]);
+                marked.push(MARK_END);
+                pos += len;
+            }
+            None => {
+                let c = text[pos..].chars().next()?;
+                marked.push(c);
+                pos += c.len_utf8();
+            }
+        }
+    }
+
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push('…');
+    }
+    snippet.push_str(&render_snippet(&marked));
+    if end < text.len() {
+        snippet.push('…');
+    }
+    Some(snippet)
+}
+
+/// 全文搜索收藏
+#[utoipa::path(
+    get,
+    path = "/api/search",
+    tag = "search",
+    params(
+        ("q" = String, Query, description = "搜索表达式，支持 \"短语\"、前缀*、text:/url:/tag:/note: 字段限定"),
+        ("page" = Option<i64>, Query, description = "页码，默认为1"),
+        ("per_page" = Option<i64>, Query, description = "每页数量，默认为10，最多100"),
+        ("tz" = Option<String>, Query, description = "IANA 时区名，如 Asia/Shanghai，也可通过 X-Timezone 请求头指定，默认为 UTC")
+    ),
+    responses(
+        (status = 200, description = "成功获取搜索结果", body = SearchResponse),
+        (status = 400, description = "搜索表达式为空"),
+        (status = 422, description = "未知的时区"),
+        (status = 500, description = "服务器内部错误")
+    )
+)]
+pub async fn search_favorites(
+    user: AuthUser,
+    tz: ClientTimezone,
+    Query(params): Query<SearchQuery>,
+    State(db): State<SqlitePool>,
+) -> Result<Json<SearchResponse>, AppError> {
+    let per_page = params.per_page.unwrap_or(10).clamp(1, MAX_PER_PAGE);
+    let offset = (params.page.unwrap_or(1).max(1) - 1) * per_page;
+
+    let filter = SearchFilter::parse(&params.q)
+        .ok_or_else(|| AppError::BadRequest("搜索表达式不能为空".to_string()))?;
+    let (where_clause, values) = filter.where_clause();
+
+    // 只搜索当前用户的收藏，不含回收站
+    let where_clause = format!("f.user_id = ? AND f.deleted_at IS NULL AND {}", where_clause);
+    let mut params_values = vec![user.id.to_string()];
+    params_values.extend(values);
+
+    let ranked = filter.match_expr.is_some();
+    let (rank_columns, order_by) = if ranked {
+        (
+            format!(
+                "bm25(favorites_fts) as rank, \
+                 snippet(favorites_fts, -1, char({}), char({}), '…', 32) as snippet",
+                MARK_START as u32, MARK_END as u32
+            ),
+            "rank",
+        )
+    } else {
+        ("NULL as rank, NULL as snippet".to_string(), "f.created_at DESC")
+    };
+
+    let from = "FROM favorites_fts \
+                JOIN favorites f ON f.id = favorites_fts.rowid \
+                LEFT JOIN categories c ON f.category_id = c.id";
+
+    // 执行总数查询
+    let count_sql = format!("SELECT COUNT(*) {} WHERE {}", from, where_clause);
+    let mut query = sqlx::query_scalar(&count_sql);
+    for param in &params_values {
+        query = query.bind(param);
+    }
+    let total: i64 = query.fetch_one(&db)
+        .await
+        .map_err(AppError::Database)?;
+
+    // 执行搜索查询
+    let sql = format!(
+        "SELECT {}, {} {} WHERE {} ORDER BY {} LIMIT ? OFFSET ?",
+        FAVORITE_COLUMNS, rank_columns, from, where_clause, order_by
+    );
+    let mut query = sqlx::query_as::<_, SearchHit>(&sql);
+    for param in &params_values {
+        query = query.bind(param);
+    }
+    query = query.bind(per_page).bind(offset);
+
+    let items = query.fetch_all(&db)
+        .await
+        .map_err(AppError::Database)?;
+
+    let items = items
+        .into_iter()
+        .map(|mut item| {
+            item.snippet = match item.snippet.take() {
+                Some(raw) => Some(render_snippet(&raw)),
+                None => fallback_snippet(&item.favorite.text, &filter.keywords),
+            };
+            item.favorite = item.favorite.localize(&tz);
+            item
+        })
+        .collect();
+
+    Ok(Json(SearchResponse { total, items }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(input: &str) -> Vec<(Option<Field>, String, bool)> {
+        parse_terms(input)
+            .into_iter()
+            .map(|term| (term.field, term.value, term.prefix))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_terms() {
+        assert_eq!(
+            terms(r#"rust "hello  world" TAG:lang url:github.com pre* note:"my note" foo:bar"#),
+            vec![
+                (None, "rust".to_string(), false),
+                (None, "hello  world".to_string(), false),
+                (Some(Field::Tag), "lang".to_string(), false),
+                (Some(Field::Url), "github.com".to_string(), false),
+                (None, "pre".to_string(), true),
+                (Some(Field::Note), "my note".to_string(), false),
+                (None, "foo:bar".to_string(), false),
+            ]
+        );
+        assert!(terms(r#"  "" * tag: "#).is_empty());
+    }
+
+    #[test]
+    fn test_filter() {
+        assert!(SearchFilter::parse("   ").is_none());
+
+        let filter = SearchFilter::parse("hello tag:rust url:github.com").unwrap();
+        assert_eq!(filter.match_expr.as_deref(), Some(r#""hello" AND url : "github.com""#));
+        assert_eq!(filter.params, vec!["rust"]);
+        assert!(filter.conditions[0].contains("t.name = ?"));
+        // 网址不参与高亮
+        assert_eq!(filter.keywords, vec!["hello"]);
+
+        let (clause, params) = filter.where_clause();
+        assert!(clause.starts_with("favorites_fts MATCH ? AND favorites_fts.rowid IN"));
+        assert_eq!(params, vec![r#""hello" AND url : "github.com""#, "rust"]);
+    }
+
+    #[test]
+    fn test_filter_short_and_prefix() {
+        // 过短的关键词使用 LIKE 并转义通配符
+        let filter = SearchFilter::parse("_%").unwrap();
+        assert!(filter.match_expr.is_none());
+        assert_eq!(filter.params, vec![r"%\_\%%"; 4]);
+
+        let filter = SearchFilter::parse("text:Rus*").unwrap();
+        assert_eq!(filter.match_expr.as_deref(), Some(r#"text : "Rus""#));
+        assert_eq!(filter.conditions.len(), 1);
+        assert_eq!(filter.params, vec!["rus*", "*[^0-9a-z]rus*"]);
+
+        let filter = SearchFilter::parse("tag:ru*").unwrap();
+        assert!(filter.conditions[0].contains("t.name LIKE ?"));
+        assert_eq!(filter.params, vec!["ru%"]);
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape_glob("a*b?[c"), "a[*]b[?][[]c");
+        assert_eq!(escape_like(r"50%_\"), r"50\%\_\\");
+        assert_eq!(escape_html(r#"<a href="x">'&'</a>"#), "&lt;a href=&quot;x&quot;&gt;&#39;&amp;&#39;&lt;/a&gt;");
+    }
+
+    #[test]
+    fn test_fallback_snippet() {
+        assert_eq!(
+            fallback_snippet("Hello <b>Rust</b> world", &["rust".to_string()]).as_deref(),
+            Some("Hello &lt;b&gt;<mark>Rust</mark>&lt;/b&gt; world")
+        );
+        assert_eq!(fallback_snippet("Hello", &["rust".to_string()]), None);
+
+        // 长文本只截取关键词前后的上下文
+        let text = format!("{}key{}", "x".repeat(50), "y".repeat(60));
+        assert_eq!(
+            fallback_snippet(&text, &["KEY".to_string()]).unwrap(),
+            format!("…{}<mark>key</mark>{}…", "x".repeat(24), "y".repeat(45))
+        );
+    }
+}
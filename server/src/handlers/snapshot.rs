@@ -0,0 +1,116 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqlitePool;
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use crate::archiver;
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::timezone::ClientTimezone;
+
+/// 快照的返回格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SnapshotFormat {
+    #[default]
+    Json, // 抓取信息和提取出的正文
+    Raw,  // 原网页内容
+}
+
+/// 快照查询参数
+#[derive(Debug, Deserialize)]
+pub struct SnapshotQuery {
+    pub format: Option<SnapshotFormat>,
+}
+
+/// 收藏来源网页的快照
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Snapshot {
+    pub favorite_id: i64,
+    pub url: String,                  // 抓取的网址
+    pub final_url: Option<String>,    // 跟随重定向后的网址
+    pub status: Option<i64>,          // HTTP 状态码，请求失败时为 null
+    pub content_type: Option<String>,
+    pub content_hash: Option<String>, // 原网页内容的 SHA-256
+    pub size: Option<i64>,            // 原网页内容的字节数
+    pub text: Option<String>,         // 提取出的正文
+    pub error: Option<String>,        // 抓取失败的原因
+    pub fetched_at: String,
+}
+
+/// 获取收藏来源网页的快照
+/// 快照由后台任务在保存收藏后抓取，需在配置中启用
+#[utoipa::path(
+    get,
+    path = "/api/favorites/{id}/snapshot",
+    tag = "favorites",
+    params(
+        ("id" = i64, Path, description = "收藏ID"),
+        ("format" = Option<SnapshotFormat>, Query, description = "json 返回抓取信息和正文，raw 返回原网页内容，默认为 json"),
+        ("tz" = Option<String>, Query, description = "IANA 时区名，如 Asia/Shanghai，也可通过 X-Timezone 请求头指定，默认为 UTC")
+    ),
+    responses(
+        (status = 200, description = "成功获取快照", body = Snapshot),
+        (status = 404, description = "收藏不存在、尚未抓取，或抓取失败时请求原网页内容"),
+        (status = 500, description = "服务器内部错误")
+    )
+)]
+pub async fn get_snapshot(
+    user: AuthUser,
+    tz: ClientTimezone,
+    Path(id): Path<i64>,
+    Query(params): Query<SnapshotQuery>,
+    State(db): State<SqlitePool>,
+) -> Result<Response, AppError> {
+    if params.format.unwrap_or_default() == SnapshotFormat::Raw {
+        let (content_type, content): (Option<String>, Option<Vec<u8>>) = sqlx::query_as(
+            "SELECT s.content_type, s.content FROM snapshots s
+             JOIN favorites f ON f.id = s.favorite_id
+             WHERE s.favorite_id = ? AND f.user_id = ? AND f.deleted_at IS NULL"
+        )
+        .bind(id)
+        .bind(user.id)
+        .fetch_optional(&db)
+        .await
+        .map_err(AppError::Database)?
+        .ok_or(AppError::NotFound)?;
+
+        let content = content.ok_or(AppError::NotFound)?;
+        let body = archiver::decompress(&content)
+            .map_err(|err| AppError::Database(sqlx::Error::Decode(Box::new(err))))?;
+
+        // 原网页来自第三方，禁止其脚本在本站域名下执行
+        return Ok((
+            [
+                (header::CONTENT_TYPE, content_type.unwrap_or_else(|| "application/octet-stream".to_string())),
+                (header::CONTENT_SECURITY_POLICY, "sandbox".to_string()),
+                (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+            ],
+            body,
+        )
+            .into_response());
+    }
+
+    let mut snapshot = sqlx::query_as::<_, Snapshot>(
+        "SELECT s.favorite_id, s.url, s.final_url, s.status, s.content_type, s.content_hash,
+                s.size, s.text, s.error, s.fetched_at
+         FROM snapshots s
+         JOIN favorites f ON f.id = s.favorite_id
+         WHERE s.favorite_id = ? AND f.user_id = ? AND f.deleted_at IS NULL"
+    )
+    .bind(id)
+    .bind(user.id)
+    .fetch_optional(&db)
+    .await
+    .map_err(AppError::Database)?
+    .ok_or(AppError::NotFound)?;
+
+    tz.apply(&mut snapshot.fetched_at);
+
+    Ok(Json(snapshot).into_response())
+}
\ No newline at end of file
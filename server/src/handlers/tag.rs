@@ -1,12 +1,17 @@
 use axum::{
     extract::{Path, State},
-    response::IntoResponse,
     Json,
     http::StatusCode,
 };
 use serde::{Deserialize, Serialize};
-use sqlx::{sqlite::SqlitePool, FromRow};
+use sqlx::{sqlite::{SqliteConnection, SqlitePool}, FromRow};
 use utoipa::ToSchema;
+use validator::Validate;
+use crate::audit::{self, Entity};
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::events::{Action, EventBus, Topic};
+use crate::validation::{self, ValidatedJson};
 
 /// 标签数据结构
 #[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
@@ -16,8 +21,9 @@ pub struct Tag {
 }
 
 /// 创建标签请求
-#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct CreateTag {
+    #[validate(custom = "validation::not_blank", length(max = 50, message = "不能超过 50 个字符"))]
     pub name: String, // 标签名称
 }
 
@@ -31,6 +37,15 @@ pub struct TaggedFavorite {
     pub tags: String,
 }
 
+/// 查询当前用户的单个标签
+async fn fetch_tag(conn: &mut SqliteConnection, user_id: i64, id: i64) -> Result<Option<Tag>, sqlx::Error> {
+    sqlx::query_as::<_, Tag>("SELECT id, name FROM tags WHERE id = ? AND user_id = ?")
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&mut *conn)
+        .await
+}
+
 /// 获取标签列表
 #[utoipa::path(
     get,
@@ -41,14 +56,17 @@ pub struct TaggedFavorite {
         (status = 500, description = "服务器内部错误")
     )
 )]
-pub async fn list_tags(State(db): State<SqlitePool>) -> impl IntoResponse {
-    match sqlx::query_as::<_, Tag>("SELECT DISTINCT id, name FROM tags")
+pub async fn list_tags(
+    user: AuthUser,
+    State(db): State<SqlitePool>,
+) -> Result<Json<Vec<Tag>>, AppError> {
+    let tags = sqlx::query_as::<_, Tag>("SELECT DISTINCT id, name FROM tags WHERE user_id = ?")
+        .bind(user.id)
         .fetch_all(&db)
         .await
-    {
-        Ok(tags) => Json(tags).into_response(),
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
-    }
+        .map_err(AppError::Database)?;
+
+    Ok(Json(tags))
 }
 
 /// 创建标签
@@ -59,21 +77,38 @@ pub async fn list_tags(State(db): State<SqlitePool>) -> impl IntoResponse {
     request_body = CreateTag,
     responses(
         (status = 201, description = "成功创建标签"),
+        (status = 409, description = "标签名称已存在"),
+        (status = 422, description = "标签名称为空或过长"),
         (status = 500, description = "服务器内部错误")
     )
 )]
 pub async fn create_tag(
+    user: AuthUser,
     State(db): State<SqlitePool>,
-    Json(tag): Json<CreateTag>,
-) -> impl IntoResponse {
-    match sqlx::query("INSERT INTO tags (name) VALUES (?)")
-        .bind(&tag.name)
-        .execute(&db)
+    State(events): State<EventBus>,
+    ValidatedJson(tag): ValidatedJson<CreateTag>,
+) -> Result<StatusCode, AppError> {
+    let name = tag.name.trim();
+    let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+    let result = sqlx::query("INSERT INTO tags (user_id, name) VALUES (?, ?)")
+        .bind(user.id)
+        .bind(name)
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| AppError::unique_conflict(err, format!("标签 {} 已存在", name)))?;
+
+    let id = result.last_insert_rowid();
+    let after = Tag { id, name: name.to_string() };
+    audit::record(&mut tx, user.id, Entity::Tag, id, "create", None, audit::snapshot(&after))
         .await
-    {
-        Ok(_) => StatusCode::CREATED.into_response(),
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
-    }
+        .map_err(AppError::Database)?;
+
+    tx.commit().await.map_err(AppError::Database)?;
+
+    events.publish(user.id, Topic::Tag, Action::Created, id);
+
+    Ok(StatusCode::CREATED)
 }
 
 /// 获取单个标签
@@ -91,18 +126,19 @@ pub async fn create_tag(
     )
 )]
 pub async fn get_tag(
+    user: AuthUser,
     Path(id): Path<i64>,
     State(db): State<SqlitePool>,
-) -> impl IntoResponse {
-    match sqlx::query_as::<_, Tag>("SELECT id, name FROM tags WHERE id = ?")
+) -> Result<Json<Tag>, AppError> {
+    let tag = sqlx::query_as::<_, Tag>("SELECT id, name FROM tags WHERE id = ? AND user_id = ?")
         .bind(id)
+        .bind(user.id)
         .fetch_optional(&db)
         .await
-    {
-        Ok(Some(tag)) => Json(tag).into_response(),
-        Ok(None) => StatusCode::NOT_FOUND.into_response(),
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
-    }
+        .map_err(AppError::Database)?
+        .ok_or(AppError::NotFound)?;
+
+    Ok(Json(tag))
 }
 
 /// 更新标签
@@ -117,23 +153,44 @@ pub async fn get_tag(
     responses(
         (status = 200, description = "成功更新标签"),
         (status = 404, description = "标签不存在"),
+        (status = 409, description = "标签名称已存在"),
+        (status = 422, description = "标签名称为空或过长"),
         (status = 500, description = "服务器内部错误")
     )
 )]
 pub async fn update_tag(
+    user: AuthUser,
     Path(id): Path<i64>,
     State(db): State<SqlitePool>,
-    Json(tag): Json<CreateTag>,
-) -> impl IntoResponse {
-    match sqlx::query("UPDATE tags SET name = ? WHERE id = ?")
-        .bind(&tag.name)
+    State(events): State<EventBus>,
+    ValidatedJson(tag): ValidatedJson<CreateTag>,
+) -> Result<StatusCode, AppError> {
+    let name = tag.name.trim();
+    let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+    let before = fetch_tag(&mut tx, user.id, id)
+        .await
+        .map_err(AppError::Database)?
+        .ok_or(AppError::NotFound)?;
+
+    sqlx::query("UPDATE tags SET name = ? WHERE id = ? AND user_id = ?")
+        .bind(name)
         .bind(id)
-        .execute(&db)
+        .bind(user.id)
+        .execute(&mut *tx)
         .await
-    {
-        Ok(_) => StatusCode::OK.into_response(),
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
-    }
+        .map_err(|err| AppError::unique_conflict(err, format!("标签 {} 已存在", name)))?;
+
+    let after = Tag { id, name: name.to_string() };
+    audit::record(&mut tx, user.id, Entity::Tag, id, "update", audit::snapshot(&before), audit::snapshot(&after))
+        .await
+        .map_err(AppError::Database)?;
+
+    tx.commit().await.map_err(AppError::Database)?;
+
+    events.publish(user.id, Topic::Tag, Action::Updated, id);
+
+    Ok(StatusCode::OK)
 }
 
 /// 删除标签
@@ -151,17 +208,45 @@ pub async fn update_tag(
     )
 )]
 pub async fn delete_tag(
+    user: AuthUser,
     Path(id): Path<i64>,
     State(db): State<SqlitePool>,
-) -> impl IntoResponse {
-    match sqlx::query("DELETE FROM tags WHERE id = ?")
+    State(events): State<EventBus>,
+) -> Result<StatusCode, AppError> {
+    let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+    let before = fetch_tag(&mut tx, user.id, id)
+        .await
+        .map_err(AppError::Database)?
+        .ok_or(AppError::NotFound)?;
+
+    // 先移除标签与收藏的关联，再删除标签本身
+    sqlx::query(
+        "DELETE FROM favorite_tags
+         WHERE tag_id = (SELECT id FROM tags WHERE id = ? AND user_id = ?)"
+    )
+    .bind(id)
+    .bind(user.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(AppError::Database)?;
+
+    sqlx::query("DELETE FROM tags WHERE id = ? AND user_id = ?")
         .bind(id)
-        .execute(&db)
+        .bind(user.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::Database)?;
+
+    audit::record(&mut tx, user.id, Entity::Tag, id, "delete", audit::snapshot(&before), None)
         .await
-    {
-        Ok(_) => StatusCode::OK.into_response(),
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
-    }
+        .map_err(AppError::Database)?;
+
+    tx.commit().await.map_err(AppError::Database)?;
+
+    events.publish(user.id, Topic::Tag, Action::Deleted, id);
+
+    Ok(StatusCode::OK)
 }
 
 /// 获取标签相关的收藏
@@ -178,24 +263,31 @@ pub async fn delete_tag(
     )
 )]
 pub async fn get_favorites_by_tag(
+    user: AuthUser,
     Path(tag_name): Path<String>,
     State(db): State<SqlitePool>,
-) -> impl IntoResponse {
+) -> Result<Json<Vec<TaggedFavorite>>, AppError> {
     let sql = r#"
-        SELECT f.id, COALESCE(c.name, '未分类') as category_name, f.text, f.url, f.tags
+        SELECT f.id, COALESCE(c.name, '未分类') as category_name, f.text, f.url,
+            (SELECT json_group_array(t2.name)
+               FROM favorite_tags ft2
+               JOIN tags t2 ON t2.id = ft2.tag_id
+              WHERE ft2.favorite_id = f.id) as tags
         FROM favorites f
+        JOIN favorite_tags ft ON ft.favorite_id = f.id
+        JOIN tags t ON t.id = ft.tag_id
         LEFT JOIN categories c ON f.category_id = c.id
-        WHERE f.tags LIKE ?
+        WHERE t.name = ? AND t.user_id = ? AND f.user_id = ? AND f.deleted_at IS NULL
         ORDER BY f.id DESC
     "#;
 
-    let pattern = format!("%{}%", tag_name);
-    match sqlx::query_as::<_, TaggedFavorite>(sql)
-        .bind(pattern)
+    let favorites = sqlx::query_as::<_, TaggedFavorite>(sql)
+        .bind(&tag_name)
+        .bind(user.id)
+        .bind(user.id)
         .fetch_all(&db)
         .await
-    {
-        Ok(favorites) => Json(favorites).into_response(),
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
-    }
-} 
\ No newline at end of file
+        .map_err(AppError::Database)?;
+
+    Ok(Json(favorites))
+}
@@ -0,0 +1,174 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqlitePool;
+use std::time::Duration as StdDuration;
+use utoipa::ToSchema;
+use crate::audit::{self, Entity};
+use crate::auth::AuthUser;
+use crate::config::TrashConfig;
+use crate::error::AppError;
+use crate::events::{Action, EventBus, Topic};
+use crate::handlers::favorite::{favorite_snapshot, fetch_favorite, Favorite, FAVORITE_COLUMNS, MAX_PER_PAGE};
+use crate::timezone::{self, ClientTimezone};
+
+/// 回收站列表查询参数
+#[derive(Debug, Deserialize)]
+pub struct TrashQuery {
+    pub page: Option<i64>,     // 页码
+    pub per_page: Option<i64>, // 每页数量
+}
+
+/// 回收站列表响应
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TrashResponse {
+    pub total: i64,
+    pub items: Vec<Favorite>,
+}
+
+/// 获取回收站中的收藏，按删除时间倒序
+#[utoipa::path(
+    get,
+    path = "/api/trash",
+    tag = "trash",
+    params(
+        ("page" = Option<i64>, Query, description = "页码，默认为1"),
+        ("per_page" = Option<i64>, Query, description = "每页数量，默认为10，最多100"),
+        ("tz" = Option<String>, Query, description = "IANA 时区名，如 Asia/Shanghai，也可通过 X-Timezone 请求头指定，默认为 UTC")
+    ),
+    responses(
+        (status = 200, description = "成功获取回收站列表", body = TrashResponse),
+        (status = 500, description = "服务器内部错误")
+    )
+)]
+pub async fn list_trash(
+    user: AuthUser,
+    tz: ClientTimezone,
+    Query(params): Query<TrashQuery>,
+    State(db): State<SqlitePool>,
+) -> Result<Json<TrashResponse>, AppError> {
+    let per_page = params.per_page.unwrap_or(10).clamp(1, MAX_PER_PAGE);
+    let offset = (params.page.unwrap_or(1).max(1) - 1) * per_page;
+
+    let total: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM favorites WHERE user_id = ? AND deleted_at IS NOT NULL"
+    )
+    .bind(user.id)
+    .fetch_one(&db)
+    .await
+    .map_err(AppError::Database)?;
+
+    let sql = format!(
+        "SELECT {} FROM favorites f LEFT JOIN categories c ON f.category_id = c.id
+         WHERE f.user_id = ? AND f.deleted_at IS NOT NULL
+         ORDER BY f.deleted_at DESC, f.id DESC LIMIT ? OFFSET ?",
+        FAVORITE_COLUMNS
+    );
+    let items = sqlx::query_as::<_, Favorite>(&sql)
+        .bind(user.id)
+        .bind(per_page)
+        .bind(offset)
+        .fetch_all(&db)
+        .await
+        .map_err(AppError::Database)?;
+
+    Ok(Json(TrashResponse {
+        total,
+        items: items.into_iter().map(|favorite| favorite.localize(&tz)).collect(),
+    }))
+}
+
+/// 从回收站恢复收藏
+#[utoipa::path(
+    post,
+    path = "/api/trash/{id}/restore",
+    tag = "trash",
+    params(
+        ("id" = i64, Path, description = "收藏ID"),
+        ("tz" = Option<String>, Query, description = "IANA 时区名，如 Asia/Shanghai，也可通过 X-Timezone 请求头指定，默认为 UTC")
+    ),
+    responses(
+        (status = 200, description = "成功恢复收藏，返回恢复后的记录", body = Favorite),
+        (status = 404, description = "回收站中没有该收藏"),
+        (status = 500, description = "服务器内部错误")
+    )
+)]
+pub async fn restore_favorite(
+    user: AuthUser,
+    tz: ClientTimezone,
+    Path(id): Path<i64>,
+    State(db): State<SqlitePool>,
+    State(events): State<EventBus>,
+) -> Result<Json<Favorite>, AppError> {
+    let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+    let before = favorite_snapshot(&mut tx, id).await.map_err(AppError::Database)?;
+
+    let result = sqlx::query(
+        "UPDATE favorites SET deleted_at = NULL, updated_at = ?
+         WHERE id = ? AND user_id = ? AND deleted_at IS NOT NULL"
+    )
+    .bind(timezone::now())
+    .bind(id)
+    .bind(user.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(AppError::Database)?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    let favorite = fetch_favorite(&mut tx, id).await.map_err(AppError::Database)?;
+    audit::record(&mut tx, user.id, Entity::Favorite, id, "restore", before, audit::snapshot(&favorite))
+        .await
+        .map_err(AppError::Database)?;
+
+    tx.commit().await.map_err(AppError::Database)?;
+
+    // 对列表而言恢复的收藏相当于新出现
+    events.publish(user.id, Topic::Favorite, Action::Created, id);
+
+    Ok(Json(favorite.localize(&tz)))
+}
+
+/// 彻底删除在回收站中超过保留期限的收藏，返回删除的数量
+/// 标签关联随之删除，笔记由外键级联删除
+pub async fn purge_expired(db: &SqlitePool, retention_days: i64) -> Result<u64, sqlx::Error> {
+    let cutoff = timezone::to_storage(Utc::now() - Duration::days(retention_days));
+
+    let mut tx = db.begin().await?;
+
+    sqlx::query(
+        "DELETE FROM favorite_tags WHERE favorite_id IN
+            (SELECT id FROM favorites WHERE deleted_at IS NOT NULL AND deleted_at < ?)"
+    )
+    .bind(&cutoff)
+    .execute(&mut *tx)
+    .await?;
+
+    let result = sqlx::query("DELETE FROM favorites WHERE deleted_at IS NOT NULL AND deleted_at < ?")
+        .bind(&cutoff)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(result.rows_affected())
+}
+
+/// 按配置的间隔定期清理回收站，在后台任务中运行
+pub async fn run_purge(db: SqlitePool, config: TrashConfig) {
+    let mut interval = tokio::time::interval(StdDuration::from_secs(config.purge_interval_secs.max(1)));
+    loop {
+        interval.tick().await;
+        match purge_expired(&db, config.retention_days).await {
+            Ok(0) => {}
+            Ok(count) => tracing::info!("Purged {} favorites from trash", count),
+            Err(err) => tracing::error!("Failed to purge trash: {}", err),
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use futures_util::StreamExt;
+use reqwest::{Method, StatusCode};
+use sqlx::sqlite::SqlitePool;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use crate::config::LinkCheckConfig;
+use crate::outbound;
+use crate::url_normalize;
+use crate::timezone;
+
+/// 一个网址的检查结果
+struct CheckResult {
+    final_url: Option<String>, // 跟随重定向后的网址
+    status: Option<u16>,       // 最终响应的状态码，请求失败时为 None
+    error: Option<String>,     // 请求失败的原因
+}
+
+/// 检查网址是否仍可访问，先发送 HEAD，失败或被拒绝时改用 GET
+/// 部分服务器不支持 HEAD 或对其返回错误状态，GET 的结果更可靠
+async fn check(client: &outbound::Client, url: &str) -> CheckResult {
+    let response = match client.send(Method::HEAD, url).await {
+        Ok(response) if response.status() < StatusCode::BAD_REQUEST => Ok(response),
+        _ => client.send(Method::GET, url).await,
+    };
+
+    match response {
+        Ok(response) => CheckResult {
+            final_url: Some(response.url().to_string()),
+            status: Some(response.status().as_u16()),
+            error: None,
+        },
+        Err(err) => CheckResult {
+            final_url: None,
+            status: None,
+            error: Some(err),
+        },
+    }
+}
+
+/// 保存检查结果，重定向到其他页面时标记为已迁移
+async fn save(db: &SqlitePool, url: &str, result: CheckResult) -> Result<(), sqlx::Error> {
+    let moved = result
+        .final_url
+        .as_deref()
+        .is_some_and(|final_url| url_normalize::canonical_url(final_url) != url_normalize::canonical_url(url));
+
+    sqlx::query(
+        "INSERT OR REPLACE INTO link_checks (url, final_url, last_status, error, moved, last_checked_at)
+         VALUES (?, ?, ?, ?, ?, ?)"
+    )
+    .bind(url)
+    .bind(result.final_url)
+    .bind(result.status)
+    .bind(result.error)
+    .bind(moved)
+    .bind(timezone::now())
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// 检查所有收藏中不重复的网址，返回检查的数量
+/// 按主机分组，组内依次请求并保持间隔，不同主机之间并发
+pub async fn check_all(
+    db: &SqlitePool,
+    client: &outbound::Client,
+    config: &LinkCheckConfig,
+) -> Result<usize, sqlx::Error> {
+    // 清理已不被任何收藏引用的网址
+    sqlx::query("DELETE FROM link_checks WHERE url NOT IN (SELECT url FROM favorites)")
+        .execute(db)
+        .await?;
+
+    let urls: Vec<String> = sqlx::query_scalar("SELECT DISTINCT url FROM favorites WHERE deleted_at IS NULL")
+        .fetch_all(db)
+        .await?;
+    let total = urls.len();
+
+    let mut hosts: HashMap<String, Vec<String>> = HashMap::new();
+    for url in urls {
+        hosts.entry(url_normalize::host(&url)).or_default().push(url);
+    }
+
+    let delay = Duration::from_millis(config.per_host_delay_ms);
+    futures_util::stream::iter(hosts.into_values())
+        .for_each_concurrent(config.concurrency.max(1), |urls| async move {
+            for (index, url) in urls.iter().enumerate() {
+                if index > 0 {
+                    tokio::time::sleep(delay).await;
+                }
+                let result = check(client, url).await;
+                if let Err(err) = save(db, url, result).await {
+                    tracing::error!("Failed to save link check for {}: {}", url, err);
+                }
+            }
+        })
+        .await;
+
+    Ok(total)
+}
+
+/// 按配置的间隔定期检查收藏的网址，在后台任务中运行，未启用时直接返回
+pub async fn run_link_checker(db: SqlitePool, config: LinkCheckConfig) {
+    if !config.enabled {
+        return;
+    }
+
+    let client = match outbound::Client::new(
+        Duration::from_secs(config.timeout_secs.max(1)),
+        concat!("chrome-ex-get-text/", env!("CARGO_PKG_VERSION")),
+        config.allow_private_network,
+    ) {
+        Ok(client) => client,
+        Err(err) => {
+            tracing::error!("Failed to create link checker HTTP client: {}", err);
+            return;
+        }
+    };
+
+    // 一轮检查超过间隔时，下一轮从检查结束后重新计时
+    let mut interval = tokio::time::interval(Duration::from_secs(config.interval_secs.max(1)));
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        match check_all(&db, &client, &config).await {
+            Ok(count) => tracing::info!("Checked {} links", count),
+            Err(err) => tracing::error!("Failed to check links: {}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{response::Redirect, routing::get, Router};
+    use crate::tests::serve;
+
+    #[tokio::test]
+    async fn test_check() {
+        let router = Router::new()
+            .route("/ok", get(|| async { "ok" }))
+            .route("/gone", get(|| async { StatusCode::NOT_FOUND }))
+            .route("/old", get(|| async { Redirect::permanent("/ok") }))
+            // 拒绝 HEAD 的服务器改用 GET 检查
+            .route(
+                "/no-head",
+                get(|| async { "ok" }).head(|| async { StatusCode::METHOD_NOT_ALLOWED }),
+            );
+        let addr = serve(router).await;
+        let client = outbound::Client::new(Duration::from_secs(5), "test", true).unwrap();
+        let url = |path: &str| format!("http://{}{}", addr, path);
+
+        let result = check(&client, &url("/ok")).await;
+        assert_eq!((result.status, result.final_url, result.error), (Some(200), Some(url("/ok")), None));
+
+        let result = check(&client, &url("/gone")).await;
+        assert_eq!((result.status, result.error), (Some(404), None));
+
+        let result = check(&client, &url("/old")).await;
+        assert_eq!((result.status, result.final_url), (Some(200), Some(url("/ok"))));
+
+        let result = check(&client, &url("/no-head")).await;
+        assert_eq!(result.status, Some(200));
+
+        let result = check(&client, "http://127.0.0.1:1/").await;
+        assert_eq!((result.status, result.final_url), (None, None));
+        assert!(result.error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_check_rejects_private_addresses() {
+        let addr = serve(Router::new().route("/", get(|| async { "ok" }))).await;
+        let client = outbound::Client::new(Duration::from_secs(5), "test", false).unwrap();
+
+        let result = check(&client, &format!("http://{}/", addr)).await;
+        assert_eq!(result.status, None);
+        assert!(result.error.is_some_and(|error| error.contains("内网地址")));
+    }
+}
\ No newline at end of file
@@ -1,6 +1,7 @@
 use axum::{
-    routing::{get, post, delete, put},
+    routing::{get, post, delete, put, patch},
     body::Body,
+    extract::DefaultBodyLimit,
     Router,
     response::IntoResponse,
     http::{StatusCode, Request, Response},
@@ -15,21 +16,54 @@ use tower_http::{
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
-use sqlx::sqlite::SqlitePool;
 use dotenv::dotenv;
 use serde_json::json;
 use tracing::Level;
 use std::time::Duration;
+use archiver::ArchiverHandle;
+use events::EventBus;
+use state::AppState;
 
 mod api_doc;
+mod archiver;
+mod audit;
+mod auth;
 mod config;
 mod db;
 mod error;
+mod events;
+mod link_checker;
+mod normalize;
+mod outbound;
+mod sanitize;
+mod state;
+mod text_fragment;
+mod timezone;
+mod url_normalize;
+mod validation;
 mod handlers {
     pub mod favorite;
+    pub mod note;
     pub mod category;
     pub mod tag;
+    pub mod search;
+    pub mod admin;
+    pub mod export;
+    pub mod import;
+    pub mod batch;
+    pub mod trash;
+    pub mod history;
+    pub mod snapshot;
+    pub mod link;
+    pub mod domain;
+    pub mod page;
+    pub mod event;
 }
+#[cfg(test)]
+mod tests;
+
+/// 导入文件的请求体大小上限
+const IMPORT_BODY_LIMIT: usize = 32 * 1024 * 1024;
 
 // 添加健康检查处理函数
 async fn health_check() -> impl IntoResponse {
@@ -42,25 +76,55 @@ async fn health_check() -> impl IntoResponse {
 
 /// 创建应用路由
 /// 设置所有 API 端点的路由规则
-fn create_routes(db: SqlitePool) -> Router {
+fn create_routes(state: AppState) -> Router {
     Router::new()
         .route("/api/health", get(health_check))  // 添加健康检查路由
         .route("/api/categories", get(handlers::category::list_categories))
         .route("/api/categories", post(handlers::category::create_category))
+        .route("/api/categories/tree", get(handlers::category::get_category_tree))
         .route("/api/categories/:id", get(handlers::category::get_category))
         .route("/api/categories/:id", put(handlers::category::update_category))
         .route("/api/categories/:id", delete(handlers::category::delete_category))
+        .route("/api/categories/:id/move", post(handlers::category::move_category))
         .route("/api/favorites", get(handlers::favorite::list_favorites))
         .route("/api/favorites", post(handlers::favorite::create_favorite))
+        .route("/api/favorites/merge", post(handlers::favorite::merge_favorites))
+        .route("/api/favorites/batch", post(handlers::batch::batch_favorites))
+        .route("/api/favorites/:id", get(handlers::favorite::get_favorite))
         .route("/api/favorites/:id", put(handlers::favorite::update_favorite))
+        .route("/api/favorites/:id", patch(handlers::favorite::patch_favorite))
         .route("/api/favorites/:id", delete(handlers::favorite::delete_favorite))
+        .route("/api/favorites/:id/history", get(handlers::history::favorite_history))
+        .route("/api/favorites/:id/revert/:version", post(handlers::history::revert_favorite))
+        .route("/api/favorites/:id/snapshot", get(handlers::snapshot::get_snapshot))
+        .route("/api/favorites/:id/notes", get(handlers::note::list_notes))
+        .route("/api/favorites/:id/notes", post(handlers::note::create_note))
+        .route("/api/favorites/:id/notes/:note_id", put(handlers::note::update_note))
+        .route("/api/favorites/:id/notes/:note_id", delete(handlers::note::delete_note))
         .route("/api/tags", get(handlers::tag::list_tags))
         .route("/api/tags", post(handlers::tag::create_tag))
         .route("/api/tags/:id", get(handlers::tag::get_tag))
         .route("/api/tags/:id", put(handlers::tag::update_tag))
         .route("/api/tags/:id", delete(handlers::tag::delete_tag))
         .route("/api/tags/:name/favorites", get(handlers::tag::get_favorites_by_tag))
-        .with_state(db)
+        .route("/api/trash", get(handlers::trash::list_trash))
+        .route("/api/trash/:id/restore", post(handlers::trash::restore_favorite))
+        .route("/api/links/broken", get(handlers::link::list_broken_links))
+        .route("/api/domains", get(handlers::domain::list_domains))
+        .route("/api/pages", get(handlers::page::list_pages))
+        .route("/api/pages/:id/favorites", get(handlers::page::list_page_favorites))
+        .route("/api/search", get(handlers::search::search_favorites))
+        .route("/api/events", get(handlers::event::stream_events))
+        .route("/api/events/token", post(handlers::event::issue_event_token))
+        .route("/api/export", get(handlers::export::export_favorites))
+        .route(
+            "/api/import",
+            post(handlers::import::import_favorites).layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT)),
+        )
+        .route("/api/admin/keys", get(handlers::admin::list_keys))
+        .route("/api/admin/keys", post(handlers::admin::issue_key))
+        .route("/api/admin/keys/:id", delete(handlers::admin::revoke_key))
+        .with_state(state)
 }
 
 #[tokio::main]
@@ -80,8 +144,23 @@ async fn main() {
     // 初始化数据库连接池
     let pool = db::init_db().await.expect("Failed to initialize database");
 
+    // 确保管理员可以登录
+    auth::ensure_admin_key(&pool, &config.server.admin_key_file)
+        .await
+        .expect("Failed to issue admin API key");
+
+    // 后台定期清理回收站
+    tokio::spawn(handlers::trash::run_purge(pool.clone(), config.trash.clone()));
+
+    // 后台为新收藏保存来源网页的快照（需在配置中启用），保存收藏后通过 archiver 唤醒
+    let archiver = ArchiverHandle::default();
+    tokio::spawn(archiver::run_archiver(pool.clone(), config.archiver.clone(), archiver.clone()));
+
+    // 后台定期检查收藏的网址是否失效（需在配置中启用）
+    tokio::spawn(link_checker::run_link_checker(pool.clone(), config.link_check.clone()));
+
     // 创建基础 API 路由
-    let api_routes = create_routes(pool);
+    let api_routes = create_routes(AppState { db: pool, archiver, events: EventBus::default() });
 
     // 创建 Swagger UI 路由，用于API文档展示
     let swagger_ui = Router::new()
@@ -123,7 +202,7 @@ async fn main() {
         );
 
     // 配置并启动服务器
-    let addr = SocketAddr::from(([127, 0, 0, 1], config.server.port));
+    let addr = SocketAddr::new(config.server.host, config.server.port);
     tracing::info!("Server running on http://{}", addr);
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
     axum::serve(listener, app.into_make_service()).await.unwrap();
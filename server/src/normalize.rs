@@ -0,0 +1,56 @@
+use sha2::{Digest, Sha256};
+use crate::url_normalize;
+
+/// 全角 ASCII 字符转为半角
+fn fold_width(c: char) -> char {
+    match c {
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        '\u{3000}' => ' ',
+        _ => c,
+    }
+}
+
+/// 规范化文本用于查重：全角转半角、转小写，并去除空白和标点
+pub fn normalize_text(text: &str) -> String {
+    text.chars()
+        .map(fold_width)
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// 查重键：规范化 URL 与规范化文本的哈希，二者都相同即视为重复
+pub fn dedup_key(text: &str, url: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(url_normalize::canonical_url(url).as_bytes());
+    hasher.update(b"\n");
+    hasher.update(normalize_text(text).as_bytes());
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_text() {
+        assert_eq!(normalize_text("Hello, World!"), "helloworld");
+        assert_eq!(normalize_text("ＡＢＣ　１２３！"), "abc123");
+        assert_eq!(normalize_text("你好，世界。"), "你好世界");
+        assert_eq!(normalize_text("  \t\n "), "");
+    }
+
+    #[test]
+    fn test_dedup_key() {
+        let key = dedup_key("Hello, World", "https://example.com/a");
+        assert_eq!(key.len(), 64);
+        // 只有格式差异的文本和网址视为重复
+        assert_eq!(key, dedup_key("  hello world！", "HTTPS://Example.com/a/?utm_source=x#top"));
+        assert_ne!(key, dedup_key("Hello, World", "https://example.com/b"));
+        assert_ne!(key, dedup_key("Hello, Rust", "https://example.com/a"));
+    }
+}
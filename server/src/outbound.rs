@@ -0,0 +1,224 @@
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::redirect::Policy;
+use reqwest::{Method, Response, Url};
+use std::error::Error;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// 最多跟随的重定向次数，与 reqwest 的默认值相同
+const MAX_REDIRECTS: usize = 10;
+
+/// 判断是否允许连接某个地址
+type AddrFilter = fn(IpAddr) -> bool;
+
+/// 是否为公网 IPv4 地址，排除回环、私有网段、链路本地（含云服务元数据地址 169.254.169.254）等保留地址
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || a == 0                              // 0.0.0.0/8
+        || (a == 100 && (64..128).contains(&b)) // 100.64.0.0/10 运营商级 NAT
+        || (a == 192 && b == 0 && c == 0)       // 192.0.0.0/24 协议分配
+        || (a == 198 && (18..20).contains(&b))  // 198.18.0.0/15 基准测试
+        || a >= 240)                            // 240.0.0.0/4 保留
+}
+
+/// 是否为公网地址，IPv4 映射的 IPv6 地址按其中的 IPv4 地址判断
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(v4) => is_public_v4(v4),
+            None => is_public_v6(ip),
+        },
+    }
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        || ip.is_unique_local()
+        || ip.is_unicast_link_local()
+        || first == 0x2001 && ip.segments()[1] == 0x0db8 // 2001:db8::/32 文档示例
+        || first == 0x0064 && ip.segments()[1] == 0xff9b) // 64:ff9b::/96 NAT64，可映射到内网地址
+}
+
+/// 检查网址中以 IP 形式给出的主机，域名在连接时由 FilteredResolver 检查
+fn check_host(url: &Url, allowed: AddrFilter) -> Result<(), String> {
+    let host = url.host_str().ok_or_else(|| "网址缺少主机名".to_string())?;
+    match host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+        Ok(ip) if !allowed(ip) => Err(format!("不允许访问内网地址 {}", ip)),
+        _ => Ok(()),
+    }
+}
+
+/// 只返回允许连接的地址的 DNS 解析器，域名只解析到内网地址时报错
+/// 每次建立连接（包括重定向后）都经过解析器，避免先解析到公网地址、连接时再指向内网
+struct FilteredResolver(AddrFilter);
+
+impl Resolve for FilteredResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        let allowed = self.0;
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| allowed(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} 没有可访问的公网地址", host).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// 访问收藏网址的 HTTP 客户端，供快照抓取和失效链接检查使用
+/// 默认只能连接公网地址，首个请求和每次重定向都会检查，防止借收藏的网址访问内网服务
+#[derive(Clone)]
+pub struct Client {
+    inner: reqwest::Client,
+    allowed: AddrFilter,
+}
+
+impl Client {
+    /// `allow_private` 为 true 时不限制目标地址，用于收藏内网网页的私有部署
+    pub fn new(timeout: Duration, user_agent: &str, allow_private: bool) -> reqwest::Result<Self> {
+        let allowed: AddrFilter = if allow_private { |_| true } else { is_public };
+        Self::with_filter(timeout, user_agent, allowed)
+    }
+
+    fn with_filter(timeout: Duration, user_agent: &str, allowed: AddrFilter) -> reqwest::Result<Self> {
+        let redirect = Policy::custom(move |attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                return attempt.error("重定向次数过多");
+            }
+            match check_host(attempt.url(), allowed) {
+                Ok(()) => attempt.follow(),
+                Err(err) => attempt.error(err),
+            }
+        });
+
+        let inner = reqwest::Client::builder()
+            .timeout(timeout)
+            .user_agent(user_agent)
+            .redirect(redirect)
+            .dns_resolver(Arc::new(FilteredResolver(allowed)))
+            // 经代理访问时由代理解析域名，无法检查目标地址
+            .no_proxy()
+            .build()?;
+
+        Ok(Client { inner, allowed })
+    }
+
+    /// 发送请求，失败时返回包含底层原因的错误描述
+    pub async fn send(&self, method: Method, url: &str) -> Result<Response, String> {
+        let url = Url::parse(url).map_err(|err| format!("不是有效的网址: {}", err))?;
+        check_host(&url, self.allowed)?;
+        self.inner.request(method, url).send().await.map_err(|err| describe(&err))
+    }
+}
+
+/// 拼接错误及其来源，reqwest 的错误本身只包含请求的网址
+pub fn describe(err: &dyn Error) -> String {
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(err) = source {
+        message.push_str(": ");
+        message.push_str(&err.to_string());
+        source = err.source();
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{response::Redirect, routing::get, Router};
+    use crate::tests::serve;
+
+    fn client(allow_private: bool) -> Client {
+        Client::new(Duration::from_secs(5), "test", allow_private).unwrap()
+    }
+
+    #[test]
+    fn test_is_public() {
+        for ip in ["8.8.8.8", "1.1.1.1", "2606:4700:4700::1111", "::ffff:8.8.8.8"] {
+            assert!(is_public(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "255.255.255.255",
+            "::1",
+            "::",
+            "fc00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "::ffff:169.254.169.254",
+        ] {
+            assert!(!is_public(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rejects_private_addresses() {
+        let addr = serve(Router::new().route("/", get(|| async { "secret" }))).await;
+        let client = client(false);
+
+        let err = client.send(Method::GET, &format!("http://{}/", addr)).await.unwrap_err();
+        assert!(err.contains("内网地址"), "{}", err);
+
+        // 域名解析到内网地址
+        let err = client.send(Method::GET, &format!("http://localhost:{}/", addr.port())).await.unwrap_err();
+        assert!(err.contains("公网地址"), "{}", err);
+
+        let err = client.send(Method::GET, "http://[::1]/").await.unwrap_err();
+        assert!(err.contains("内网地址"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_redirect_to_private_address() {
+        // 测试网站只能监听本机，这里把 127.0.0.1 当作唯一的公网地址，检查重定向的每一跳
+        let router = Router::new()
+            .route("/", get(|| async { Redirect::temporary("/next") }))
+            .route("/next", get(|| async { Redirect::temporary("http://169.254.169.254/latest/meta-data") }))
+            .route("/local", get(|| async { Redirect::temporary("http://127.0.0.2/") }));
+        let addr = serve(router).await;
+        let client = Client::with_filter(Duration::from_secs(5), "test", |ip| {
+            ip == IpAddr::V4(Ipv4Addr::LOCALHOST)
+        })
+        .unwrap();
+
+        let err = client.send(Method::GET, &format!("http://{}/", addr)).await.unwrap_err();
+        assert!(err.contains("内网地址 169.254.169.254"), "{}", err);
+
+        let err = client.send(Method::GET, &format!("http://{}/local", addr)).await.unwrap_err();
+        assert!(err.contains("内网地址 127.0.0.2"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_allow_private() {
+        let router = Router::new()
+            .route("/", get(|| async { Redirect::temporary("/final") }))
+            .route("/final", get(|| async { "ok" }));
+        let addr = serve(router).await;
+
+        let response = client(true).send(Method::GET, &format!("http://{}/", addr)).await.unwrap();
+        assert_eq!(response.url().path(), "/final");
+        assert_eq!(response.text().await.unwrap(), "ok");
+    }
+}
\ No newline at end of file
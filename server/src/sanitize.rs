@@ -0,0 +1,57 @@
+use ammonia::UrlRelative;
+use std::collections::HashSet;
+
+/// 允许保留的标签，只包含排版相关的元素
+const ALLOWED_TAGS: &[&str] = &[
+    "a", "abbr", "b", "blockquote", "br", "code", "del", "div", "em", "h1", "h2", "h3", "h4",
+    "h5", "h6", "hr", "i", "ins", "li", "mark", "ol", "p", "pre", "q", "s", "small", "span",
+    "strong", "sub", "sup", "table", "tbody", "td", "tfoot", "th", "thead", "tr", "u", "ul",
+];
+
+/// 链接允许的协议
+const ALLOWED_URL_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+/// 按白名单清理选区的 HTML，去除脚本、事件属性、样式及不安全的链接
+pub fn sanitize_html(html: &str) -> String {
+    ammonia::Builder::empty()
+        .tags(ALLOWED_TAGS.iter().copied().collect::<HashSet<_>>())
+        .clean_content_tags(["script", "style"].into_iter().collect::<HashSet<_>>())
+        .add_tag_attributes("a", ["href", "title"])
+        .add_tag_attributes("abbr", ["title"])
+        .add_tag_attributes("td", ["colspan", "rowspan"])
+        .add_tag_attributes("th", ["colspan", "rowspan"])
+        .url_schemes(ALLOWED_URL_SCHEMES.iter().copied().collect::<HashSet<_>>())
+        .url_relative(UrlRelative::Deny)
+        .link_rel(Some("noopener noreferrer nofollow"))
+        .clean(html)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_html() {
+        assert_eq!(sanitize_html("<p onclick=\"x()\">Hi<script>alert(1)</script></p>"), "<p>Hi</p>");
+        assert_eq!(sanitize_html("<style>p { color: red }</style>text"), "text");
+        assert_eq!(
+            sanitize_html("<div style=\"color: red\"><img src=\"x\"><b>bold</b></div>"),
+            "<div><b>bold</b></div>"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_links() {
+        assert_eq!(
+            sanitize_html("<a href=\"https://example.com\" target=\"_blank\">x</a>"),
+            "<a href=\"https://example.com\" rel=\"noopener noreferrer nofollow\">x</a>"
+        );
+        // 不安全的协议和相对链接去掉 href
+        assert_eq!(
+            sanitize_html("<a href=\"javascript:alert(1)\">x</a>"),
+            "<a rel=\"noopener noreferrer nofollow\">x</a>"
+        );
+        assert_eq!(sanitize_html("<a href=\"/a\">x</a>"), "<a rel=\"noopener noreferrer nofollow\">x</a>");
+    }
+}
@@ -0,0 +1,30 @@
+use axum::extract::FromRef;
+use sqlx::sqlite::SqlitePool;
+use crate::archiver::ArchiverHandle;
+use crate::events::EventBus;
+
+/// 路由共享的状态，处理函数通过 `State<SqlitePool>` 等按需取出其中一项
+#[derive(Clone)]
+pub struct AppState {
+    pub db: SqlitePool,
+    pub archiver: ArchiverHandle, // 唤醒快照抓取任务
+    pub events: EventBus,         // 数据变更事件
+}
+
+impl FromRef<AppState> for SqlitePool {
+    fn from_ref(state: &AppState) -> Self {
+        state.db.clone()
+    }
+}
+
+impl FromRef<AppState> for ArchiverHandle {
+    fn from_ref(state: &AppState) -> Self {
+        state.archiver.clone()
+    }
+}
+
+impl FromRef<AppState> for EventBus {
+    fn from_ref(state: &AppState) -> Self {
+        state.events.clone()
+    }
+}
\ No newline at end of file
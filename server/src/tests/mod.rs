@@ -1,369 +1,1588 @@
-use crate::{config::Config, db::DbPool};
-use rusqlite::Connection;
-use std::sync::Once;
 use axum::{
-    body::Body,
-    http::{Request, StatusCode},
-    routing::{get, post, put, delete},
+    body::{self, Body},
+    http::{header, Method, Request, StatusCode},
     Router,
 };
+use serde_json::{json, Value};
+use sqlx::sqlite::SqlitePool;
+use std::net::SocketAddr;
 use tower::ServiceExt;
-use serde_json::json;
-use crate::handlers::{category, favorite, tag};
+use crate::archiver::ArchiverHandle;
+use crate::events::EventBus;
+use crate::state::AppState;
+use crate::{auth, create_routes, db};
 
-static INIT: Once = Once::new();
+/// 测试用的应用，每个测试使用独立的内存数据库
+struct TestApp {
+    db: SqlitePool,
+    router: Router,
+    events: EventBus,
+    admin_key: String, // 默认管理员的 API Key
+}
 
 // 初始化测试环境
-pub fn init_test() -> DbPool {
-    INIT.call_once(|| {
-        let _ = tracing_subscriber::fmt().try_init();
+async fn init_test() -> TestApp {
+    let db = db::connect("sqlite::memory:").await.expect("Failed to create database");
+
+    let admin_id: i64 = sqlx::query_scalar("SELECT id FROM users WHERE is_admin = 1 ORDER BY id LIMIT 1")
+        .fetch_one(&db)
+        .await
+        .unwrap();
+    let (_, admin_key) = auth::issue_key(&db, admin_id).await.unwrap();
+
+    let events = EventBus::default();
+    let router = create_routes(AppState {
+        db: db.clone(),
+        archiver: ArchiverHandle::default(),
+        events: events.clone(),
     });
+    TestApp { db, router, events, admin_key }
+}
 
-    // 使用内存数据库进行测试
-    let manager = r2d2_sqlite::SqliteConnectionManager::memory();
-    let pool = r2d2::Pool::new(manager).expect("Failed to create pool");
-    
-    // 初始化数据库表
-    let conn = pool.get().unwrap();
-    init_test_db(&conn);
-    
-    pool
-}
-
-// 初始化测试数据库
-fn init_test_db(conn: &Connection) {
-    conn.execute_batch(
-        "
-        -- 删除旧表(如果存在)
-        DROP TABLE IF EXISTS bookmark_tags;
-        DROP TABLE IF EXISTS bookmarks;
-
-        -- 创建必要的表
-        CREATE TABLE categories (
-            id INTEGER PRIMARY KEY,
-            name TEXT NOT NULL UNIQUE
-        );
-
-        CREATE TABLE tags (
-            id INTEGER PRIMARY KEY,
-            name TEXT NOT NULL UNIQUE
-        );
-
-        CREATE TABLE favorites (
-            id INTEGER PRIMARY KEY,
-            text TEXT NOT NULL,
-            url TEXT NOT NULL,
-            category_id INTEGER,
-            tags TEXT NOT NULL,
-            created_at TEXT NOT NULL,
-            FOREIGN KEY(category_id) REFERENCES categories(id)
-        );
-
-        -- 插入测试数据
-        INSERT INTO categories (id, name) VALUES (1, 'Test Category');
-        INSERT INTO tags (id, name) VALUES (1, 'Test Tag');
-        INSERT INTO favorites (id, text, url, category_id, tags, created_at) 
-        VALUES (1, 'Test Content', 'http://test.com', 1, '[\"Test Tag\"]', datetime('now'));
-        "
-    ).expect("Failed to initialize test database");
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    // 分类相关测试
-    #[tokio::test]
-    async fn test_category_crud() {
-        let pool = init_test();
-        let app = Router::new()
-            .route("/api/categories", get(category::list_categories))
-            .route("/api/categories", post(category::create_category))
-            .route("/api/categories/:id", get(category::get_category))
-            .route("/api/categories/:id", put(category::update_category))
-            .route("/api/categories/:id", delete(category::delete_category))
-            .with_state(pool);
-
-        // 测试获取分类列表
-        let response = app
-            .clone()
-            .oneshot(Request::builder().uri("/api/categories").body(Body::empty()).unwrap())
-            .await
-            .unwrap();
-        assert_eq!(response.status(), StatusCode::OK);
-        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
-        let categories: Vec<category::Category> = serde_json::from_slice(&body).unwrap();
-        assert!(!categories.is_empty());
-
-        // 测试创建分类
-        let new_category = json!({
-            "name": "New Category"
-        });
-        let response = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .method("POST")
-                    .uri("/api/categories")
-                    .header("Content-Type", "application/json")
-                    .body(Body::from(serde_json::to_string(&new_category).unwrap()))
-                    .unwrap(),
-            )
-            .await
-            .unwrap();
-        assert_eq!(response.status(), StatusCode::CREATED);
-
-        // 测试更新分类
-        let update_category = json!({
-            "id": 1,
-            "name": "Updated Category"
-        });
-        let response = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .method("PUT")
-                    .uri("/api/categories/1")
-                    .header("Content-Type", "application/json")
-                    .body(Body::from(serde_json::to_string(&update_category).unwrap()))
-                    .unwrap(),
-            )
-            .await
-            .unwrap();
-        assert_eq!(response.status(), StatusCode::OK);
-
-        // 测试删除分类
-        let response = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .method("DELETE")
-                    .uri("/api/categories/1")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
-            .await
-            .unwrap();
-        assert_eq!(response.status(), StatusCode::OK);
+impl TestApp {
+    /// 以 `key` 的身份发送请求，返回状态码和响应体，响应体不是 JSON 时为字符串
+    async fn request(&self, key: &str, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::AUTHORIZATION, format!("Bearer {}", key));
+        let body = match body {
+            Some(body) => {
+                request = request.header(header::CONTENT_TYPE, "application/json");
+                Body::from(body.to_string())
+            }
+            None => Body::empty(),
+        };
+        self.send(request.body(body).unwrap()).await
     }
 
-    // 标签相关测试
-    #[tokio::test]
-    async fn test_tag_crud() {
-        let pool = init_test();
-        let app = Router::new()
-            .route("/api/tags", get(tag::list_tags))
-            .route("/api/tags", post(tag::create_tag))
-            .route("/api/tags/:id", get(tag::get_tag))
-            .route("/api/tags/:id", put(tag::update_tag))
-            .route("/api/tags/:id", delete(tag::delete_tag))
-            .with_state(pool);
-
-        // 测试获取标签列表
-        let response = app
-            .clone()
-            .oneshot(Request::builder().uri("/api/tags").body(Body::empty()).unwrap())
-            .await
-            .unwrap();
-        assert_eq!(response.status(), StatusCode::OK);
-        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
-        let tags: Vec<tag::Tag> = serde_json::from_slice(&body).unwrap();
-        assert!(!tags.is_empty());
-
-        // 测试创建标签
-        let new_tag = json!({
-            "name": "New Tag"
-        });
-        let response = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .method("POST")
-                    .uri("/api/tags")
-                    .header("Content-Type", "application/json")
-                    .body(Body::from(serde_json::to_string(&new_tag).unwrap()))
-                    .unwrap(),
-            )
-            .await
+    /// 以 `key` 的身份发送原样的请求体
+    async fn post_raw(&self, key: &str, uri: &str, body: &str) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(uri)
+            .header(header::AUTHORIZATION, format!("Bearer {}", key))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
             .unwrap();
-        assert_eq!(response.status(), StatusCode::CREATED);
-
-        // 测试更新标签
-        let update_tag = json!({
-            "name": "Updated Tag"
-        });
-        let response = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .method("PUT")
-                    .uri("/api/tags/1")
-                    .header("Content-Type", "application/json")
-                    .body(Body::from(serde_json::to_string(&update_tag).unwrap()))
-                    .unwrap(),
-            )
-            .await
-            .unwrap();
-        assert_eq!(response.status(), StatusCode::OK);
-
-        // 测试删除标签
-        let response = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .method("DELETE")
-                    .uri("/api/tags/1")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
-            .await
-            .unwrap();
-        assert_eq!(response.status(), StatusCode::OK);
+        self.send(request).await
+    }
+
+    async fn send(&self, request: Request<Body>) -> (StatusCode, Value) {
+        let response = self.router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let value = serde_json::from_slice(&bytes)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).into_owned()));
+        (status, value)
+    }
+
+    async fn get(&self, uri: &str) -> (StatusCode, Value) {
+        self.request(&self.admin_key, Method::GET, uri, None).await
+    }
+
+    async fn post(&self, uri: &str, body: Value) -> (StatusCode, Value) {
+        self.request(&self.admin_key, Method::POST, uri, Some(body)).await
+    }
+
+    async fn put(&self, uri: &str, body: Value) -> (StatusCode, Value) {
+        self.request(&self.admin_key, Method::PUT, uri, Some(body)).await
+    }
+
+    async fn delete(&self, uri: &str) -> (StatusCode, Value) {
+        self.request(&self.admin_key, Method::DELETE, uri, None).await
+    }
+
+    /// 创建收藏，返回收藏ID
+    async fn create_favorite(&self, text: &str, url: &str) -> i64 {
+        let (status, favorite) = self
+            .post("/api/favorites", json!({ "text": text, "url": url, "tags": [] }))
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", favorite);
+        favorite["id"].as_i64().unwrap()
     }
 
-    // 收藏相关测试
-    #[tokio::test]
-    async fn test_favorite_crud() {
-        let pool = init_test();
-        let app = Router::new()
-            .route("/api/favorites", get(favorite::list_favorites))
-            .route("/api/favorites", post(favorite::create_favorite))
-            .route("/api/favorites/:id", put(favorite::update_favorite))
-            .route("/api/favorites/:id", delete(favorite::delete_favorite))
-            .with_state(pool);
-
-        // 测试获取收藏列表
-        let response = app
-            .clone()
-            .oneshot(Request::builder().uri("/api/favorites").body(Body::empty()).unwrap())
+    /// 创建分类，返回分类ID
+    async fn create_category(&self, name: &str, parent_id: Option<i64>) -> i64 {
+        let (status, body) = self
+            .post("/api/categories", json!({ "name": name, "parent_id": parent_id }))
+            .await;
+        assert_eq!(status, StatusCode::CREATED, "{}", body);
+        sqlx::query_scalar("SELECT id FROM categories WHERE name = ? AND parent_id IS ?")
+            .bind(name)
+            .bind(parent_id)
+            .fetch_one(&self.db)
             .await
-            .unwrap();
-        assert_eq!(response.status(), StatusCode::OK);
-        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
-        let favorites: favorite::FavoriteResponse = serde_json::from_slice(&body).unwrap();
-        assert!(!favorites.items.is_empty());
-
-        // 测试创建收藏
-        let new_favorite = json!({
-            "category_id": 1,
+            .unwrap()
+    }
+
+    /// 为 `user_name` 签发 API Key，用户不存在时创建
+    async fn issue_key(&self, user_name: &str) -> String {
+        let (status, issued) = self.post("/api/admin/keys", json!({ "user_name": user_name })).await;
+        assert_eq!(status, StatusCode::CREATED, "{}", issued);
+        issued["key"].as_str().unwrap().to_string()
+    }
+}
+
+/// 在本机随机端口启动一个代替原网页的测试网站，返回其地址
+pub(crate) async fn serve(router: Router) -> SocketAddr {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    addr
+}
+
+/// 列表中名称为 `name` 的记录的ID
+fn id_by_name(list: &Value, name: &str) -> i64 {
+    list.as_array()
+        .unwrap()
+        .iter()
+        .find(|item| item["name"] == name)
+        .unwrap_or_else(|| panic!("{} 不在列表中: {}", name, list))["id"]
+        .as_i64()
+        .unwrap()
+}
+
+/// 收藏列表中的收藏ID
+fn item_ids(list: &Value) -> Vec<i64> {
+    list["items"].as_array().unwrap().iter().map(|item| item["id"].as_i64().unwrap()).collect()
+}
+
+// 分类相关测试
+#[tokio::test]
+async fn test_category_crud() {
+    let app = init_test().await;
+
+    let (status, categories) = app.get("/api/categories").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(categories, json!([]));
+
+    let (status, _) = app.post("/api/categories", json!({ "name": "New Category" })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _) = app.post("/api/categories", json!({ "name": "New Category" })).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (_, categories) = app.get("/api/categories").await;
+    let id = id_by_name(&categories, "New Category");
+    let uri = format!("/api/categories/{}", id);
+    let (status, category) = app.get(&uri).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(category["parent_id"].is_null());
+
+    let (status, _) = app
+        .put(&uri, json!({ "id": id, "name": "Updated Category", "parent_id": null }))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, category) = app.get(&uri).await;
+    assert_eq!(category["name"], "Updated Category");
+
+    let (status, _) = app.request(&app.admin_key, Method::DELETE, &uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app.get(&uri).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+// 标签相关测试
+#[tokio::test]
+async fn test_tag_crud() {
+    let app = init_test().await;
+
+    let (status, _) = app.post("/api/tags", json!({ "name": "New Tag" })).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, tags) = app.get("/api/tags").await;
+    assert_eq!(status, StatusCode::OK);
+    let id = id_by_name(&tags, "New Tag");
+    let uri = format!("/api/tags/{}", id);
+
+    let (status, _) = app.put(&uri, json!({ "name": "Updated Tag" })).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, tag) = app.get(&uri).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(tag["name"], "Updated Tag");
+
+    // 标签下的收藏
+    let (status, _) = app
+        .post("/api/favorites", json!({ "text": "tagged", "url": "http://example.com", "tags": ["Updated Tag"] }))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, favorites) = app.get("/api/tags/Updated%20Tag/favorites").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(favorites.as_array().unwrap().len(), 1);
+
+    // 删除标签时一并移除收藏上的关联
+    let (status, _) = app.request(&app.admin_key, Method::DELETE, &uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app.get(&uri).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let links: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM favorite_tags WHERE tag_id = ?")
+        .bind(id)
+        .fetch_one(&app.db)
+        .await
+        .unwrap();
+    assert_eq!(links, 0);
+}
+
+// 收藏相关测试
+#[tokio::test]
+async fn test_favorite_crud() {
+    let app = init_test().await;
+    app.post("/api/categories", json!({ "name": "Test Category" })).await;
+    let (_, categories) = app.get("/api/categories").await;
+    let category_id = id_by_name(&categories, "Test Category");
+
+    let (status, favorite) = app
+        .post("/api/favorites", json!({
+            "category_id": category_id,
             "text": "New Favorite",
             "url": "http://example.com",
             "tags": ["Test Tag"]
-        });
-        let response = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .method("POST")
-                    .uri("/api/favorites")
-                    .header("Content-Type", "application/json")
-                    .body(Body::from(serde_json::to_string(&new_favorite).unwrap()))
-                    .unwrap(),
-            )
-            .await
-            .unwrap();
-        assert_eq!(response.status(), StatusCode::OK);
+        }))
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", favorite);
+    assert_eq!(favorite["text"], "New Favorite");
+    assert_eq!(favorite["category_id"], category_id);
+    assert_eq!(favorite["tags"], r#"["Test Tag"]"#);
+    let id = favorite["id"].as_i64().unwrap();
+    let uri = format!("/api/favorites/{}", id);
+
+    let (status, list) = app.get("/api/favorites").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(item_ids(&list), vec![id]);
 
-        // 测试更新收藏
-        let update_favorite = json!({
-            "category_id": 1,
+    let (status, _) = app
+        .put(&uri, json!({
+            "category_id": null,
             "text": "Updated Favorite",
-            "url": "http://example.com",
+            "url": "http://example.com/updated",
+            "tags": ["Other Tag"]
+        }))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, favorite) = app.get(&uri).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(favorite["text"], "Updated Favorite");
+    assert_eq!(favorite["url"], "http://example.com/updated");
+    assert!(favorite["category_id"].is_null());
+    assert_eq!(favorite["tags"], r#"["Other Tag"]"#);
+
+    let (status, _) = app.request(&app.admin_key, Method::DELETE, &uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app.get(&uri).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (_, list) = app.get("/api/favorites").await;
+    assert!(item_ids(&list).is_empty());
+}
+
+// 搜索和过滤测试
+#[tokio::test]
+async fn test_search_and_filter() {
+    let app = init_test().await;
+    app.post("/api/categories", json!({ "name": "Test Category" })).await;
+    let (_, categories) = app.get("/api/categories").await;
+    let category_id = id_by_name(&categories, "Test Category");
+
+    let (_, tagged) = app
+        .post("/api/favorites", json!({
+            "category_id": category_id,
+            "text": "Test Content",
+            "url": "http://test.com",
             "tags": ["Test Tag"]
-        });
-        let response = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .method("PUT")
-                    .uri("/api/favorites/1")
-                    .header("Content-Type", "application/json")
-                    .body(Body::from(serde_json::to_string(&update_favorite).unwrap()))
-                    .unwrap(),
-            )
-            .await
-            .unwrap();
-        assert_eq!(response.status(), StatusCode::OK);
-
-        // 测试删除收藏
-        let response = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .method("DELETE")
-                    .uri("/api/favorites/1")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
-            .await
-            .unwrap();
-        assert_eq!(response.status(), StatusCode::OK);
+        }))
+        .await;
+    let tagged = tagged["id"].as_i64().unwrap();
+    let other = app.create_favorite("Other Content", "http://other.com").await;
+    let (_, tags) = app.get("/api/tags").await;
+    let tag_id = id_by_name(&tags, "Test Tag");
+
+    let (status, result) = app.get("/api/favorites?search=Test").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(item_ids(&result), vec![tagged]);
+
+    let (_, result) = app.get(&format!("/api/favorites?category_id={}", category_id)).await;
+    assert_eq!(item_ids(&result), vec![tagged]);
+
+    let (_, result) = app.get(&format!("/api/favorites?tag_id={}", tag_id)).await;
+    assert_eq!(item_ids(&result), vec![tagged]);
+
+    let (status, result) = app.get("/api/favorites?page=1&per_page=1").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(result["items"].as_array().unwrap().len(), 1);
+    assert_eq!(result["total"], 2);
+    let (_, result) = app.get("/api/favorites?page=2&per_page=1").await;
+    let mut ids = item_ids(&result);
+    ids.extend(item_ids(&app.get("/api/favorites?page=1&per_page=1").await.1));
+    ids.sort_unstable();
+    assert_eq!(ids, vec![tagged, other]);
+}
+
+// 网页快照相关测试
+#[tokio::test]
+async fn test_snapshot() {
+    use crate::archiver;
+    use crate::config::ArchiverConfig;
+    use crate::outbound;
+    use axum::routing::get;
+    use std::time::Duration;
+
+    let page = "<html><body><nav>菜单</nav><p>快照正文</p></body></html>";
+    let site = Router::new()
+        .route("/article", get(move || async move { ([(header::CONTENT_TYPE, "text/html")], page) }))
+        .route("/missing", get(|| async { StatusCode::NOT_FOUND }));
+    let addr = serve(site).await;
+
+    let app = init_test().await;
+    let article = app.create_favorite("快照正文", &format!("http://{}/article", addr)).await;
+    let missing = app.create_favorite("不存在", &format!("http://{}/missing", addr)).await;
+    let unreachable = app.create_favorite("无法连接", "http://127.0.0.1:1/").await;
+
+    // 尚未抓取
+    let (status, _) = app.get(&format!("/api/favorites/{}/snapshot", article)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let config = ArchiverConfig::default();
+    let client = outbound::Client::new(Duration::from_secs(5), "test", true).unwrap();
+    assert_eq!(archiver::archive_pending(&app.db, &client, &config).await.unwrap(), 3);
+    // 已抓取的不再重复抓取
+    assert_eq!(archiver::archive_pending(&app.db, &client, &config).await.unwrap(), 0);
+
+    let (status, snapshot) = app.get(&format!("/api/favorites/{}/snapshot", article)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(snapshot["status"], 200);
+    assert_eq!(snapshot["text"], "快照正文");
+    assert_eq!(snapshot["size"], page.len());
+    assert_eq!(snapshot["error"], Value::Null);
+
+    let (status, raw) = app.get(&format!("/api/favorites/{}/snapshot?format=raw", article)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(raw, page);
+
+    // 错误状态码照常保存
+    let (status, snapshot) = app.get(&format!("/api/favorites/{}/snapshot", missing)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(snapshot["status"], 404);
+
+    // 请求失败时只记录原因，没有原网页内容
+    let (status, snapshot) = app.get(&format!("/api/favorites/{}/snapshot", unreachable)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(snapshot["status"], Value::Null);
+    assert!(snapshot["error"].as_str().is_some_and(|error| !error.is_empty()));
+    let (status, _) = app.get(&format!("/api/favorites/{}/snapshot?format=raw", unreachable)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+// 失效链接相关测试
+#[tokio::test]
+async fn test_broken_links() {
+    use crate::config::LinkCheckConfig;
+    use crate::{link_checker, outbound};
+    use axum::{response::Redirect, routing::get};
+    use std::time::Duration;
+
+    let site = Router::new()
+        .route("/ok", get(|| async { "ok" }))
+        .route("/gone", get(|| async { StatusCode::GONE }))
+        .route("/old", get(|| async { Redirect::permanent("/new") }))
+        .route("/new", get(|| async { "new" }))
+        // 只是规范化后相同的重定向不算迁移
+        .route("/same", get(|| async { Redirect::permanent("/same/") }))
+        .route("/same/", get(|| async { "same" }));
+    let addr = serve(site).await;
+    let url = |path: &str| format!("http://{}{}", addr, path);
+
+    let app = init_test().await;
+    app.create_favorite("正常", &url("/ok")).await;
+    let gone = app.create_favorite("失效", &url("/gone")).await;
+    let moved = app.create_favorite("迁移", &url("/old")).await;
+    app.create_favorite("尾部斜杠", &url("/same")).await;
+    let unreachable = app.create_favorite("无法连接", "http://127.0.0.1:1/").await;
+
+    let config = LinkCheckConfig { per_host_delay_ms: 0, ..LinkCheckConfig::default() };
+    let client = outbound::Client::new(Duration::from_secs(5), "test", true).unwrap();
+    assert_eq!(link_checker::check_all(&app.db, &client, &config).await.unwrap(), 5);
+
+    let ids = |links: &Value| -> Vec<i64> {
+        let mut ids: Vec<i64> = links["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|link| link["favorite"]["id"].as_i64().unwrap())
+            .collect();
+        ids.sort_unstable();
+        ids
+    };
+
+    let (status, links) = app.get("/api/links/broken").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(links["total"], 3);
+    assert_eq!(ids(&links), vec![gone, moved, unreachable]);
+
+    let (_, links) = app.get("/api/links/broken?state=gone").await;
+    assert_eq!(ids(&links), vec![gone, unreachable]);
+    let link = links["items"].as_array().unwrap().iter().find(|link| link["favorite"]["id"] == gone).unwrap();
+    assert_eq!((&link["state"], &link["last_status"]), (&json!("gone"), &json!(410)));
+    let link = links["items"].as_array().unwrap().iter().find(|link| link["favorite"]["id"] == unreachable).unwrap();
+    assert_eq!(link["last_status"], Value::Null);
+    assert!(link["error"].is_string());
+
+    let (_, links) = app.get("/api/links/broken?state=moved").await;
+    assert_eq!(ids(&links), vec![moved]);
+    assert_eq!(links["items"][0]["state"], "moved");
+    assert_eq!(links["items"][0]["final_url"], url("/new"));
+
+    // 移入回收站的收藏不再列出
+    let (status, _) = app.request(&app.admin_key, Method::DELETE, &format!("/api/favorites/{}", gone), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, links) = app.get("/api/links/broken?state=gone").await;
+    assert_eq!(ids(&links), vec![unreachable]);
+}
+
+// 事件流相关测试
+#[tokio::test]
+async fn test_event_stream_token() {
+    use futures_util::StreamExt;
+    use std::time::Duration;
+
+    let app = init_test().await;
+    let open = |uri: String| {
+        let router = app.router.clone();
+        async move {
+            router
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap()
+        }
+    };
+
+    // 没有 API Key 也没有凭证
+    assert_eq!(open("/api/events".to_string()).await.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(open("/api/events?token=invalid".to_string()).await.status(), StatusCode::UNAUTHORIZED);
+
+    let (status, token) = app.post("/api/events/token", json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(token["expires_in"], 60);
+    let token = token["token"].as_str().unwrap();
+
+    let response = open(format!("/api/events?token={}", token)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "text/event-stream");
+    let mut stream = response.into_body().into_data_stream();
+
+    let id = app.create_favorite("事件", "https://example.com/event").await;
+    let chunk = tokio::time::timeout(Duration::from_secs(5), stream.next()).await.unwrap().unwrap().unwrap();
+    let chunk = String::from_utf8_lossy(&chunk);
+    assert!(chunk.contains("event: favorite.created"), "{}", chunk);
+    assert!(chunk.contains(&format!("data: {{\"id\":{}}}", id)), "{}", chunk);
+
+    // 凭证可重复使用；无法补发时先收到 reset
+    let response = open(format!("/api/events?token={}&last_event_id=1", token)).await;
+    let mut stream = response.into_body().into_data_stream();
+    let chunk = tokio::time::timeout(Duration::from_secs(5), stream.next()).await.unwrap().unwrap().unwrap();
+    assert!(String::from_utf8_lossy(&chunk).contains("event: reset"));
+}
+#[tokio::test]
+async fn test_implicit_creation_events() {
+    let app = init_test().await;
+    let (_, mut receiver) = app.events.subscribe(1, None);
+    let mut names = || {
+        let mut names = Vec::new();
+        while let Ok(event) = receiver.try_recv() {
+            names.push(event.name());
+        }
+        names
+    };
+
+    // 新标签随收藏一起创建，已有的标签不再发布
+    let (status, _) = app
+        .post("/api/favorites", json!({ "text": "标签", "url": "https://example.com/a", "tags": ["标签", "新标签"] }))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(names(), vec!["tag.created", "tag.created", "favorite.created"]);
+
+    let (status, _) = app
+        .post("/api/favorites", json!({ "text": "已有标签", "url": "https://example.com/b", "tags": ["新标签"] }))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(names(), vec!["favorite.created"]);
+
+    // 导入时自动创建的分类
+    let (status, result) = app
+        .post("/api/import", json!([{ "text": "导入", "url": "https://example.com/c", "category": "阅读", "tags": ["导入"] }]))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(result["created"], 1, "{}", result);
+    assert_eq!(names(), vec!["tag.created", "category.created", "favorite.created"]);
+
+    // 试运行不发布事件
+    let (status, _) = app
+        .post("/api/import?dry_run=true", json!([{ "text": "试运行", "url": "https://example.com/d", "category": "新分类" }]))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(names().is_empty());
+}
+
+// 认证与数据隔离相关测试
+#[tokio::test]
+async fn test_auth_isolation() {
+    let app = init_test().await;
+
+    let (status, _) = app.request("", Method::GET, "/api/favorites", None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = app.request("invalid", Method::GET, "/api/favorites", None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let bob = app.issue_key("bob").await;
+    let (status, _) = app.request(&bob, Method::GET, "/api/admin/keys", None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // 其他用户的收藏视为不存在
+    let id = app.create_favorite("管理员的收藏", "https://example.com/admin").await;
+    let uri = format!("/api/favorites/{}", id);
+    for method in [Method::GET, Method::DELETE] {
+        let (status, _) = app.request(&bob, method, &uri, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
     }
+    let (status, _) = app
+        .request(&bob, Method::PATCH, &uri, Some(json!({ "text": "篡改" })))
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (_, list) = app.request(&bob, Method::GET, "/api/favorites", None).await;
+    assert_eq!(list["items"], json!([]));
+    let search = "/api/search?q=%E7%AE%A1%E7%90%86%E5%91%98";
+    let (_, found) = app.get(search).await;
+    assert_eq!(found["total"], 1);
+    let (_, found) = app.request(&bob, Method::GET, search, None).await;
+    assert_eq!(found["total"], 0);
 
-    // 测试搜索和过滤功能
-    #[tokio::test]
-    async fn test_search_and_filter() {
-        let pool = init_test();
-        let app = Router::new()
-            .route("/api/favorites", get(favorite::list_favorites))
-            .with_state(pool);
-
-        // 测试搜索
-        let response = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .uri("/api/favorites?search=Test")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
-            .await
-            .unwrap();
-        assert_eq!(response.status(), StatusCode::OK);
-        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
-        let result: favorite::FavoriteResponse = serde_json::from_slice(&body).unwrap();
-        assert!(!result.items.is_empty());
-
-        // 测试分类过滤
-        let response = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .uri("/api/favorites?category_id=1")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
-            .await
-            .unwrap();
-        assert_eq!(response.status(), StatusCode::OK);
-
-        // 测试标签过滤
-        let response = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .uri("/api/favorites?tag_id=1")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
+    // 不能使用其他用户的分类，同名标签互不影响
+    let category = app.create_category("管理员的分类", None).await;
+    let (status, _) = app
+        .request(&bob, Method::POST, "/api/favorites", Some(json!({
+            "text": "bob", "url": "https://example.com/bob", "category_id": category, "tags": []
+        })))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = app
+        .request(&bob, Method::POST, "/api/favorites", Some(json!({
+            "text": "bob", "url": "https://example.com/admin", "tags": ["共用"]
+        })))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    app.post("/api/favorites", json!({ "text": "admin", "url": "https://example.com/x", "tags": ["共用"] }))
+        .await;
+    let (_, tags) = app.request(&bob, Method::GET, "/api/tags", None).await;
+    assert_eq!(tags.as_array().unwrap().len(), 1);
+
+    // 相同内容在不同用户之间不算重复
+    let (status, _) = app
+        .request(&bob, Method::POST, "/api/favorites", Some(json!({
+            "text": "管理员的收藏", "url": "https://example.com/admin", "tags": []
+        })))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // 吊销后不能再访问
+    let (_, keys) = app.get("/api/admin/keys").await;
+    let key_id = keys
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|key| key["user_name"] == "bob")
+        .unwrap()["id"]
+        .as_i64()
+        .unwrap();
+    let (status, _) = app
+        .request(&app.admin_key, Method::DELETE, &format!("/api/admin/keys/{}", key_id), None)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app.request(&bob, Method::GET, "/api/favorites", None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+// 回收站相关测试
+#[tokio::test]
+async fn test_trash() {
+    let app = init_test().await;
+    let trashed = app.create_favorite("移入回收站", "https://example.com/trash").await;
+    let kept = app.create_favorite("保留", "https://example.com/keep").await;
+    let uri = format!("/api/favorites/{}", trashed);
+
+    let (status, _) = app.request(&app.admin_key, Method::DELETE, &uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app.get(&uri).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = app.request(&app.admin_key, Method::DELETE, &uri, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (_, list) = app.get("/api/favorites").await;
+    assert_eq!(list["items"].as_array().unwrap().len(), 1);
+    assert_eq!(list["items"][0]["id"], kept);
+    let (_, trash) = app.get("/api/trash").await;
+    assert_eq!(trash["total"], 1);
+    assert_eq!(trash["items"][0]["id"], trashed);
+    assert!(trash["items"][0]["deleted_at"].is_string());
+
+    // 其他用户不能恢复
+    let bob = app.issue_key("bob").await;
+    let restore = format!("/api/trash/{}/restore", trashed);
+    let (status, _) = app.request(&bob, Method::POST, &restore, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, favorite) = app.post(&restore, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(favorite["id"], trashed);
+    assert!(favorite["deleted_at"].is_null());
+    let (status, _) = app.post(&restore, json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (_, trash) = app.get("/api/trash").await;
+    assert_eq!(trash["total"], 0);
+    let (status, _) = app.get(&uri).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+// 查重与合并相关测试
+#[tokio::test]
+async fn test_duplicates_and_merge() {
+    let app = init_test().await;
+    let payload = |text: &str, tags: Value| json!({ "text": text, "url": "https://example.com/a", "tags": tags });
+
+    let (status, first) = app.post("/api/favorites", payload("Hello, World", json!(["x"]))).await;
+    assert_eq!(status, StatusCode::OK);
+    let first = first["id"].as_i64().unwrap();
+
+    // 只有格式差异时返回已有记录
+    let (status, existing) = app.post("/api/favorites", payload("  hello world！", json!([]))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(existing["id"], first);
+
+    let mut duplicate = payload("hello world", json!(["y"]));
+    duplicate["allow_duplicate"] = json!(true);
+    let (status, second) = app.post("/api/favorites", duplicate).await;
+    assert_eq!(status, StatusCode::OK);
+    let second = second["id"].as_i64().unwrap();
+    let (status, _) = app
+        .post(&format!("/api/favorites/{}/notes", second), json!({ "body": "笔记" }))
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    // 无效的请求
+    let (status, _) = app.post("/api/favorites/merge", json!({ "ids": [first] })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let (status, _) = app.post("/api/favorites/merge", json!({ "ids": [first, first] })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = app
+        .post("/api/favorites/merge", json!({ "ids": [first, second], "target_id": 999 }))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = app.post("/api/favorites/merge", json!({ "ids": [first, 999] })).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // 默认保留最早的收藏，标签取并集，笔记随之移动
+    let (status, merged) = app.post("/api/favorites/merge", json!({ "ids": [second, first] })).await;
+    assert_eq!(status, StatusCode::OK, "{}", merged);
+    assert_eq!(merged["id"], first);
+    let mut tags: Vec<String> = serde_json::from_str(merged["tags"].as_str().unwrap()).unwrap();
+    tags.sort_unstable();
+    assert_eq!(tags, vec!["x", "y"]);
+    let (_, notes) = app.get(&format!("/api/favorites/{}/notes", first)).await;
+    assert_eq!(notes.as_array().unwrap().len(), 1);
+
+    // 其余的收藏移入回收站
+    let (status, _) = app.get(&format!("/api/favorites/{}", second)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (_, trash) = app.get("/api/trash").await;
+    assert_eq!(trash["items"][0]["id"], second);
+}
+
+// 删除分类相关测试
+#[tokio::test]
+async fn test_delete_category_strategies() {
+    let app = init_test().await;
+    let favorite_in = |category_id: i64, url: &'static str| {
+        let app = &app;
+        async move {
+            let (status, favorite) = app
+                .post("/api/favorites", json!({ "text": url, "url": url, "category_id": category_id, "tags": [] }))
+                .await;
+            assert_eq!(status, StatusCode::OK, "{}", favorite);
+            favorite["id"].as_i64().unwrap()
+        }
+    };
+    let category_of = |id: i64| {
+        let db = app.db.clone();
+        async move {
+            sqlx::query_scalar::<_, Option<i64>>("SELECT category_id FROM favorites WHERE id = ?")
+                .bind(id)
+                .fetch_one(&db)
+                .await
+                .unwrap()
+        }
+    };
+
+    let reading = app.create_category("阅读", None).await;
+    let tech = app.create_category("技术", Some(reading)).await;
+    let other = app.create_category("其他", None).await;
+    let f1 = favorite_in(reading, "https://example.com/1").await;
+    let f2 = favorite_in(tech, "https://example.com/2").await;
+    let trashed = favorite_in(reading, "https://example.com/3").await;
+    app.delete(&format!("/api/favorites/{}", trashed)).await;
+
+    // 非空分类需要指定处理方式
+    let (status, _) = app.delete(&format!("/api/categories/{}", reading)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let invalid_targets = [
+        "strategy=reassign".to_string(),
+        format!("strategy=reassign&target={}", reading),
+        "strategy=reassign&target=999".to_string(),
+    ];
+    for query in invalid_targets {
+        let (status, _) = app.delete(&format!("/api/categories/{}?{}", reading, query)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", query);
+    }
+    let (status, body) = app.delete(&format!("/api/categories/{}?strategy=merge", reading)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["code"], "VALIDATION_ERROR");
+    assert!(body["details"]["strategy"].is_string(), "{}", body);
+
+    // 收藏移到目标分类，回收站中的收藏一并移动但不计数，子分类移到上一级
+    let (status, result) = app.delete(&format!("/api/categories/{}?strategy=reassign&target={}", reading, other)).await;
+    assert_eq!(status, StatusCode::OK, "{}", result);
+    assert_eq!(result["favorites_reassigned"], 1);
+    assert_eq!(result["categories_deleted"], 1);
+    assert_eq!(category_of(f1).await, Some(other));
+    assert_eq!(category_of(trashed).await, Some(other));
+    let (_, category) = app.get(&format!("/api/categories/{}", tech)).await;
+    assert!(category["parent_id"].is_null());
+
+    let (status, result) = app.delete(&format!("/api/categories/{}?strategy=uncategorize", other)).await;
+    assert_eq!(status, StatusCode::OK, "{}", result);
+    assert_eq!(result["favorites_uncategorized"], 1);
+    assert_eq!(category_of(f1).await, None);
+    assert_eq!(category_of(trashed).await, None);
+
+    // 连同子分类一起删除，其中的收藏移入回收站并改为未分类
+    let nested = app.create_category("Rust", Some(tech)).await;
+    let f4 = favorite_in(nested, "https://example.com/4").await;
+    let (status, result) = app.delete(&format!("/api/categories/{}?strategy=cascade", tech)).await;
+    assert_eq!(status, StatusCode::OK, "{}", result);
+    assert_eq!(result["categories_deleted"], 2);
+    assert_eq!(result["favorites_deleted"], 2);
+    let (_, trash) = app.get("/api/trash").await;
+    assert_eq!(trash["total"], 3);
+    assert_eq!((category_of(f2).await, category_of(f4).await), (None, None));
+    let (_, categories) = app.get("/api/categories").await;
+    assert_eq!(categories, json!([]));
+
+    // 子分类移到上一级时不能与已有分类重名
+    let parent = app.create_category("父", None).await;
+    app.create_category("同名", None).await;
+    app.create_category("同名", Some(parent)).await;
+    let (status, _) = app.delete(&format!("/api/categories/{}?strategy=uncategorize", parent)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+}
+
+/// 沿 next_cursor 翻到最后一页，再沿 prev_cursor 翻回第一页，返回两次依次得到的收藏ID
+async fn walk(app: &TestApp, query: &str) -> (Vec<i64>, Vec<i64>) {
+    let mut forward = Vec::new();
+    let (status, mut page) = app.get(&format!("/api/favorites?{}", query)).await;
+    assert_eq!(status, StatusCode::OK, "{}", page);
+    assert!(page["prev_cursor"].is_null(), "第一页不应有上一页: {}", query);
+    loop {
+        forward.extend(item_ids(&page));
+        let Some(next) = page["next_cursor"].as_str().map(str::to_string) else {
+            break;
+        };
+        page = app.get(&format!("/api/favorites?{}&after={}", query, next)).await.1;
+        assert!(page["prev_cursor"].is_string(), "{}", query);
+        assert!(page.get("total").is_none(), "游标分页默认不统计总数");
+    }
+
+    let mut backward = item_ids(&page);
+    while let Some(prev) = page["prev_cursor"].as_str().map(str::to_string) {
+        page = app.get(&format!("/api/favorites?{}&before={}", query, prev)).await.1;
+        assert!(page["next_cursor"].is_string(), "{}", query);
+        let mut ids = item_ids(&page);
+        ids.extend(backward);
+        backward = ids;
+    }
+    (forward, backward)
+}
+
+// 收藏列表分页相关测试
+#[tokio::test]
+async fn test_cursor_pagination() {
+    let app = init_test().await;
+    let a = app.create_category("A", None).await;
+    let b = app.create_category("B", None).await;
+
+    // 创建时间和分类有重复，排序时按ID区分
+    let rows = [
+        ("2024-01-01T00:00:00Z", "2024-02-07T00:00:00Z", "https://example.com/e", Some(b)),
+        ("2024-01-01T00:00:00Z", "2024-02-06T00:00:00Z", "https://example.com/a", None),
+        ("2024-01-01T00:00:00Z", "2024-02-05T00:00:00Z", "https://example.com/c", Some(a)),
+        ("2024-01-02T00:00:00Z", "2024-02-04T00:00:00Z", "https://example.com/a", Some(b)),
+        ("2024-01-02T00:00:00Z", "2024-02-03T00:00:00Z", "https://example.com/d", None),
+        ("2024-01-03T00:00:00Z", "2024-02-02T00:00:00Z", "https://example.com/b", Some(a)),
+        ("2024-01-03T00:00:00Z", "2024-02-01T00:00:00Z", "https://example.com/f", None),
+    ];
+    let mut favorites = Vec::new();
+    for (i, (created_at, updated_at, url, category_id)) in rows.iter().enumerate() {
+        let (status, favorite) = app
+            .post("/api/favorites", json!({ "text": format!("收藏 {}", i), "url": url, "category_id": category_id, "tags": [] }))
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", favorite);
+        let id = favorite["id"].as_i64().unwrap();
+        sqlx::query("UPDATE favorites SET created_at = ?, updated_at = ? WHERE id = ?")
+            .bind(created_at)
+            .bind(updated_at)
+            .bind(id)
+            .execute(&app.db)
             .await
             .unwrap();
-        assert_eq!(response.status(), StatusCode::OK);
-
-        // 测试分页
-        let response = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .uri("/api/favorites?page=1&per_page=5")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
+        let category = match category_id {
+            Some(id) if *id == a => "A",
+            Some(_) => "B",
+            None => "未分类",
+        };
+        favorites.push((id, [*created_at, *updated_at, *url, category]));
+    }
+
+    for (column, sort) in ["created_at", "updated_at", "url", "category"].into_iter().enumerate() {
+        for order in ["asc", "desc"] {
+            let mut expected: Vec<(&str, i64)> = favorites.iter().map(|(id, keys)| (keys[column], *id)).collect();
+            expected.sort_unstable();
+            if order == "desc" {
+                expected.reverse();
+            }
+            let expected: Vec<i64> = expected.into_iter().map(|(_, id)| id).collect();
+
+            for per_page in [1, 2, 3, 7] {
+                let query = format!("sort={}&order={}&per_page={}", sort, order, per_page);
+                let (forward, backward) = walk(&app, &query).await;
+                assert_eq!(forward, expected, "{}", query);
+                assert_eq!(backward, expected, "{}", query);
+            }
+
+            // 按页码分页的结果一致
+            let mut paged = Vec::new();
+            for page in 1..=3 {
+                let (_, result) = app
+                    .get(&format!("/api/favorites?sort={}&order={}&per_page=3&page={}", sort, order, page))
+                    .await;
+                assert_eq!(result["total"], 7);
+                paged.extend(item_ids(&result));
+            }
+            assert_eq!(paged, expected, "sort={} order={}", sort, order);
+        }
+    }
+
+    // 默认按创建时间降序
+    let (_, first) = app.get("/api/favorites?per_page=2").await;
+    let (_, explicit) = app.get("/api/favorites?per_page=2&sort=created_at&order=desc").await;
+    assert_eq!(item_ids(&first), item_ids(&explicit));
+
+    // 游标分页可要求统计总数
+    let next = first["next_cursor"].as_str().unwrap();
+    let (_, page) = app.get(&format!("/api/favorites?per_page=2&after={}&include_total=true", next)).await;
+    assert_eq!(page["total"], 7);
+
+    // 游标与排序字段不符或格式错误时报错，不返回错误的结果
+    for query in [format!("sort=url&after={}", next), "after=zz".to_string(), "after=abc".to_string()] {
+        let (status, body) = app.get(&format!("/api/favorites?{}", query)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", query);
+        assert!(body["details"]["after"].is_string(), "{}", body);
+    }
+    let (status, body) = app.get(&format!("/api/favorites?sort=url&before={}", next)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body["details"]["before"].is_string(), "{}", body);
+    let (status, _) = app.get("/api/favorites?sort=title").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+/// 收藏的标签名称，按名称排序
+fn tag_names(favorite: &Value) -> Vec<String> {
+    let mut tags: Vec<String> = serde_json::from_str(favorite["tags"].as_str().unwrap()).unwrap();
+    tags.sort_unstable();
+    tags
+}
+
+// 部分更新收藏相关测试
+#[tokio::test]
+async fn test_patch_favorite() {
+    let app = init_test().await;
+    let category = app.create_category("A", None).await;
+    let (_, favorite) = app
+        .post("/api/favorites", json!({
+            "text": "原文本",
+            "url": "https://example.com/a",
+            "category_id": category,
+            "tags": ["a", "b"],
+            "title": "标题",
+            "context_before": "前文"
+        }))
+        .await;
+    let id = favorite["id"].as_i64().unwrap();
+    let uri = format!("/api/favorites/{}", id);
+    let patch = |body: Value| app.request(&app.admin_key, Method::PATCH, &uri, Some(body));
+
+    // 未提供的字段保持不变
+    let (status, patched) = patch(json!({ "text": "新文本" })).await;
+    assert_eq!(status, StatusCode::OK, "{}", patched);
+    assert_eq!(patched["text"], "新文本");
+    assert_eq!(patched["url"], "https://example.com/a");
+    assert_eq!(patched["category_id"], category);
+    assert_eq!(patched["title"], "标题");
+    assert_eq!(patched["context_before"], "前文");
+    assert_eq!(tag_names(&patched), vec!["a", "b"]);
+    let (_, empty) = patch(json!({})).await;
+    assert_eq!(empty["category_id"], category);
+    assert_eq!(empty["text"], "新文本");
+
+    // 显式的 null 清除分类，空字符串清空可选文本
+    let (_, patched) = patch(json!({ "category_id": null, "title": "" })).await;
+    assert!(patched["category_id"].is_null());
+    assert!(patched["title"].is_null());
+    assert_eq!(patched["context_before"], "前文");
+    let (_, patched) = patch(json!({ "category_id": category })).await;
+    assert_eq!(patched["category_id"], category);
+
+    // 追加和移除只影响指定的标签，整体替换先于追加
+    let (_, patched) = patch(json!({ "add_tags": ["c"], "remove_tags": ["a"] })).await;
+    assert_eq!(tag_names(&patched), vec!["b", "c"]);
+    let (_, patched) = patch(json!({ "tags": ["x"], "add_tags": ["y"] })).await;
+    assert_eq!(tag_names(&patched), vec!["x", "y"]);
+
+    // 修改网址后重新生成域名和链接
+    let (_, patched) = patch(json!({ "url": "https://other.org/p" })).await;
+    assert_eq!(patched["domain"], "other.org");
+    assert!(patched["deep_link"].as_str().unwrap().starts_with("https://other.org/p#:~:text="));
+
+    // 其他用户的分类不可用
+    let bob = app.issue_key("bob").await;
+    let (_, categories) = app.request(&bob, Method::GET, "/api/categories", None).await;
+    assert_eq!(categories, json!([]));
+    app.request(&bob, Method::POST, "/api/categories", Some(json!({ "name": "bob" }))).await;
+    let bob_category: i64 = sqlx::query_scalar("SELECT id FROM categories WHERE name = 'bob'")
+        .fetch_one(&app.db)
+        .await
+        .unwrap();
+    let (status, _) = patch(json!({ "category_id": bob_category })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // 其他用户、回收站中和不存在的收藏
+    let (status, _) = app
+        .request(&bob, Method::PATCH, &uri, Some(json!({ "text": "篡改" })))
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = app
+        .request(&app.admin_key, Method::PATCH, "/api/favorites/999", Some(json!({ "text": "x" })))
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    app.delete(&uri).await;
+    let (status, _) = patch(json!({ "text": "回收站" })).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (_, trash) = app.get("/api/trash").await;
+    assert_eq!(trash["items"][0]["text"], "新文本");
+}
+
+// 批量操作收藏相关测试
+#[tokio::test]
+async fn test_batch_favorites() {
+    let app = init_test().await;
+    let category = app.create_category("批量", None).await;
+    let mut ids = Vec::new();
+    for i in 0..4 {
+        ids.push(app.create_favorite(&format!("批量{}", i), "https://example.com/batch").await);
+    }
+    let bob = app.issue_key("bob").await;
+    let (_, bobs) = app
+        .request(&bob, Method::POST, "/api/favorites", Some(json!({ "text": "bob", "url": "https://bob.org", "tags": [] })))
+        .await;
+    let bob_id = bobs["id"].as_i64().unwrap();
+
+    // 按ID选取，不存在的和其他用户的收藏标记为 not_found
+    let (status, body) = app
+        .post("/api/favorites/batch", json!({
+            "ids": [ids[0], ids[1], ids[1], bob_id, 999],
+            "action": "set_category",
+            "category_id": category
+        }))
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["succeeded"], 2);
+    assert_eq!(body["not_found"], 2);
+    let status_of = |id: i64| {
+        body["results"].as_array().unwrap().iter().find(|r| r["id"] == id).unwrap()["status"].clone()
+    };
+    assert_eq!(status_of(ids[0]), "ok");
+    assert_eq!(status_of(bob_id), "not_found");
+    assert_eq!(status_of(999), "not_found");
+    let (_, bobs) = app.request(&bob, Method::GET, &format!("/api/favorites/{}", bob_id), None).await;
+    assert!(bobs["category_id"].is_null());
+
+    // 按筛选条件选取
+    let (status, body) = app
+        .post("/api/favorites/batch", json!({
+            "filter": format!("category_id={}", category),
+            "action": "add_tags",
+            "tags": ["批量标签"]
+        }))
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["succeeded"], 2);
+    assert_eq!(body["not_found"], 0);
+    let (_, tags) = app.get("/api/tags").await;
+    let tag = id_by_name(&tags, "批量标签");
+    let (_, list) = app.get(&format!("/api/favorites?tags_all={}", tag)).await;
+    let mut tagged = item_ids(&list);
+    tagged.sort_unstable();
+    assert_eq!(tagged, vec![ids[0], ids[1]]);
+
+    // 选择器和数量校验
+    let too_many: Vec<i64> = (1..=1001).collect();
+    for request in [
+        json!({ "ids": too_many, "action": "archive" }),
+        json!({ "ids": [], "action": "archive" }),
+        json!({ "ids": [ids[0]], "filter": "q=x", "action": "archive" }),
+        json!({ "action": "archive" }),
+    ] {
+        let (status, body) = app.post("/api/favorites/batch", request).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(body["details"]["ids"].is_string(), "{}", body);
+    }
+    let (status, _) = app
+        .post("/api/favorites/batch", json!({ "ids": [ids[0]], "action": "set_category", "category_id": 999 }))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // 中途失败时整个事务回滚
+    sqlx::query(&format!(
+        "CREATE TRIGGER fail_archive BEFORE UPDATE OF archived_at ON favorites WHEN NEW.id = {} \
+         BEGIN SELECT RAISE(ABORT, 'boom'); END",
+        ids[2]
+    ))
+    .execute(&app.db)
+    .await
+    .unwrap();
+    let audit_before: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM audit_log").fetch_one(&app.db).await.unwrap();
+    let (status, _) = app
+        .post("/api/favorites/batch", json!({ "ids": [ids[0], ids[1], ids[2], ids[3]], "action": "archive" }))
+        .await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    let archived: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM favorites WHERE archived_at IS NOT NULL")
+        .fetch_one(&app.db)
+        .await
+        .unwrap();
+    assert_eq!(archived, 0);
+    let audit_after: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM audit_log").fetch_one(&app.db).await.unwrap();
+    assert_eq!(audit_after, audit_before);
+
+    sqlx::query("DROP TRIGGER fail_archive").execute(&app.db).await.unwrap();
+    let (status, body) = app
+        .post("/api/favorites/batch", json!({ "ids": [ids[0], ids[1], ids[2], ids[3]], "action": "archive" }))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["succeeded"], 4);
+    let (_, list) = app.get("/api/favorites?archived=true").await;
+    assert_eq!(list["items"].as_array().unwrap().len(), 4);
+}
+
+// 修改历史和版本恢复相关测试
+#[tokio::test]
+async fn test_history_and_revert() {
+    let app = init_test().await;
+    let first = app.create_category("第一", None).await;
+    let second = app.create_category("第二", None).await;
+    let (_, favorite) = app
+        .post("/api/favorites", json!({
+            "text": "第一版",
+            "url": "https://example.com/v",
+            "category_id": first,
+            "tags": ["a", "b"]
+        }))
+        .await;
+    let id = favorite["id"].as_i64().unwrap();
+    let (status, _) = app
+        .request(&app.admin_key, Method::PATCH, &format!("/api/favorites/{}", id), Some(json!({
+            "text": "第二版",
+            "category_id": second,
+            "tags": ["c"]
+        })))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // 恢复到第一版，内容、标签和分类一并恢复
+    let (status, reverted) = app.post(&format!("/api/favorites/{}/revert/1", id), json!({})).await;
+    assert_eq!(status, StatusCode::OK, "{}", reverted);
+    assert_eq!(reverted["text"], "第一版");
+    assert_eq!(reverted["category_id"], first);
+    assert_eq!(tag_names(&reverted), vec!["a", "b"]);
+    let (_, fetched) = app.get(&format!("/api/favorites/{}", id)).await;
+    assert_eq!(tag_names(&fetched), vec!["a", "b"]);
+
+    // 恢复本身写入第三个版本
+    let (status, history) = app.get(&format!("/api/favorites/{}/history", id)).await;
+    assert_eq!(status, StatusCode::OK);
+    let versions: Vec<(i64, &str)> = history
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| (entry["version"].as_i64().unwrap(), entry["action"].as_str().unwrap()))
+        .collect();
+    assert_eq!(versions, vec![(1, "create"), (2, "update"), (3, "revert")]);
+    assert_eq!(history[2]["before"]["text"], "第二版");
+    assert_eq!(history[2]["after"]["text"], "第一版");
+    assert_eq!(history[2]["actor_id"], history[0]["actor_id"]);
+
+    // 不存在的版本
+    let (status, _) = app.post(&format!("/api/favorites/{}/revert/99", id), json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = app.post("/api/favorites/999/revert/1", json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // 历史和恢复只对收藏的所有者可见
+    let bob = app.issue_key("bob").await;
+    let (status, _) = app
+        .request(&bob, Method::GET, &format!("/api/favorites/{}/history", id), None)
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = app
+        .request(&bob, Method::POST, &format!("/api/favorites/{}/revert/1", id), Some(json!({})))
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (_, fetched) = app.get(&format!("/api/favorites/{}", id)).await;
+    assert_eq!(fetched["text"], "第一版");
+
+    // 回收站中的收藏需先恢复
+    app.delete(&format!("/api/favorites/{}", id)).await;
+    let (status, _) = app.post(&format!("/api/favorites/{}/revert/2", id), json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, history) = app.get(&format!("/api/favorites/{}/history", id)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(history.as_array().unwrap().len(), 4);
+}
+
+// 按来源网页查看收藏相关测试
+#[tokio::test]
+async fn test_page_favorites_in_document_order() {
+    let app = init_test().await;
+    let url = "https://example.com/article";
+    let third = app.create_favorite("Third paragraph", url).await;
+    let lost = app.create_favorite("Removed from the page", url).await;
+    let first = app.create_favorite("First paragraph", &format!("{}?utm_source=feed", url)).await;
+    let second = app.create_favorite("second   PARAGRAPH", url).await;
+
+    let (_, pages) = app.get("/api/pages").await;
+    assert_eq!(pages["total"], 1);
+    let page = pages["items"][0]["id"].as_i64().unwrap();
+    assert_eq!(pages["items"][0]["favorite_count"], 4);
+
+    // 没有快照时按收藏时间排序
+    let (status, body) = app.get(&format!("/api/pages/{}/favorites", page)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["on_page_order"], false);
+    assert_eq!(item_ids(&body), vec![third, lost, first, second]);
+
+    sqlx::query(
+        "INSERT INTO snapshots (favorite_id, url, status, text, fetched_at)
+         VALUES (?, ?, 200, ?, '2024-01-01 00:00:00')"
+    )
+    .bind(third)
+    .bind(url)
+    .bind("First paragraph.\nSecond paragraph.\nThird paragraph.")
+    .execute(&app.db)
+    .await
+    .unwrap();
+
+    // 按正文中的位置排序，无法定位的排在最后
+    let (_, body) = app.get(&format!("/api/pages/{}/favorites", page)).await;
+    assert_eq!(body["on_page_order"], true);
+    assert_eq!(item_ids(&body), vec![first, second, third, lost]);
+
+    let bob = app.issue_key("bob").await;
+    let (status, _) = app
+        .request(&bob, Method::GET, &format!("/api/pages/{}/favorites", page), None)
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+
+/// 按分类筛选的收藏ID，按ID排序
+async fn category_favorite_ids(app: &TestApp, category: i64, include_descendants: bool) -> Vec<i64> {
+    let (_, list) = app
+        .get(&format!("/api/favorites?category_id={}&include_descendants={}", category, include_descendants))
+        .await;
+    let mut ids = item_ids(&list);
+    ids.sort_unstable();
+    ids
+}
+
+// 移动分类和按子树筛选相关测试
+#[tokio::test]
+async fn test_move_category() {
+    let app = init_test().await;
+    let root = app.create_category("根", None).await;
+    let child = app.create_category("子", Some(root)).await;
+    let grandchild = app.create_category("孙", Some(child)).await;
+    let sibling = app.create_category("旁支", None).await;
+    let mut favorites = Vec::new();
+    for (text, category) in [("根收藏", root), ("子收藏", child), ("孙收藏", grandchild), ("旁支收藏", sibling)] {
+        let (_, favorite) = app
+            .post("/api/favorites", json!({ "text": text, "url": "https://example.com", "category_id": category, "tags": [] }))
+            .await;
+        favorites.push(favorite["id"].as_i64().unwrap());
+    }
+    let move_uri = |id: i64| format!("/api/categories/{}/move", id);
+
+    assert_eq!(category_favorite_ids(&app, root, true).await, favorites[..3]);
+    assert_eq!(category_favorite_ids(&app, root, false).await, vec![favorites[0]]);
+
+    // 不能移到自身或子孙之下
+    for parent in [root, child, grandchild] {
+        let (status, body) = app.post(&move_uri(root), json!({ "parent_id": parent })).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(body["details"]["parent_id"].is_string(), "{}", body);
+    }
+    let (status, _) = app.post(&move_uri(root), json!({ "parent_id": 999 })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let bob = app.issue_key("bob").await;
+    let (status, _) = app
+        .request(&bob, Method::POST, &move_uri(child), Some(json!({ "parent_id": null })))
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // 移动后分类树和子树筛选随之变化
+    let (status, moved) = app.post(&move_uri(grandchild), json!({ "parent_id": sibling })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(moved["parent_id"], sibling);
+    let (_, tree) = app.get("/api/categories/tree").await;
+    let node = |id: i64| tree.as_array().unwrap().iter().find(|node| node["id"] == id).unwrap().clone();
+    assert_eq!(node(root)["children"][0]["id"], child);
+    assert_eq!(node(root)["children"][0]["children"], json!([]));
+    assert_eq!(node(sibling)["children"][0]["id"], grandchild);
+    assert_eq!(node(sibling)["children"][0]["favorite_count"], 1);
+    assert_eq!(category_favorite_ids(&app, root, true).await, favorites[..2]);
+    assert_eq!(category_favorite_ids(&app, sibling, true).await, vec![favorites[2], favorites[3]]);
+
+    // 移到顶级
+    let (status, _) = app.post(&move_uri(child), json!({ "parent_id": null })).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, tree) = app.get("/api/categories/tree").await;
+    assert_eq!(tree.as_array().unwrap().len(), 3);
+    assert_eq!(category_favorite_ids(&app, root, true).await, vec![favorites[0]]);
+}
+
+
+// 错误响应映射相关测试
+#[tokio::test]
+async fn test_error_responses() {
+    let app = init_test().await;
+
+    // 重名标签返回带错误码的 409
+    let (status, _) = app.post("/api/tags", json!({ "name": "重复" })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, body) = app.post("/api/tags", json!({ "name": " 重复 " })).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["code"], "CONFLICT");
+    assert!(body["error"].as_str().unwrap().contains("重复"), "{}", body);
+    assert!(body.get("details").is_none());
+    app.post("/api/tags", json!({ "name": "其他" })).await;
+    let (_, tags) = app.get("/api/tags").await;
+    let other = id_by_name(&tags, "其他");
+    let (status, body) = app.put(&format!("/api/tags/{}", other), json!({ "id": other, "name": "重复" })).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["code"], "CONFLICT");
+
+    // 不存在的分类
+    for (method, uri, body) in [
+        (Method::GET, "/api/categories/999", None),
+        (Method::PUT, "/api/categories/999", Some(json!({ "id": 999, "name": "x" }))),
+        (Method::DELETE, "/api/categories/999", None),
+        (Method::POST, "/api/categories/999/move", Some(json!({ "parent_id": null }))),
+    ] {
+        let (status, body) = app.request(&app.admin_key, method, uri, body).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{}", uri);
+        assert_eq!(body["code"], "NOT_FOUND");
+    }
+
+    // 已删除的标签按ID引用
+    let id = app.create_favorite("带标签", "https://example.com").await;
+    let (status, _) = app
+        .put(&format!("/api/favorites/{}", id), json!({ "text": "带标签", "url": "https://example.com", "tags": ["其他"] }))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, favorite) = app.get(&format!("/api/favorites/{}", id)).await;
+    assert_eq!(tag_names(&favorite), vec!["其他"]);
+    let (status, _) = app.delete(&format!("/api/tags/{}", other)).await;
+    assert_eq!(status, StatusCode::OK);
+    for (method, body) in [
+        (Method::GET, None),
+        (Method::PUT, Some(json!({ "id": other, "name": "复活" }))),
+        (Method::DELETE, None),
+    ] {
+        let (status, body) = app.request(&app.admin_key, method, &format!("/api/tags/{}", other), body).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "NOT_FOUND");
+    }
+    let (status, list) = app.get(&format!("/api/favorites?tag_id={}", other)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(list["items"], json!([]));
+    let (_, favorite) = app.get(&format!("/api/favorites/{}", id)).await;
+    assert!(tag_names(&favorite).is_empty());
+}
+
+
+// 请求体校验相关测试
+#[tokio::test]
+async fn test_validation_errors() {
+    let app = init_test().await;
+    let valid = json!({ "text": "文本", "url": "https://example.com", "tags": [] });
+    let with = |field: &str, value: Value| {
+        let mut body = valid.clone();
+        body[field] = value;
+        body
+    };
+    let too_many: Vec<String> = (0..21).map(|i| format!("标签{}", i)).collect();
+
+    for (body, field) in [
+        (with("text", json!("   ")), "text"),
+        (with("url", json!("javascript:alert(1)")), "url"),
+        (with("url", json!("ftp://example.com/file")), "url"),
+        (with("tags", json!(too_many)), "tags"),
+        (with("tags", json!(["长".repeat(51)])), "tags"),
+    ] {
+        let (status, response) = app.post("/api/favorites", body.clone()).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", body);
+        assert_eq!(response["code"], "VALIDATION_ERROR");
+        assert!(response["details"][field].is_string(), "{}", response);
+    }
+
+    // 恰好在上限内的请求可以通过
+    let at_limit: Vec<String> = (0..20).map(|i| format!("标签{}", i)).collect();
+    let (status, _) = app.post("/api/favorites", with("tags", json!(at_limit))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app
+        .post("/api/favorites", json!({ "text": "另一段", "url": "https://example.com", "tags": ["长".repeat(50)] }))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // 格式错误和类型不符的请求体同样返回 JSON 格式的 422
+    for body in [r#"{"text": "#, "not json", r#"{"text": 1, "url": "https://example.com", "tags": []}"#] {
+        let (status, response) = app.post_raw(&app.admin_key, "/api/favorites", body).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", body);
+        assert_eq!(response["code"], "VALIDATION_ERROR");
+        assert!(response["details"]["body"].is_string(), "{}", response);
+    }
+    let (_, list) = app.get("/api/favorites").await;
+    assert_eq!(list["total"], 2);
+}
+
+
+/// 导出内容中收藏的分类、文本、网址和排序后的标签，按网址排序
+async fn exported(app: &TestApp, key: &str) -> Vec<(Value, Value, Value, Vec<String>)> {
+    let (status, body) = app.request(key, Method::GET, "/api/export?format=json", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let mut items: Vec<_> = body
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| {
+            let mut tags: Vec<String> = serde_json::from_value(item["tags"].clone()).unwrap();
+            tags.sort_unstable();
+            (item["category"].clone(), item["text"].clone(), item["url"].clone(), tags)
+        })
+        .collect();
+    items.sort_by_key(|item| item.2.to_string());
+    items
+}
+
+// 导出后再导入相关测试
+#[tokio::test]
+async fn test_export_import_round_trip() {
+    let app = init_test().await;
+    let reading = app.create_category("阅读, 笔记", None).await;
+    for (text, url, category, tags) in [
+        ("第一行\n第二行, 带\"引号\"", "https://example.com/1", Some(reading), json!(["a", "b"])),
+        ("<b>未分类</b>", "https://example.com/2?q=1&x=2", None, json!([])),
+        ("另一条", "https://other.org/3", Some(reading), json!(["b"])),
+    ] {
+        let (status, _) = app
+            .post("/api/favorites", json!({ "text": text, "url": url, "category_id": category, "tags": tags }))
+            .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+    let original = exported(&app, &app.admin_key).await;
+    assert_eq!(original.len(), 3);
+
+    for format in ["json", "csv", "html"] {
+        let (status, body) = app
+            .request(&app.admin_key, Method::GET, &format!("/api/export?format={}", format), None)
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        let body = match body {
+            Value::String(text) => text,
+            value => value.to_string(),
+        };
+
+        let user = app.issue_key(&format!("{}-user", format)).await;
+        let (status, result) = app
+            .post_raw(&user, &format!("/api/import?format={}", format), &body)
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", result);
+        assert_eq!(exported(&app, &user).await, original, "{}", format);
+
+        // 再次导入时全部判为重复
+        let (_, result) = app
+            .post_raw(&user, &format!("/api/import?format={}", format), &body)
+            .await;
+        assert_eq!(exported(&app, &user).await.len(), 3, "{}", result);
+    }
+}
+
+
+/// 搜索结果中的收藏ID
+async fn search_ids(app: &TestApp, q: &str) -> Vec<i64> {
+    let (status, body) = app.get(&format!("/api/search?q={}", q)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    item_ids(&body)
+}
+
+// 笔记相关测试
+#[tokio::test]
+async fn test_notes() {
+    let app = init_test().await;
+    let id = app.create_favorite("带笔记的收藏", "https://example.com/note").await;
+    let other = app.create_favorite("另一条收藏", "https://example.com/other").await;
+    let notes = format!("/api/favorites/{}/notes", id);
+
+    // 创建笔记后可按笔记内容搜索到收藏
+    let (status, note) = app.post(&notes, json!({ "body": "serendipity **markdown**" })).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", note);
+    assert_eq!(note["favorite_id"], id);
+    let note_id = note["id"].as_i64().unwrap();
+    let (_, list) = app.get(&notes).await;
+    assert_eq!(list.as_array().unwrap().len(), 1);
+    let (_, favorite) = app.get(&format!("/api/favorites/{}", id)).await;
+    assert_eq!(favorite["note_count"], 1);
+    assert_eq!(favorite["has_notes"], true);
+    assert_eq!(search_ids(&app, "serendipity").await, vec![id]);
+    assert_eq!(search_ids(&app, "note:serendipity").await, vec![id]);
+    assert!(search_ids(&app, "text:serendipity").await.is_empty());
+
+    // 更新笔记后索引随之更新
+    let note_uri = format!("{}/{}", notes, note_id);
+    let (status, updated) = app.put(&note_uri, json!({ "body": "quixotic" })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["body"], "quixotic");
+    assert!(search_ids(&app, "serendipity").await.is_empty());
+    assert_eq!(search_ids(&app, "quixotic").await, vec![id]);
+
+    // 校验和笔记与收藏不匹配的情况
+    let (status, body) = app.put(&note_uri, json!({ "body": "  " })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body["details"]["body"].is_string());
+    let (status, _) = app
+        .put(&format!("/api/favorites/{}/notes/{}", other, note_id), json!({ "body": "错位" }))
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = app.post("/api/favorites/999/notes", json!({ "body": "不存在" })).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // 其他用户既不能访问笔记，也搜索不到
+    let bob = app.issue_key("bob").await;
+    for (method, uri, body) in [
+        (Method::GET, notes.clone(), None),
+        (Method::POST, notes.clone(), Some(json!({ "body": "bob" }))),
+        (Method::PUT, note_uri.clone(), Some(json!({ "body": "bob" }))),
+        (Method::DELETE, note_uri.clone(), None),
+    ] {
+        let (status, _) = app.request(&bob, method, &uri, body).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{}", uri);
+    }
+    let (_, body) = app.request(&bob, Method::GET, "/api/search?q=quixotic", None).await;
+    assert_eq!(body["total"], 0);
+    let (_, list) = app.get(&notes).await;
+    assert_eq!(list[0]["body"], "quixotic");
+
+    // 删除笔记
+    let (status, _) = app.delete(&note_uri).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app.delete(&note_uri).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (_, list) = app.get(&notes).await;
+    assert_eq!(list, json!([]));
+    assert!(search_ids(&app, "quixotic").await.is_empty());
+    let (_, favorite) = app.get(&format!("/api/favorites/{}", id)).await;
+    assert_eq!(favorite["note_count"], 0);
+    assert_eq!(favorite["has_notes"], false);
+}
+
+
+// 收藏列表的日期、域名和标签组合筛选相关测试
+#[tokio::test]
+async fn test_list_filters() {
+    let app = init_test().await;
+    let mut ids = Vec::new();
+    for (url, created_at, tags) in [
+        ("https://example.com/1", "2024-01-01T00:00:00Z", json!(["a"])),
+        ("https://a.example.com/2", "2024-01-31T23:59:59Z", json!(["a", "b"])),
+        ("https://notexample.com/3", "2024-02-01T00:00:00Z", json!(["b", "c"])),
+        ("https://example.com.evil.org/4", "2023-12-31T23:59:59Z", json!([])),
+        ("https://www.sub.EXAMPLE.com/5", "2024-01-15T12:00:00Z", json!(["c"])),
+    ] {
+        let (status, favorite) = app.post("/api/favorites", json!({ "text": url, "url": url, "tags": tags })).await;
+        assert_eq!(status, StatusCode::OK, "{}", favorite);
+        let id = favorite["id"].as_i64().unwrap();
+        sqlx::query("UPDATE favorites SET created_at = ? WHERE id = ?")
+            .bind(created_at)
+            .bind(id)
+            .execute(&app.db)
             .await
             .unwrap();
-        assert_eq!(response.status(), StatusCode::OK);
-        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
-        let result: favorite::FavoriteResponse = serde_json::from_slice(&body).unwrap();
-        assert!(result.items.len() <= 5);
+        ids.push(id);
+    }
+    let (_, tags) = app.get("/api/tags").await;
+    let (a, b, c) = (id_by_name(&tags, "a"), id_by_name(&tags, "b"), id_by_name(&tags, "c"));
+    // 日期上下限都包含当天
+    let cases: Vec<(String, Vec<usize>)> = vec![
+        ("from=2024-01-01&to=2024-01-31".into(), vec![1, 2, 5]),
+        ("from=2024-01-31&to=2024-01-31".into(), vec![2]),
+        ("from=2024-02-01".into(), vec![3]),
+        ("to=2023-12-31".into(), vec![4]),
+        ("from=2024-02-02".into(), vec![]),
+        // 域名匹配自身和子域名，不匹配仅以其结尾的其他域名
+        ("domain=example.com".into(), vec![1, 2, 5]),
+        ("domain=EXAMPLE.com.".into(), vec![1, 2, 5]),
+        ("domain=a.example.com".into(), vec![2]),
+        ("domain=notexample.com".into(), vec![3]),
+        ("domain=ample.com".into(), vec![]),
+        // 标签的全部、任一和排除
+        (format!("tags_all={},{}", a, b), vec![2]),
+        (format!("tags_all={},{}", a, a), vec![1, 2]),
+        (format!("tags_all={},{}", a, c), vec![]),
+        (format!("tags_any={},{}", a, c), vec![1, 2, 3, 5]),
+        (format!("exclude_tag={}", a), vec![3, 4, 5]),
+        (format!("exclude_tag={},{}", a, c), vec![4]),
+        (format!("tags_any={}&exclude_tag={}", b, c), vec![2]),
+        (format!("tags_all={}&tags_any={},{}", b, a, c), vec![2, 3]),
+        // 多个条件同时生效
+        (format!("domain=example.com&tags_any={}&to=2024-01-15", c), vec![5]),
+    ];
+    for (query, expected) in cases {
+        let (status, body) = app.get(&format!("/api/favorites?per_page=100&{}", query)).await;
+        assert_eq!(status, StatusCode::OK, "{}: {}", query, body);
+        // 按创建顺序编号，便于和预期对照
+        let mut found: Vec<usize> = item_ids(&body)
+            .iter()
+            .map(|id| ids.iter().position(|i| i == id).unwrap() + 1)
+            .collect();
+        found.sort_unstable();
+        assert_eq!(found, expected, "{}", query);
     }
-} 
\ No newline at end of file
+
+    // 日期格式错误
+    let (status, _) = app.get("/api/favorites?from=2024-13-01").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
\ No newline at end of file
@@ -0,0 +1,149 @@
+/// 选区不超过该字符数时整段作为 textStart，否则拆分为 textStart,textEnd
+const MAX_EXACT_CHARS: usize = 80;
+
+/// 长选区按词拆分时，起止各取的词数
+const RANGE_WORDS: usize = 5;
+
+/// 长选区没有足够的空格分词时（如中文），起止各取的字符数
+const RANGE_CHARS: usize = 20;
+
+/// 前后文各取的词数，用于消除页面中相同文本的歧义
+const CONTEXT_WORDS: usize = 3;
+
+/// 前后文没有足够的空格分词时，各取的字符数
+const CONTEXT_CHARS: usize = 10;
+
+/// 按 Text Fragments 规范编码：除字母、数字和 `._~` 外一律百分号编码，
+/// `-`、`,`、`&` 在指令中有特殊含义，必须编码
+fn encode(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'.' | b'_' | b'~' => out.push(byte as char),
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+/// 取前 n 个字符
+fn head(text: &str, n: usize) -> String {
+    text.chars().take(n).collect::<String>().trim_end().to_string()
+}
+
+/// 取后 n 个字符
+fn tail(text: &str, n: usize) -> String {
+    let count = text.chars().count();
+    text.chars().skip(count.saturating_sub(n)).collect::<String>().trim_start().to_string()
+}
+
+/// 取选区前文末尾的几个词作为 prefix
+fn context_prefix(context: &str) -> Option<String> {
+    let words: Vec<&str> = context.split_whitespace().collect();
+    let prefix = words[words.len().saturating_sub(CONTEXT_WORDS)..].join(" ");
+    let prefix = if words.len() < CONTEXT_WORDS { tail(&prefix, CONTEXT_CHARS) } else { prefix };
+    Some(prefix).filter(|p| !p.is_empty())
+}
+
+/// 取选区后文开头的几个词作为 suffix
+fn context_suffix(context: &str) -> Option<String> {
+    let words: Vec<&str> = context.split_whitespace().collect();
+    let suffix = words[..words.len().min(CONTEXT_WORDS)].join(" ");
+    let suffix = if words.len() < CONTEXT_WORDS { head(&suffix, CONTEXT_CHARS) } else { suffix };
+    Some(suffix).filter(|s| !s.is_empty())
+}
+
+/// 根据选中的文本及其前后文生成 `text=[prefix-,]start[,end][,-suffix]` 指令
+fn text_directive(text: &str, before: Option<&str>, after: Option<&str>) -> Option<String> {
+    // 浏览器匹配时会折叠空白，这里保持一致
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if words.is_empty() {
+        return None;
+    }
+    let text = words.join(" ");
+
+    let mut parts = Vec::new();
+    if let Some(prefix) = before.and_then(context_prefix) {
+        parts.push(format!("{}-", encode(&prefix)));
+    }
+
+    if text.chars().count() <= MAX_EXACT_CHARS {
+        parts.push(encode(&text));
+    } else if words.len() >= RANGE_WORDS * 2 {
+        parts.push(encode(&words[..RANGE_WORDS].join(" ")));
+        parts.push(encode(&words[words.len() - RANGE_WORDS..].join(" ")));
+    } else {
+        parts.push(encode(&head(&text, RANGE_CHARS)));
+        parts.push(encode(&tail(&text, RANGE_CHARS)));
+    }
+
+    if let Some(suffix) = after.and_then(context_suffix) {
+        parts.push(format!("-{}", encode(&suffix)));
+    }
+
+    Some(format!("text={}", parts.join(",")))
+}
+
+/// 生成定位到原文段落的链接，如 `https://example.com/#:~:text=foo`
+/// 提供选区前后文时会加上 prefix/suffix，已有的文本片段指令会被替换，普通锚点予以保留
+pub fn deep_link(url: &str, text: &str, before: Option<&str>, after: Option<&str>) -> String {
+    let url = url.trim();
+    let base = url.split(":~:").next().unwrap_or_default();
+
+    let Some(directive) = text_directive(text, before, after) else {
+        return base.to_string();
+    };
+
+    if base.contains('#') {
+        format!("{}:~:{}", base, directive)
+    } else {
+        format!("{}#:~:{}", base, directive)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode("a-b, c&d"), "a%2Db%2C%20c%26d");
+        assert_eq!(encode("A.b_c~1"), "A.b_c~1");
+        assert_eq!(encode("中"), "%E4%B8%AD");
+    }
+
+    #[test]
+    fn test_deep_link() {
+        assert_eq!(
+            deep_link("https://example.com/a", "Hello   world", None, None),
+            "https://example.com/a#:~:text=Hello%20world"
+        );
+        assert_eq!(
+            deep_link("https://example.com/a", "Hello world", Some("one two three four"), Some("five six seven eight")),
+            "https://example.com/a#:~:text=two%20three%20four-,Hello%20world,-five%20six%20seven"
+        );
+        // 保留普通锚点，替换已有的文本片段指令
+        assert_eq!(deep_link("https://example.com/a#sec", "x", None, None), "https://example.com/a#sec:~:text=x");
+        assert_eq!(deep_link("https://example.com/a#:~:text=old", "x", None, None), "https://example.com/a#:~:text=x");
+        // 没有可定位的文本
+        assert_eq!(deep_link(" https://example.com/a ", "  ", None, None), "https://example.com/a");
+    }
+
+    #[test]
+    fn test_long_selection() {
+        let text = (1..=20).map(|i| format!("word{}", i)).collect::<Vec<_>>().join(" ");
+        assert_eq!(
+            deep_link("https://example.com/", &text, None, None),
+            "https://example.com/#:~:text=word1%20word2%20word3%20word4%20word5,word16%20word17%20word18%20word19%20word20"
+        );
+
+        // 中文没有空格分词，按字符截取，前后文同样按字符截取
+        let text = "一二三四五六七八九十".repeat(9);
+        let link = deep_link("https://example.com/", &text, Some("这是前文的很长一段内容"), None);
+        let start = encode("一二三四五六七八九十一二三四五六七八九十");
+        assert_eq!(
+            link,
+            format!("https://example.com/#:~:text={}-,{},{}", encode("是前文的很长一段内容"), start, start)
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Query},
+    http::request::Parts,
+};
+use chrono::{DateTime, Local, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::Deserialize;
+use crate::error::AppError;
+
+/// 指定时区的请求头
+const TIMEZONE_HEADER: &str = "x-timezone";
+
+/// 旧版本按服务器本地时间保存的时间格式
+const LEGACY_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// 当前 UTC 时间，RFC 3339 格式，精确到秒
+pub fn now() -> String {
+    to_storage(Utc::now())
+}
+
+/// 转为保存到数据库的 UTC RFC 3339 字符串
+pub fn to_storage<T: TimeZone>(time: DateTime<T>) -> String {
+    time.with_timezone(&Utc).to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// 将旧版本的服务器本地时间转为 UTC，无法识别时返回 None
+pub fn from_legacy(value: &str) -> Option<String> {
+    NaiveDateTime::parse_from_str(value.trim(), LEGACY_FORMAT)
+        .ok()?
+        .and_local_timezone(Local)
+        .earliest()
+        .map(to_storage)
+}
+
+/// 调用方指定的时区，通过 `tz` 查询参数或 `X-Timezone` 请求头传入 IANA 时区名
+/// 未指定时按 UTC 返回时间
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ClientTimezone(pub Option<Tz>);
+
+impl ClientTimezone {
+    /// 将保存的 UTC 时间转为调用方时区，带时区偏移
+    pub fn render(&self, value: &str) -> String {
+        let Some(tz) = self.0 else {
+            return value.to_string();
+        };
+        match DateTime::parse_from_rfc3339(value) {
+            Ok(time) => time.with_timezone(&tz).to_rfc3339_opts(SecondsFormat::Secs, false),
+            Err(_) => value.to_string(),
+        }
+    }
+
+    /// 原地转换时间字段
+    pub fn apply(&self, value: &mut String) {
+        *value = self.render(value);
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ClientTimezone
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        #[derive(Deserialize)]
+        struct TimezoneQuery {
+            tz: Option<String>,
+        }
+
+        // 查询参数优先于请求头
+        let name = Query::<TimezoneQuery>::try_from_uri(&parts.uri)
+            .ok()
+            .and_then(|Query(query)| query.tz)
+            .or_else(|| {
+                parts
+                    .headers
+                    .get(TIMEZONE_HEADER)
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string)
+            });
+
+        match name.as_deref().map(str::trim) {
+            None | Some("") => Ok(ClientTimezone(None)),
+            Some(name) => name
+                .parse::<Tz>()
+                .map(|tz| ClientTimezone(Some(tz)))
+                .map_err(|_| AppError::field("tz", format!("未知的时区：{}", name))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request;
+
+    async fn extract(uri: &str, header: Option<&str>) -> Result<ClientTimezone, AppError> {
+        let mut request = Request::builder().uri(uri);
+        if let Some(header) = header {
+            request = request.header(TIMEZONE_HEADER, header);
+        }
+        let (mut parts, _) = request.body(()).unwrap().into_parts();
+        ClientTimezone::from_request_parts(&mut parts, &()).await
+    }
+
+    #[test]
+    fn test_to_storage() {
+        let time = DateTime::parse_from_rfc3339("2024-03-01T08:30:15.250+08:00").unwrap();
+        assert_eq!(to_storage(time), "2024-03-01T00:30:15Z");
+    }
+
+    #[test]
+    fn test_from_legacy() {
+        let value = from_legacy(" 2024-03-01 08:30:15 ").unwrap();
+        let time = DateTime::parse_from_rfc3339(&value).unwrap();
+        let local = Local.from_local_datetime(&NaiveDateTime::parse_from_str("2024-03-01 08:30:15", LEGACY_FORMAT).unwrap());
+        assert_eq!(time, local.earliest().unwrap());
+        assert!(value.ends_with('Z'));
+
+        assert_eq!(from_legacy("2024-03-01T08:30:15Z"), None);
+        assert_eq!(from_legacy(""), None);
+    }
+
+    #[test]
+    fn test_render() {
+        let tz = ClientTimezone(Some(chrono_tz::Asia::Shanghai));
+        assert_eq!(tz.render("2024-03-01T00:30:15Z"), "2024-03-01T08:30:15+08:00");
+        // 夏令时按当时的偏移
+        let tz = ClientTimezone(Some(chrono_tz::America::New_York));
+        assert_eq!(tz.render("2024-07-01T12:00:00Z"), "2024-07-01T08:00:00-04:00");
+        assert_eq!(tz.render("2024-01-01T12:00:00Z"), "2024-01-01T07:00:00-05:00");
+        // 无法解析时原样返回
+        assert_eq!(tz.render("not a time"), "not a time");
+        assert_eq!(ClientTimezone(None).render("2024-03-01T00:30:15Z"), "2024-03-01T00:30:15Z");
+    }
+
+    #[tokio::test]
+    async fn test_extract() {
+        assert_eq!(extract("/", None).await.unwrap().0, None);
+        assert_eq!(extract("/?tz=", Some(" ")).await.unwrap().0, None);
+        assert_eq!(extract("/", Some("Asia/Tokyo")).await.unwrap().0, Some(chrono_tz::Asia::Tokyo));
+        // 查询参数优先于请求头
+        assert_eq!(
+            extract("/?tz=Europe/Berlin", Some("Asia/Tokyo")).await.unwrap().0,
+            Some(chrono_tz::Europe::Berlin)
+        );
+        assert!(extract("/?tz=Mars/Olympus", None).await.is_err());
+    }
+}
@@ -0,0 +1,86 @@
+use axum::{
+    async_trait,
+    extract::{rejection::JsonRejection, FromRequest, Request},
+    Json,
+};
+use serde::de::DeserializeOwned;
+use std::borrow::Cow;
+use validator::{Validate, ValidationError};
+use crate::error::AppError;
+
+/// 收藏地址允许的协议
+const ALLOWED_URL_SCHEMES: &[&str] = &["http", "https"];
+
+/// 单个收藏最多的标签数量
+const MAX_TAGS: usize = 20;
+
+/// 标签名称的最大长度
+const MAX_TAG_CHARS: usize = 50;
+
+/// 反序列化后按 `Validate` 规则校验的 JSON 请求体
+/// 校验失败返回 422，并按字段列出错误提示
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidatedJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(|rejection| match rejection {
+                // 格式错误、字段缺失或类型不符同样按校验错误返回
+                JsonRejection::JsonSyntaxError(err) => AppError::field("body", err.body_text()),
+                JsonRejection::JsonDataError(err) => AppError::field("body", err.body_text()),
+                rejection => AppError::BadRequest(rejection.body_text()),
+            })?;
+        value.validate()?;
+        Ok(ValidatedJson(value))
+    }
+}
+
+/// 构造带提示的校验错误
+pub(crate) fn error(code: &'static str, message: impl Into<Cow<'static, str>>) -> ValidationError {
+    let mut error = ValidationError::new(code);
+    error.message = Some(message.into());
+    error
+}
+
+/// 去除首尾空白后不能为空
+pub fn not_blank(value: &str) -> Result<(), ValidationError> {
+    if value.trim().is_empty() {
+        return Err(error("blank", "不能为空"));
+    }
+    Ok(())
+}
+
+/// 必须是带主机名的 http 或 https 地址
+pub fn http_url(value: &str) -> Result<(), ValidationError> {
+    let Some((scheme, rest)) = value.trim().split_once("://") else {
+        return Err(error("url", "不是有效的网址"));
+    };
+    if !ALLOWED_URL_SCHEMES.contains(&scheme.to_ascii_lowercase().as_str()) {
+        return Err(error("url_scheme", "只支持 http 和 https 地址"));
+    }
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    if host.is_empty() || host.contains(char::is_whitespace) {
+        return Err(error("url", "不是有效的网址"));
+    }
+    Ok(())
+}
+
+/// 标签数量及每个标签名称的长度
+pub fn tag_list(tags: &[String]) -> Result<(), ValidationError> {
+    if tags.len() > MAX_TAGS {
+        return Err(error("tags_count", format!("标签不能超过 {} 个", MAX_TAGS)));
+    }
+    if tags.iter().any(|tag| tag.trim().chars().count() > MAX_TAG_CHARS) {
+        return Err(error("tag_length", format!("标签名称不能超过 {} 个字符", MAX_TAG_CHARS)));
+    }
+    Ok(())
+}